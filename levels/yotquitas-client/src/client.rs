@@ -0,0 +1,498 @@
+//! HTTP client for the node's JSON-RPC API
+
+use crate::error::{ClientError, Result};
+use crate::types::{
+    to_hex, to_quantity, Account, Block, BlockNumber, FeeEstimate, FeeHistory, PooledTransaction,
+    Receipt, Simulation, Supply, TransactionInfo, Txpool, TxpoolStatus,
+};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use yotquitas_core::{Address, Hash, Transaction};
+
+/// Interval between receipt polls in [`Client::wait_for_receipt`]
+pub const RECEIPT_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// How failed requests are retried
+///
+/// Only transient failures are retried (see [`ClientError::is_transient`]);
+/// calls the node rejects are returned at once.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt
+    pub max_retries: u32,
+    /// Delay before the first retry, doubled after each further attempt
+    pub initial_backoff: Duration,
+    /// Longest delay between attempts
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(2),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Never retry
+    pub fn none() -> Self {
+        Self {
+            max_retries: 0,
+            ..Self::default()
+        }
+    }
+
+    /// Delay before retry number `attempt`, counting from 0
+    fn backoff(&self, attempt: u32) -> Duration {
+        self.initial_backoff
+            .saturating_mul(1u32.checked_shl(attempt).unwrap_or(u32::MAX))
+            .min(self.max_backoff)
+    }
+}
+
+/// Error object of a JSON-RPC response
+#[derive(Debug, Deserialize)]
+pub(crate) struct RpcError {
+    code: i64,
+    message: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct RpcResponse {
+    result: Option<serde_json::Value>,
+    error: Option<RpcError>,
+}
+
+/// Typed client of one node's JSON-RPC endpoint
+///
+/// Clones share the underlying connection pool.
+#[derive(Debug, Clone)]
+pub struct Client {
+    url: String,
+    http: reqwest::Client,
+    retry: RetryPolicy,
+    next_id: Arc<AtomicU64>,
+}
+
+impl Client {
+    /// Client of the node serving JSON-RPC at `url`, e.g. `http://127.0.0.1:8545`
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            http: reqwest::Client::new(),
+            retry: RetryPolicy::default(),
+            next_id: Arc::new(AtomicU64::new(1)),
+        }
+    }
+
+    /// Use `retry` for failed requests
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Endpoint the client talks to
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Call `method` with positional `params` and decode its result, retrying
+    /// transient failures
+    pub async fn request<T: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: serde_json::Value,
+    ) -> Result<T> {
+        let mut attempt = 0;
+        loop {
+            match self.request_once(method, &params).await {
+                Err(e) if e.is_transient() && attempt < self.retry.max_retries => {
+                    tokio::time::sleep(self.retry.backoff(attempt)).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    async fn request_once<T: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: &serde_json::Value,
+    ) -> Result<T> {
+        let request = serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": self.next_id.fetch_add(1, Ordering::Relaxed),
+            "method": method,
+            "params": params,
+        });
+        let response: RpcResponse = self
+            .http
+            .post(&self.url)
+            .json(&request)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        decode_response(method, response.result, response.error)
+    }
+
+    /// Chain ID of the genesis the node was started from
+    pub async fn chain_id(&self) -> Result<u64> {
+        self.quantity("eth_chainId", serde_json::json!([])).await
+    }
+
+    /// Network ID of the node, as returned by `net_version`
+    pub async fn network_id(&self) -> Result<u64> {
+        let version: String = self.request("net_version", serde_json::json!([])).await?;
+        version.parse().map_err(|_| ClientError::InvalidResponse {
+            method: "net_version".to_string(),
+            message: format!("{:?} is not a network ID", version),
+        })
+    }
+
+    /// Software version of the node
+    pub async fn client_version(&self) -> Result<String> {
+        self.request("web3_clientVersion", serde_json::json!([]))
+            .await
+    }
+
+    /// Height of the chain head
+    pub async fn block_number(&self) -> Result<u64> {
+        self.quantity("eth_blockNumber", serde_json::json!([]))
+            .await
+    }
+
+    /// Block by number, with full transaction bodies if `full` is set
+    pub async fn block_by_number(&self, number: BlockNumber, full: bool) -> Result<Option<Block>> {
+        self.request(
+            "eth_getBlockByNumber",
+            serde_json::json!([number.to_param(), full]),
+        )
+        .await
+    }
+
+    /// Block by hash, with full transaction bodies if `full` is set
+    pub async fn block_by_hash(&self, hash: &Hash, full: bool) -> Result<Option<Block>> {
+        self.request(
+            "eth_getBlockByHash",
+            serde_json::json!([to_hex(hash), full]),
+        )
+        .await
+    }
+
+    /// Committed transaction by hash
+    pub async fn transaction(&self, hash: &Hash) -> Result<Option<TransactionInfo>> {
+        self.request(
+            "eth_getTransactionByHash",
+            serde_json::json!([to_hex(hash)]),
+        )
+        .await
+    }
+
+    /// Receipt of a committed transaction
+    pub async fn receipt(&self, hash: &Hash) -> Result<Option<Receipt>> {
+        self.request(
+            "eth_getTransactionReceipt",
+            serde_json::json!([to_hex(hash)]),
+        )
+        .await
+    }
+
+    /// Balance of an account
+    pub async fn balance(&self, address: &Address) -> Result<u64> {
+        self.quantity(
+            "aequitas_getAccountBalance",
+            serde_json::json!([to_hex(address)]),
+        )
+        .await
+    }
+
+    /// Balance, vesting lock and nonce of an account
+    pub async fn account(&self, address: &Address) -> Result<Account> {
+        self.request("aequitas_getAccount", serde_json::json!([to_hex(address)]))
+            .await
+    }
+
+    /// Minted, burned and total supply
+    pub async fn supply(&self) -> Result<Supply> {
+        self.request("aequitas_getSupply", serde_json::json!([]))
+            .await
+    }
+
+    /// Nonce of an account in committed state
+    pub async fn transaction_count(&self, address: &Address) -> Result<u64> {
+        self.quantity(
+            "eth_getTransactionCount",
+            serde_json::json!([to_hex(address), "latest"]),
+        )
+        .await
+    }
+
+    /// Pooled transactions sent by an account
+    pub async fn pending_transactions(&self, address: &Address) -> Result<Vec<TransactionInfo>> {
+        self.request(
+            "aequitas_getPendingTransactions",
+            serde_json::json!([to_hex(address)]),
+        )
+        .await
+    }
+
+    /// Pooled transaction by hash
+    pub async fn pending_transaction(&self, hash: &Hash) -> Result<Option<PooledTransaction>> {
+        self.request(
+            "aequitas_getPendingTransaction",
+            serde_json::json!([to_hex(hash)]),
+        )
+        .await
+    }
+
+    /// Nonce to use for the next transaction from `address`, counting the
+    /// sender's pooled transactions up to the first nonce gap
+    pub async fn next_nonce(&self, address: &Address) -> Result<u64> {
+        let confirmed = self.transaction_count(address).await?;
+        let mut nonces: Vec<u64> = self
+            .pending_transactions(address)
+            .await?
+            .iter()
+            .map(|tx| tx.nonce)
+            .collect();
+        nonces.sort_unstable();
+        Ok(nonces.into_iter().fold(
+            confirmed,
+            |next, nonce| if nonce == next { next + 1 } else { next },
+        ))
+    }
+
+    /// Number of pending and queued transactions in the pool
+    pub async fn txpool_status(&self) -> Result<TxpoolStatus> {
+        self.request("txpool_status", serde_json::json!([])).await
+    }
+
+    /// All pooled transactions
+    pub async fn txpool_content(&self) -> Result<Txpool<TransactionInfo>> {
+        self.request("txpool_content", serde_json::json!([])).await
+    }
+
+    /// One-line summaries of all pooled transactions
+    pub async fn txpool_inspect(&self) -> Result<Txpool<String>> {
+        self.request("txpool_inspect", serde_json::json!([])).await
+    }
+
+    /// Submit a signed transaction and return its hash
+    ///
+    /// Submitting a transaction the node already has succeeds. Transient
+    /// failures are not retried, since the transaction may have been accepted
+    /// before the response was lost.
+    pub async fn send_transaction(&self, tx: &Transaction) -> Result<Hash> {
+        let method = "eth_sendRawTransaction";
+        let params = serde_json::json!([encode_raw_transaction(tx)]);
+        match self.request_once::<String>(method, &params).await {
+            Ok(hash) => parse_result(method, crate::types::parse_hash(&hash)),
+            Err(e) if e.is_already_known() => Ok(tx.hash()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Whether the node has `hash` in its pool or on chain
+    pub async fn is_known(&self, hash: &Hash) -> Result<bool> {
+        Ok(self.pending_transaction(hash).await?.is_some()
+            || self.transaction(hash).await?.is_some())
+    }
+
+    /// Execute a transaction against the chain head without committing it
+    ///
+    /// The signature is not required.
+    pub async fn simulate_transaction(&self, tx: &Transaction) -> Result<Simulation> {
+        self.request("aequitas_simulateTransaction", serde_json::json!([tx]))
+            .await
+    }
+
+    /// Execute a transaction against the chain head and return its output
+    pub async fn call(&self, tx: &Transaction) -> Result<Vec<u8>> {
+        let output: String = self.request("eth_call", serde_json::json!([tx])).await?;
+        parse_result("eth_call", crate::types::parse_bytes(&output))
+    }
+
+    /// Gas a transaction would use
+    pub async fn estimate_gas(&self, tx: &Transaction) -> Result<u64> {
+        self.quantity("eth_estimateGas", serde_json::json!([tx]))
+            .await
+    }
+
+    /// Gas, recommended fees and expected cost of a transaction
+    pub async fn estimate_fee(&self, tx: &Transaction) -> Result<FeeEstimate> {
+        self.request("aequitas_estimateFee", serde_json::json!([tx]))
+            .await
+    }
+
+    /// Recommended price per unit of gas, base fee plus tip
+    pub async fn gas_price(&self) -> Result<u64> {
+        self.quantity("eth_gasPrice", serde_json::json!([])).await
+    }
+
+    /// Recommended tip per unit of gas
+    pub async fn max_priority_fee_per_gas(&self) -> Result<u64> {
+        self.quantity("eth_maxPriorityFeePerGas", serde_json::json!([]))
+            .await
+    }
+
+    /// Base fees, gas usage and tips at `reward_percentiles` of up to
+    /// `block_count` blocks ending at `newest_block`
+    pub async fn fee_history(
+        &self,
+        block_count: u64,
+        newest_block: BlockNumber,
+        reward_percentiles: &[f64],
+    ) -> Result<FeeHistory> {
+        self.request(
+            "eth_feeHistory",
+            serde_json::json!([
+                to_quantity(block_count),
+                newest_block.to_param(),
+                reward_percentiles
+            ]),
+        )
+        .await
+    }
+
+    /// Poll for the receipt of `hash` until it appears or `timeout` passes
+    pub async fn wait_for_receipt(&self, hash: &Hash, timeout: Duration) -> Result<Receipt> {
+        let deadline = tokio::time::Instant::now() + timeout;
+        loop {
+            if let Some(receipt) = self.receipt(hash).await? {
+                return Ok(receipt);
+            }
+            if tokio::time::Instant::now() >= deadline {
+                return Err(ClientError::ReceiptTimeout {
+                    hash: *hash,
+                    timeout,
+                });
+            }
+            tokio::time::sleep(RECEIPT_POLL_INTERVAL).await;
+        }
+    }
+
+    /// Call a method whose result is a hex quantity
+    async fn quantity(&self, method: &str, params: serde_json::Value) -> Result<u64> {
+        let quantity: String = self.request(method, params).await?;
+        parse_result(method, crate::types::parse_quantity(&quantity))
+    }
+}
+
+/// Encode a signed transaction as `eth_sendRawTransaction` expects it:
+/// hex-encoded JSON
+pub fn encode_raw_transaction(tx: &Transaction) -> String {
+    to_hex(&serde_json::to_vec(tx).expect("transactions serialize to JSON"))
+}
+
+/// Turn a JSON-RPC result or error into the decoded result
+pub(crate) fn decode_response<T: DeserializeOwned>(
+    method: &str,
+    result: Option<serde_json::Value>,
+    error: Option<RpcError>,
+) -> Result<T> {
+    if let Some(error) = error {
+        return Err(ClientError::Rpc {
+            method: method.to_string(),
+            code: error.code,
+            message: error.message,
+        });
+    }
+    serde_json::from_value(result.unwrap_or(serde_json::Value::Null)).map_err(|e| {
+        ClientError::InvalidResponse {
+            method: method.to_string(),
+            message: e.to_string(),
+        }
+    })
+}
+
+fn parse_result<T>(method: &str, result: std::result::Result<T, String>) -> Result<T> {
+    result.map_err(|message| ClientError::InvalidResponse {
+        method: method.to_string(),
+        message,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_doubles_up_to_the_cap() {
+        let retry = RetryPolicy {
+            max_retries: 10,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(1),
+        };
+        assert_eq!(retry.backoff(0), Duration::from_millis(100));
+        assert_eq!(retry.backoff(2), Duration::from_millis(400));
+        assert_eq!(retry.backoff(4), Duration::from_secs(1));
+        assert_eq!(retry.backoff(40), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_raw_transaction_round_trip() {
+        let (signing_key, pubkey) = yotquitas_core::generate_keypair();
+        let tx = Transaction::new(
+            pubkey,
+            yotquitas_core::TransactionPayload::Transfer {
+                to: [1u8; 32],
+                amount: 5,
+            },
+            21_000,
+            2,
+            1,
+            0,
+        )
+        .sign(&signing_key);
+
+        let raw = encode_raw_transaction(&tx);
+        let bytes = hex::decode(raw.trim_start_matches("0x")).unwrap();
+        let decoded: Transaction = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(decoded, tx);
+        assert!(decoded.verify());
+    }
+
+    #[test]
+    fn test_decode_response() {
+        let ok: Result<u64> = decode_response("m", Some(serde_json::json!(5)), None);
+        assert_eq!(ok.unwrap(), 5);
+
+        let rejected: Result<u64> = decode_response(
+            "eth_sendRawTransaction",
+            None,
+            Some(RpcError {
+                code: -32000,
+                message: "Nonce too low: expected at least 2, got 1".to_string(),
+            }),
+        );
+        let err = rejected.unwrap_err();
+        assert_eq!(err.rpc_code(), Some(-32000));
+        assert!(err.is_nonce_error());
+        assert!(!err.is_transient());
+        assert!(!err.is_already_known());
+
+        let known: Result<u64> = decode_response(
+            "eth_sendRawTransaction",
+            None,
+            Some(RpcError {
+                code: -32000,
+                message: "Transaction already known".to_string(),
+            }),
+        );
+        let err = known.unwrap_err();
+        assert!(err.is_already_known());
+        assert!(!err.is_nonce_error());
+
+        let invalid: Result<u64> = decode_response("m", Some(serde_json::json!("x")), None);
+        assert!(matches!(invalid, Err(ClientError::InvalidResponse { .. })));
+    }
+}
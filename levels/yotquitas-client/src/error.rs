@@ -0,0 +1,71 @@
+//! Errors returned by the client
+
+use std::time::Duration;
+
+/// Result type of client operations
+pub type Result<T> = std::result::Result<T, ClientError>;
+
+/// Error returned by a client operation
+#[derive(Debug, thiserror::Error)]
+pub enum ClientError {
+    /// The node could not be reached or returned an HTTP error
+    #[error("transport error: {0}")]
+    Transport(#[from] reqwest::Error),
+
+    /// The WebSocket connection failed or was closed
+    #[error("websocket error: {0}")]
+    WebSocket(String),
+
+    /// The node rejected the call
+    #[error("{method} failed ({code}): {message}")]
+    Rpc {
+        method: String,
+        code: i64,
+        message: String,
+    },
+
+    /// The node's response did not have the expected shape
+    #[error("invalid response to {method}: {message}")]
+    InvalidResponse { method: String, message: String },
+
+    /// A transaction was not included in time
+    #[error("no receipt for 0x{} after {:?}", hex::encode(.hash), .timeout)]
+    ReceiptTimeout {
+        hash: yotquitas_core::Hash,
+        timeout: Duration,
+    },
+}
+
+impl ClientError {
+    /// JSON-RPC error code of a call the node rejected
+    pub fn rpc_code(&self) -> Option<i64> {
+        match self {
+            ClientError::Rpc { code, .. } => Some(*code),
+            _ => None,
+        }
+    }
+
+    /// Whether retrying the same request may succeed: connection failures,
+    /// timeouts, rate limiting and server-side HTTP errors
+    pub fn is_transient(&self) -> bool {
+        match self {
+            ClientError::Transport(e) => {
+                e.is_connect()
+                    || e.is_timeout()
+                    || e.status()
+                        .is_some_and(|status| status.is_server_error() || status.as_u16() == 429)
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether the node rejected a transaction for its nonce
+    pub fn is_nonce_error(&self) -> bool {
+        matches!(self, ClientError::Rpc { message, .. } if message.to_lowercase().contains("nonce"))
+    }
+
+    /// Whether the node rejected a transaction because it already has it
+    pub fn is_already_known(&self) -> bool {
+        matches!(self, ClientError::Rpc { message, .. } if message.to_lowercase().contains("already known"))
+    }
+}
@@ -0,0 +1,17 @@
+//! Yotquitas Client Library
+//!
+//! Typed async client of the Yotquitas node's JSON-RPC API, built on the
+//! types of `yotquitas-core`. [`Client`] covers every HTTP method and retries
+//! transient failures, [`Signer`] manages nonces and fees for one account,
+//! and [`WsClient`] streams `eth_subscribe` notifications.
+
+mod client;
+mod error;
+mod signer;
+pub mod types;
+mod ws;
+
+pub use client::{encode_raw_transaction, Client, RetryPolicy, RECEIPT_POLL_INTERVAL};
+pub use error::{ClientError, Result};
+pub use signer::{with_margin, Signer, TxOptions, GAS_LIMIT_MARGIN_PERCENT};
+pub use ws::{Subscription, WsClient};
@@ -0,0 +1,199 @@
+//! Signing and sending transactions from one account
+
+use crate::client::Client;
+use crate::error::{ClientError, Result};
+use crate::types::Receipt;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use yotquitas_core::{
+    public_key_address, Address, Hash, SigningKey, Transaction, TransactionPayload,
+};
+
+/// Headroom added to estimated gas, in percent, in case state changes
+/// between estimation and inclusion
+pub const GAS_LIMIT_MARGIN_PERCENT: u64 = 20;
+
+/// Values to use instead of those fetched from the node
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TxOptions {
+    pub nonce: Option<u64>,
+    pub gas_limit: Option<u64>,
+    pub max_fee_per_gas: Option<u64>,
+    pub max_priority_fee_per_gas: Option<u64>,
+}
+
+/// Sends transactions signed by one key, assigning nonces and fees
+///
+/// Nonces are handed out locally after the first is fetched from the node,
+/// so several transactions can be sent without waiting for each to be
+/// included. When the node rejects a nonce the signer resynchronizes with
+/// the node and signs the payload again once, unless the rejected
+/// transaction turns out to be pooled or on chain already.
+pub struct Signer {
+    client: Client,
+    signing_key: SigningKey,
+    address: Address,
+    /// Nonce of the next transaction, once known
+    next_nonce: Mutex<Option<u64>>,
+}
+
+impl Signer {
+    pub fn new(client: Client, signing_key: SigningKey) -> Self {
+        let address = public_key_address(&signing_key.verifying_key());
+        Self {
+            client,
+            signing_key,
+            address,
+            next_nonce: Mutex::new(None),
+        }
+    }
+
+    /// Address of the signing key
+    pub fn address(&self) -> Address {
+        self.address
+    }
+
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+
+    /// Forget the local nonce so the next transaction fetches it again
+    pub async fn reset_nonce(&self) {
+        *self.next_nonce.lock().await = None;
+    }
+
+    /// Send a transaction carrying `payload` and return its hash
+    pub async fn send(&self, payload: TransactionPayload) -> Result<Hash> {
+        self.send_with(payload, TxOptions::default()).await
+    }
+
+    /// Send a transaction carrying `payload`, overriding what `options` sets
+    pub async fn send_with(&self, payload: TransactionPayload, options: TxOptions) -> Result<Hash> {
+        // Held until submission so concurrent sends get consecutive nonces
+        let mut next_nonce = self.next_nonce.lock().await;
+        if options.nonce.is_some() {
+            *next_nonce = None;
+            let tx = self.fill(payload, options).await?;
+            return self.client.send_transaction(&tx).await;
+        }
+
+        let mut retried = false;
+        loop {
+            let nonce = match *next_nonce {
+                Some(nonce) => nonce,
+                None => self.client.next_nonce(&self.address).await?,
+            };
+            let options = TxOptions {
+                nonce: Some(nonce),
+                ..options
+            };
+            let tx = match self.fill(payload.clone(), options).await {
+                Ok(tx) => tx,
+                Err(e) => {
+                    *next_nonce = None;
+                    return Err(e);
+                }
+            };
+            match self.client.send_transaction(&tx).await {
+                Ok(hash) => {
+                    *next_nonce = Some(nonce + 1);
+                    return Ok(hash);
+                }
+                Err(e) if e.is_nonce_error() => {
+                    // The nonce may have been taken by this very transaction,
+                    // and signing the payload again would send it twice
+                    if self.client.is_known(&tx.hash()).await? {
+                        *next_nonce = Some(nonce + 1);
+                        return Ok(tx.hash());
+                    }
+                    *next_nonce = None;
+                    if retried {
+                        return Err(e);
+                    }
+                    retried = true;
+                }
+                Err(e) => {
+                    *next_nonce = None;
+                    return Err(e);
+                }
+            }
+        }
+    }
+
+    /// Send a transaction carrying `payload` and wait up to `timeout` for
+    /// its receipt
+    pub async fn send_and_wait(
+        &self,
+        payload: TransactionPayload,
+        timeout: Duration,
+    ) -> Result<Receipt> {
+        let hash = self.send(payload).await?;
+        self.client.wait_for_receipt(&hash, timeout).await
+    }
+
+    /// Build and sign a transaction, asking the node for whatever `options`
+    /// leaves out
+    pub async fn fill(
+        &self,
+        payload: TransactionPayload,
+        options: TxOptions,
+    ) -> Result<Transaction> {
+        let nonce = match options.nonce {
+            Some(nonce) => nonce,
+            None => self.client.next_nonce(&self.address).await?,
+        };
+        let mut tx = Transaction::new(self.signing_key.verifying_key(), payload, 0, 0, 0, nonce);
+
+        if options.gas_limit.is_none()
+            || options.max_fee_per_gas.is_none()
+            || options.max_priority_fee_per_gas.is_none()
+        {
+            // The node estimates against committed state, which has not seen
+            // the sender's pooled transactions, so the estimate uses its
+            // committed nonce
+            let mut probe = tx.clone();
+            probe.nonce = self.client.transaction_count(&self.address).await?;
+            let estimate = self.client.estimate_fee(&probe).await?;
+            tx.gas_limit = with_margin(estimate.gas_used);
+            tx.max_fee_per_gas = estimate.max_fee_per_gas;
+            tx.max_priority_fee_per_gas = estimate.max_priority_fee_per_gas;
+        }
+        tx.gas_limit = options.gas_limit.unwrap_or(tx.gas_limit);
+        tx.max_fee_per_gas = options.max_fee_per_gas.unwrap_or(tx.max_fee_per_gas);
+        tx.max_priority_fee_per_gas = options
+            .max_priority_fee_per_gas
+            .unwrap_or(tx.max_priority_fee_per_gas)
+            .min(tx.max_fee_per_gas);
+        if tx.gas_limit == 0 {
+            return Err(ClientError::InvalidResponse {
+                method: "aequitas_estimateFee".to_string(),
+                message: "estimated no gas".to_string(),
+            });
+        }
+
+        Ok(tx.sign(&self.signing_key))
+    }
+}
+
+/// Gas limit leaving headroom above the estimate
+pub fn with_margin(gas_used: u64) -> u64 {
+    gas_used.saturating_add(
+        gas_used
+            .saturating_mul(GAS_LIMIT_MARGIN_PERCENT)
+            .div_ceil(100),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gas_limit_margin() {
+        assert_eq!(with_margin(0), 0);
+        assert_eq!(with_margin(21_000), 25_200);
+        // Rounds up so small estimates still get headroom
+        assert_eq!(with_margin(1), 2);
+        assert_eq!(with_margin(u64::MAX), u64::MAX);
+    }
+}
@@ -0,0 +1,462 @@
+//! Typed results of the node's JSON-RPC methods
+//!
+//! The node renders hashes, addresses and byte strings as `0x`-prefixed hex
+//! and numbers as `0x`-prefixed hex quantities. These types decode them into
+//! core types and plain integers.
+
+use serde::{Deserialize, Deserializer};
+use std::collections::BTreeMap;
+pub use yotquitas_core::parse_hash;
+use yotquitas_core::{Address, Hash, TransactionPayload};
+
+/// Block selector for methods taking a block number or tag
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlockNumber {
+    Earliest,
+    Latest,
+    Number(u64),
+}
+
+impl BlockNumber {
+    /// Render as a JSON-RPC param
+    pub fn to_param(self) -> serde_json::Value {
+        match self {
+            BlockNumber::Earliest => "earliest".into(),
+            BlockNumber::Latest => "latest".into(),
+            BlockNumber::Number(number) => to_quantity(number).into(),
+        }
+    }
+}
+
+impl From<u64> for BlockNumber {
+    fn from(number: u64) -> Self {
+        BlockNumber::Number(number)
+    }
+}
+
+/// Block as returned by `eth_getBlockByNumber` / `eth_getBlockByHash`
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Block {
+    #[serde(deserialize_with = "quantity")]
+    pub number: u64,
+    #[serde(deserialize_with = "hash")]
+    pub hash: Hash,
+    #[serde(deserialize_with = "hash")]
+    pub parent_hash: Hash,
+    #[serde(deserialize_with = "quantity")]
+    pub timestamp: u64,
+    #[serde(deserialize_with = "hash")]
+    pub transactions_root: Hash,
+    #[serde(deserialize_with = "quantity")]
+    pub base_fee_per_gas: u64,
+    #[serde(deserialize_with = "quantity")]
+    pub gas_used: u64,
+    #[serde(deserialize_with = "quantity")]
+    pub gas_limit: u64,
+    /// Address the block's priority tips were paid to
+    #[serde(deserialize_with = "hash")]
+    pub proposer: Address,
+    pub transactions: BlockTransactions,
+}
+
+/// Block body: either transaction hashes or full transaction objects
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(untagged)]
+pub enum BlockTransactions {
+    Hashes(#[serde(deserialize_with = "hashes")] Vec<Hash>),
+    Full(Vec<TransactionInfo>),
+}
+
+impl BlockTransactions {
+    /// Hashes of the block's transactions, in order
+    pub fn hashes(&self) -> Vec<Hash> {
+        match self {
+            BlockTransactions::Hashes(hashes) => hashes.clone(),
+            BlockTransactions::Full(txs) => txs.iter().map(|tx| tx.hash).collect(),
+        }
+    }
+}
+
+/// Transaction with its inclusion location, if known
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionInfo {
+    #[serde(deserialize_with = "hash")]
+    pub hash: Hash,
+    #[serde(deserialize_with = "hash")]
+    pub from: Address,
+    #[serde(deserialize_with = "quantity")]
+    pub nonce: u64,
+    #[serde(deserialize_with = "quantity")]
+    pub gas_limit: u64,
+    #[serde(deserialize_with = "quantity")]
+    pub max_fee_per_gas: u64,
+    #[serde(deserialize_with = "quantity")]
+    pub max_priority_fee_per_gas: u64,
+    pub payload: TransactionPayload,
+    #[serde(default, deserialize_with = "option_bytes")]
+    pub signature: Option<Vec<u8>>,
+    #[serde(default, deserialize_with = "option_hash")]
+    pub block_hash: Option<Hash>,
+    #[serde(default, deserialize_with = "option_quantity")]
+    pub block_number: Option<u64>,
+    #[serde(default, deserialize_with = "option_quantity")]
+    pub transaction_index: Option<u64>,
+}
+
+/// Receipt as returned by `eth_getTransactionReceipt`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Receipt {
+    #[serde(deserialize_with = "hash")]
+    pub transaction_hash: Hash,
+    #[serde(deserialize_with = "quantity")]
+    pub transaction_index: u64,
+    #[serde(deserialize_with = "hash")]
+    pub block_hash: Hash,
+    #[serde(deserialize_with = "quantity")]
+    pub block_number: u64,
+    #[serde(deserialize_with = "hash")]
+    pub from: Address,
+    /// 1 if execution succeeded, 0 otherwise
+    #[serde(deserialize_with = "quantity")]
+    pub status: u64,
+    #[serde(deserialize_with = "quantity")]
+    pub gas_used: u64,
+    #[serde(deserialize_with = "quantity")]
+    pub effective_gas_price: u64,
+    #[serde(deserialize_with = "quantity")]
+    pub fee_paid: u64,
+    #[serde(deserialize_with = "bytes")]
+    pub return_data: Vec<u8>,
+    pub error: Option<String>,
+}
+
+impl Receipt {
+    /// Whether execution succeeded
+    pub fn success(&self) -> bool {
+        self.status == 1
+    }
+}
+
+/// Account state as returned by `aequitas_getAccount`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Account {
+    #[serde(deserialize_with = "hash")]
+    pub address: Address,
+    #[serde(deserialize_with = "quantity")]
+    pub balance: u64,
+    /// Part of the balance locked by a vesting schedule
+    #[serde(deserialize_with = "quantity")]
+    pub locked_balance: u64,
+    /// Part of the balance the account can spend
+    #[serde(deserialize_with = "quantity")]
+    pub unlocked_balance: u64,
+    #[serde(deserialize_with = "quantity")]
+    pub nonce: u64,
+    /// Height of the block the account state was read at
+    #[serde(deserialize_with = "quantity")]
+    pub block_number: u64,
+}
+
+/// Supply as returned by `aequitas_getSupply`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Supply {
+    #[serde(deserialize_with = "quantity")]
+    pub minted: u64,
+    #[serde(deserialize_with = "quantity")]
+    pub burned: u64,
+    #[serde(deserialize_with = "quantity")]
+    pub total_supply: u64,
+    #[serde(deserialize_with = "quantity")]
+    pub block_number: u64,
+}
+
+/// Mempool counts as returned by `txpool_status`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct TxpoolStatus {
+    #[serde(deserialize_with = "quantity")]
+    pub pending: u64,
+    #[serde(deserialize_with = "quantity")]
+    pub queued: u64,
+}
+
+/// Pooled transactions keyed by sender and then nonce
+pub type TxpoolGroup<T> = BTreeMap<Address, BTreeMap<u64, T>>;
+
+/// Mempool as returned by `txpool_content` and, with one-line summaries,
+/// by `txpool_inspect`
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(bound(deserialize = "T: Deserialize<'de>"))]
+pub struct Txpool<T> {
+    #[serde(deserialize_with = "txpool_group")]
+    pub pending: TxpoolGroup<T>,
+    #[serde(deserialize_with = "txpool_group")]
+    pub queued: TxpoolGroup<T>,
+}
+
+/// Whether a pooled transaction can go into the next block
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PoolStatus {
+    Pending,
+    /// Waiting behind a nonce gap
+    Queued,
+}
+
+/// Mempool transaction as returned by `aequitas_getPendingTransaction`
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct PooledTransaction {
+    #[serde(flatten)]
+    pub transaction: TransactionInfo,
+    pub status: PoolStatus,
+}
+
+/// Before and after values of a simulated change
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Change {
+    #[serde(deserialize_with = "quantity")]
+    pub from: u64,
+    #[serde(deserialize_with = "quantity")]
+    pub to: u64,
+}
+
+/// Account changed by a simulated transaction
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct AccountDiff {
+    #[serde(deserialize_with = "hash")]
+    pub address: Address,
+    pub balance: Change,
+    pub nonce: Change,
+}
+
+/// Result of `aequitas_simulateTransaction`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Simulation {
+    /// Head block the transaction was simulated on top of
+    #[serde(deserialize_with = "quantity")]
+    pub block_number: u64,
+    /// Whether the node would accept the transaction
+    pub valid: bool,
+    /// Whether execution would succeed
+    pub success: bool,
+    #[serde(deserialize_with = "quantity")]
+    pub gas_used: u64,
+    #[serde(deserialize_with = "quantity")]
+    pub fee: u64,
+    #[serde(deserialize_with = "bytes")]
+    pub return_data: Vec<u8>,
+    pub error: Option<String>,
+    pub state_diff: Vec<AccountDiff>,
+}
+
+/// Result of `aequitas_estimateFee`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FeeEstimate {
+    #[serde(deserialize_with = "quantity")]
+    pub gas_used: u64,
+    /// Base fee of the next block
+    #[serde(deserialize_with = "quantity")]
+    pub base_fee_per_gas: u64,
+    /// Recommended tip per unit of gas given recent blocks
+    #[serde(deserialize_with = "quantity")]
+    pub max_priority_fee_per_gas: u64,
+    /// Recommended max fee, leaving room for the base fee to double
+    #[serde(deserialize_with = "quantity")]
+    pub max_fee_per_gas: u64,
+    /// Expected price per unit of gas, base fee plus tip
+    #[serde(deserialize_with = "quantity")]
+    pub gas_price: u64,
+    /// Expected fee, `gas_used * gas_price`
+    #[serde(deserialize_with = "quantity")]
+    pub fee: u64,
+}
+
+/// Result of `eth_feeHistory`
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FeeHistory {
+    #[serde(deserialize_with = "quantity")]
+    pub oldest_block: u64,
+    /// Base fees of the requested blocks, followed by that of the next block
+    #[serde(deserialize_with = "quantities")]
+    pub base_fee_per_gas: Vec<u64>,
+    pub gas_used_ratio: Vec<f64>,
+    /// Tips at the requested percentiles, per block
+    #[serde(default, deserialize_with = "option_rewards")]
+    pub reward: Option<Vec<Vec<u64>>>,
+}
+
+/// Payload of a `balanceChanges` subscription notification
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BalanceChange {
+    #[serde(deserialize_with = "hash")]
+    pub address: Address,
+    #[serde(deserialize_with = "quantity")]
+    pub balance: u64,
+    #[serde(deserialize_with = "quantity")]
+    pub block_number: u64,
+}
+
+/// Encode a number as a hex quantity (e.g. `0x1a`)
+pub fn to_quantity(value: u64) -> String {
+    format!("0x{:x}", value)
+}
+
+/// Encode bytes as `0x`-prefixed hex
+pub fn to_hex(bytes: &[u8]) -> String {
+    format!("0x{}", hex::encode(bytes))
+}
+
+/// Decode a `0x`-prefixed hex quantity
+pub fn parse_quantity(quantity: &str) -> Result<u64, String> {
+    let digits = quantity
+        .strip_prefix("0x")
+        .ok_or_else(|| format!("quantity {:?} lacks the 0x prefix", quantity))?;
+    u64::from_str_radix(digits, 16).map_err(|e| format!("invalid quantity {:?}: {}", quantity, e))
+}
+
+/// Decode `0x`-prefixed hex bytes
+pub fn parse_bytes(bytes_hex: &str) -> Result<Vec<u8>, String> {
+    hex::decode(bytes_hex.trim_start_matches("0x"))
+        .map_err(|e| format!("invalid hex {:?}: {}", bytes_hex, e))
+}
+
+fn quantity<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+    parse_quantity(&String::deserialize(deserializer)?).map_err(serde::de::Error::custom)
+}
+
+fn option_quantity<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<u64>, D::Error> {
+    Option::<String>::deserialize(deserializer)?
+        .map(|quantity| parse_quantity(&quantity).map_err(serde::de::Error::custom))
+        .transpose()
+}
+
+fn quantities<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u64>, D::Error> {
+    Vec::<String>::deserialize(deserializer)?
+        .iter()
+        .map(|quantity| parse_quantity(quantity).map_err(serde::de::Error::custom))
+        .collect()
+}
+
+fn option_rewards<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Vec<Vec<u64>>>, D::Error> {
+    Option::<Vec<Vec<String>>>::deserialize(deserializer)?
+        .map(|blocks| {
+            blocks
+                .iter()
+                .map(|tips| {
+                    tips.iter()
+                        .map(|tip| parse_quantity(tip).map_err(serde::de::Error::custom))
+                        .collect()
+                })
+                .collect()
+        })
+        .transpose()
+}
+
+fn hash<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Hash, D::Error> {
+    parse_hash(&String::deserialize(deserializer)?).map_err(serde::de::Error::custom)
+}
+
+fn option_hash<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Hash>, D::Error> {
+    Option::<String>::deserialize(deserializer)?
+        .map(|hash| parse_hash(&hash).map_err(serde::de::Error::custom))
+        .transpose()
+}
+
+fn hashes<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Hash>, D::Error> {
+    Vec::<String>::deserialize(deserializer)?
+        .iter()
+        .map(|hash| parse_hash(hash).map_err(serde::de::Error::custom))
+        .collect()
+}
+
+fn bytes<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+    parse_bytes(&String::deserialize(deserializer)?).map_err(serde::de::Error::custom)
+}
+
+fn option_bytes<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Vec<u8>>, D::Error> {
+    Option::<String>::deserialize(deserializer)?
+        .map(|bytes| parse_bytes(&bytes).map_err(serde::de::Error::custom))
+        .transpose()
+}
+
+fn txpool_group<'de, D, T>(deserializer: D) -> Result<TxpoolGroup<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    BTreeMap::<String, BTreeMap<u64, T>>::deserialize(deserializer)?
+        .into_iter()
+        .map(|(sender, by_nonce)| {
+            let sender = parse_hash(&sender).map_err(serde::de::Error::custom)?;
+            Ok((sender, by_nonce))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_quantity() {
+        assert_eq!(parse_quantity("0x0").unwrap(), 0);
+        assert_eq!(parse_quantity("0x5208").unwrap(), 21_000);
+        assert!(parse_quantity("5208").is_err());
+        assert!(parse_quantity("0xzz").is_err());
+    }
+
+    #[test]
+    fn test_decode_block() {
+        let hash = to_hex(&[1u8; 32]);
+        let block: Block = serde_json::from_value(serde_json::json!({
+            "number": "0x2",
+            "hash": hash,
+            "parentHash": hash,
+            "timestamp": "0x64",
+            "transactionsRoot": hash,
+            "baseFeePerGas": "0x1",
+            "gasUsed": "0x5208",
+            "gasLimit": "0x1c9c380",
+            "proposer": to_hex(&[0u8; 32]),
+            "transactions": [hash],
+        }))
+        .unwrap();
+        assert_eq!(block.number, 2);
+        assert_eq!(block.gas_used, 21_000);
+        assert_eq!(block.transactions.hashes(), vec![[1u8; 32]]);
+    }
+
+    #[test]
+    fn test_decode_txpool() {
+        let status: TxpoolStatus =
+            serde_json::from_value(serde_json::json!({"pending": "0x2", "queued": "0x0"})).unwrap();
+        assert_eq!(
+            status,
+            TxpoolStatus {
+                pending: 2,
+                queued: 0
+            }
+        );
+
+        let sender = to_hex(&[3u8; 32]);
+        let inspect: Txpool<String> = serde_json::from_value(serde_json::json!({
+            "pending": { sender.clone(): { "0": "summary" } },
+            "queued": {},
+        }))
+        .unwrap();
+        assert_eq!(inspect.pending[&[3u8; 32]][&0], "summary");
+
+        let invalid = serde_json::json!({"pending": "2", "queued": "0x0"});
+        assert!(serde_json::from_value::<TxpoolStatus>(invalid).is_err());
+    }
+}
@@ -0,0 +1,341 @@
+//! WebSocket client with `eth_subscribe` subscriptions
+
+use crate::client::{decode_response, RpcError};
+use crate::error::{ClientError, Result};
+use crate::types::{parse_hash, to_hex, BalanceChange, Block};
+use futures_util::{SinkExt, StreamExt};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::net::TcpStream;
+use tokio::sync::{mpsc, oneshot};
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+use yotquitas_core::{Address, Hash};
+
+type Socket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// Result or error of one call, as received
+type Reply = (Option<serde_json::Value>, Option<RpcError>);
+
+/// Call waiting for its response
+struct PendingCall {
+    reply: oneshot::Sender<Reply>,
+    /// Where notifications go if the call creates a subscription
+    subscription: Option<mpsc::UnboundedSender<serde_json::Value>>,
+}
+
+/// State shared between the client handles and the connection task
+#[derive(Default)]
+struct Connection {
+    pending: HashMap<u64, PendingCall>,
+    subscriptions: HashMap<String, mpsc::UnboundedSender<serde_json::Value>>,
+    closed: bool,
+}
+
+/// Frame received from the node: a response or a subscription notification
+#[derive(Deserialize)]
+struct Incoming {
+    id: Option<u64>,
+    result: Option<serde_json::Value>,
+    error: Option<RpcError>,
+    params: Option<NotificationParams>,
+}
+
+#[derive(Deserialize)]
+struct NotificationParams {
+    subscription: String,
+    result: serde_json::Value,
+}
+
+/// Client of a node's WebSocket endpoint (e.g. `ws://127.0.0.1:8545/ws`)
+///
+/// Clones share one connection, which closes once every clone and
+/// subscription is dropped.
+#[derive(Clone)]
+pub struct WsClient {
+    outgoing: mpsc::UnboundedSender<Message>,
+    connection: Arc<Mutex<Connection>>,
+    next_id: Arc<AtomicU64>,
+}
+
+impl WsClient {
+    /// Connect to `url` and start routing its frames
+    pub async fn connect(url: &str) -> Result<Self> {
+        let (socket, _) = tokio_tungstenite::connect_async(url)
+            .await
+            .map_err(|e| ClientError::WebSocket(e.to_string()))?;
+        let (outgoing, outgoing_rx) = mpsc::unbounded_channel();
+        let connection = Arc::new(Mutex::new(Connection::default()));
+        let next_id = Arc::new(AtomicU64::new(1));
+        tokio::spawn(run_connection(
+            socket,
+            outgoing_rx,
+            connection.clone(),
+            next_id.clone(),
+        ));
+        Ok(Self {
+            outgoing,
+            connection,
+            next_id,
+        })
+    }
+
+    /// Call `method` with positional `params` and decode its result
+    pub async fn request<T: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: serde_json::Value,
+    ) -> Result<T> {
+        let (result, error) = self.call(method, params, None).await?;
+        decode_response(method, result, error)
+    }
+
+    /// Headers of blocks as they are produced
+    pub async fn subscribe_new_heads(&self) -> Result<Subscription<Block>> {
+        self.subscribe(serde_json::json!(["newHeads"]), decode_json)
+            .await
+    }
+
+    /// Hashes of transactions as they enter the pool
+    pub async fn subscribe_pending_transactions(&self) -> Result<Subscription<Hash>> {
+        self.subscribe(serde_json::json!(["newPendingTransactions"]), |result| {
+            parse_hash(result.as_str().ok_or("expected a hash string")?)
+        })
+        .await
+    }
+
+    /// New balances of `address` whenever a block changes it
+    pub async fn subscribe_balance_changes(
+        &self,
+        address: &Address,
+    ) -> Result<Subscription<BalanceChange>> {
+        self.subscribe(
+            serde_json::json!(["balanceChanges", to_hex(address)]),
+            decode_json,
+        )
+        .await
+    }
+
+    async fn subscribe<T>(
+        &self,
+        params: serde_json::Value,
+        decode: Decode<T>,
+    ) -> Result<Subscription<T>> {
+        let (sender, notifications) = mpsc::unbounded_channel();
+        let (result, error) = self.call("eth_subscribe", params, Some(sender)).await?;
+        let id: String = decode_response("eth_subscribe", result, error)?;
+        Ok(Subscription {
+            id,
+            client: self.clone(),
+            notifications,
+            decode,
+        })
+    }
+
+    async fn call(
+        &self,
+        method: &str,
+        params: serde_json::Value,
+        subscription: Option<mpsc::UnboundedSender<serde_json::Value>>,
+    ) -> Result<Reply> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let (reply, response) = oneshot::channel();
+        {
+            let mut connection = self.connection.lock().unwrap();
+            if connection.closed {
+                return Err(connection_closed());
+            }
+            connection.pending.insert(
+                id,
+                PendingCall {
+                    reply,
+                    subscription,
+                },
+            );
+        }
+
+        let request = serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": method,
+            "params": params,
+        });
+        if self
+            .outgoing
+            .send(Message::Text(request.to_string()))
+            .is_err()
+        {
+            self.connection.lock().unwrap().pending.remove(&id);
+            return Err(connection_closed());
+        }
+        response.await.map_err(|_| connection_closed())
+    }
+}
+
+/// Stream of notifications from one subscription
+pub struct Subscription<T> {
+    id: String,
+    client: WsClient,
+    notifications: mpsc::UnboundedReceiver<serde_json::Value>,
+    decode: Decode<T>,
+}
+
+impl<T> Subscription<T> {
+    /// Subscription id assigned by the node
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Next notification, or `None` once the connection is closed
+    pub async fn next(&mut self) -> Option<Result<T>> {
+        let result = self.notifications.recv().await?;
+        Some(
+            (self.decode)(result).map_err(|message| ClientError::InvalidResponse {
+                method: "eth_subscription".to_string(),
+                message,
+            }),
+        )
+    }
+
+    /// Cancel the subscription, returning whether the node still had it
+    pub async fn unsubscribe(self) -> Result<bool> {
+        self.client
+            .connection
+            .lock()
+            .unwrap()
+            .subscriptions
+            .remove(&self.id);
+        self.client
+            .request("eth_unsubscribe", serde_json::json!([self.id]))
+            .await
+    }
+}
+
+/// Turns a notification's result into the subscription's item
+type Decode<T> = fn(serde_json::Value) -> std::result::Result<T, String>;
+
+fn decode_json<T: DeserializeOwned>(result: serde_json::Value) -> std::result::Result<T, String> {
+    serde_json::from_value(result).map_err(|e| e.to_string())
+}
+
+fn connection_closed() -> ClientError {
+    ClientError::WebSocket("connection closed".to_string())
+}
+
+/// Send queued requests and route received frames until either side closes
+async fn run_connection(
+    socket: Socket,
+    mut outgoing: mpsc::UnboundedReceiver<Message>,
+    connection: Arc<Mutex<Connection>>,
+    next_id: Arc<AtomicU64>,
+) {
+    let (mut sink, mut stream) = socket.split();
+    loop {
+        tokio::select! {
+            message = outgoing.recv() => match message {
+                Some(message) => {
+                    if sink.send(message).await.is_err() {
+                        break;
+                    }
+                }
+                // Every handle was dropped
+                None => {
+                    let _ = sink.close().await;
+                    break;
+                }
+            },
+            frame = stream.next() => match frame {
+                Some(Ok(Message::Text(text))) => {
+                    if let Some(id) = route(&connection, &text) {
+                        // Nobody listens to this subscription any more
+                        let request = serde_json::json!({
+                            "jsonrpc": "2.0",
+                            "id": next_id.fetch_add(1, Ordering::Relaxed),
+                            "method": "eth_unsubscribe",
+                            "params": [id],
+                        });
+                        if sink.send(Message::Text(request.to_string())).await.is_err() {
+                            break;
+                        }
+                    }
+                }
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                // Pings are answered by tungstenite; binary frames are ignored
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+
+    // Dropping the senders fails pending calls and ends subscriptions
+    let mut connection = connection.lock().unwrap();
+    connection.closed = true;
+    connection.pending.clear();
+    connection.subscriptions.clear();
+}
+
+/// Deliver one frame to whoever waits for it, returning the id of a
+/// subscription whose receiver was dropped
+fn route(connection: &Mutex<Connection>, text: &str) -> Option<String> {
+    let Ok(incoming) = serde_json::from_str::<Incoming>(text) else {
+        return None;
+    };
+    let mut connection = connection.lock().unwrap();
+
+    if let Some(params) = incoming.params {
+        let sender = connection.subscriptions.get(&params.subscription)?;
+        if sender.send(params.result).is_err() {
+            connection.subscriptions.remove(&params.subscription);
+            return Some(params.subscription);
+        }
+        return None;
+    }
+
+    let call = connection.pending.remove(&incoming.id?)?;
+    // Registered before the caller resumes so no notification is missed
+    if let (Some(sender), Some(serde_json::Value::String(id))) =
+        (call.subscription, &incoming.result)
+    {
+        connection.subscriptions.insert(id.clone(), sender);
+    }
+    let _ = call.reply.send((incoming.result, incoming.error));
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_route_registers_subscriptions_before_replying() {
+        let connection = Mutex::new(Connection::default());
+        let (reply, mut response) = oneshot::channel();
+        let (sender, mut notifications) = mpsc::unbounded_channel();
+        connection.lock().unwrap().pending.insert(
+            7,
+            PendingCall {
+                reply,
+                subscription: Some(sender),
+            },
+        );
+
+        assert_eq!(
+            route(&connection, r#"{"jsonrpc":"2.0","id":7,"result":"0x1"}"#),
+            None
+        );
+        let notification = r#"{"jsonrpc":"2.0","method":"eth_subscription","params":{"subscription":"0x1","result":"0xab"}}"#;
+        assert_eq!(route(&connection, notification), None);
+
+        let (result, error) = response.try_recv().unwrap();
+        assert_eq!(result, Some(serde_json::json!("0x1")));
+        assert!(error.is_none());
+        assert_eq!(notifications.try_recv().unwrap(), serde_json::json!("0xab"));
+
+        // A dropped receiver asks for the subscription to be cancelled
+        drop(notifications);
+        assert_eq!(route(&connection, notification), Some("0x1".to_string()));
+        assert!(connection.lock().unwrap().subscriptions.is_empty());
+    }
+}
@@ -0,0 +1,442 @@
+//! Runs the client against a node served in-process
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tempfile::TempDir;
+use yotquitas_client::types::{BlockNumber, PoolStatus};
+use yotquitas_client::{Client, ClientError, RetryPolicy, Signer, TxOptions, WsClient};
+use yotquitas_core::{
+    generate_keypair, public_key_address, Address, SigningKey, Transaction, TransactionPayload,
+};
+use yotquitas_node::api::{self, RpcConfig};
+use yotquitas_node::genesis::{Allocation, Genesis};
+use yotquitas_node::node::Node;
+use yotquitas_node::state::StateDB;
+
+const TREASURY_BALANCE: u64 = 1_000_000_000;
+const TIMEOUT: Duration = Duration::from_secs(10);
+/// Receives the priority tips of the test node's blocks
+const PROPOSER: Address = [7u8; 32];
+
+/// Node serving JSON-RPC on an ephemeral port until dropped
+struct TestNode {
+    addr: SocketAddr,
+    signing_key: SigningKey,
+    _data_dir: TempDir,
+}
+
+impl TestNode {
+    /// Start a node of chain 1337 that funds a fresh key and seals a block
+    /// every 50ms
+    async fn start() -> Self {
+        let data_dir = TempDir::new().unwrap();
+        let (signing_key, _) = generate_keypair();
+        let genesis = Genesis {
+            chain_id: 1337,
+            timestamp: 0,
+            consensus: Default::default(),
+            alloc: vec![Allocation {
+                address: public_key_address(&signing_key.verifying_key()),
+                balance: TREASURY_BALANCE,
+                vesting: None,
+            }],
+            validators: vec![],
+        };
+        let state = Arc::new(StateDB::open(data_dir.path()).unwrap());
+        let node = Arc::new(
+            Node::new(state, &genesis, 1337)
+                .unwrap()
+                .with_proposer(PROPOSER),
+        );
+
+        let producer = node.clone();
+        tokio::spawn(async move { producer.run_block_producer(Duration::from_millis(50)).await });
+
+        let app = api::create_router(node, RpcConfig::default()).unwrap();
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(
+                listener,
+                app.into_make_service_with_connect_info::<SocketAddr>(),
+            )
+            .await
+        });
+
+        Self {
+            addr,
+            signing_key,
+            _data_dir: data_dir,
+        }
+    }
+
+    fn client(&self) -> Client {
+        Client::new(format!("http://{}", self.addr))
+    }
+
+    fn signer(&self) -> Signer {
+        Signer::new(self.client(), self.signing_key.clone())
+    }
+
+    async fn ws(&self) -> WsClient {
+        WsClient::connect(&format!("ws://{}/ws", self.addr))
+            .await
+            .unwrap()
+    }
+}
+
+fn transfer(to: Address, amount: u64) -> TransactionPayload {
+    TransactionPayload::Transfer { to, amount }
+}
+
+fn fresh_address() -> Address {
+    public_key_address(&generate_keypair().1)
+}
+
+#[tokio::test]
+async fn test_chain_queries() {
+    let node = TestNode::start().await;
+    let client = node.client();
+    let treasury = public_key_address(&node.signing_key.verifying_key());
+
+    assert_eq!(client.chain_id().await.unwrap(), 1337);
+    assert_eq!(client.network_id().await.unwrap(), 1337);
+    assert!(client
+        .client_version()
+        .await
+        .unwrap()
+        .starts_with("yotquitas-node/"));
+
+    let genesis = client
+        .block_by_number(BlockNumber::Earliest, false)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(genesis.number, 0);
+    let by_hash = client
+        .block_by_hash(&genesis.hash, true)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(by_hash.hash, genesis.hash);
+    assert!(client
+        .block_by_number(BlockNumber::Number(1_000_000), false)
+        .await
+        .unwrap()
+        .is_none());
+    let latest = client.block_number().await.unwrap();
+    let block = client
+        .block_by_number(BlockNumber::Latest, false)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(block.number >= latest);
+
+    assert_eq!(client.balance(&treasury).await.unwrap(), TREASURY_BALANCE);
+    let account = client.account(&treasury).await.unwrap();
+    assert_eq!(account.address, treasury);
+    assert_eq!(account.unlocked_balance, TREASURY_BALANCE);
+    assert_eq!(client.transaction_count(&treasury).await.unwrap(), 0);
+    assert_eq!(client.supply().await.unwrap().minted, TREASURY_BALANCE);
+
+    assert!(client.gas_price().await.unwrap() > 0);
+    assert!(client.max_priority_fee_per_gas().await.unwrap() > 0);
+    let history = client
+        .fee_history(4, BlockNumber::Latest, &[50.0])
+        .await
+        .unwrap();
+    assert_eq!(
+        history.base_fee_per_gas.len(),
+        history.gas_used_ratio.len() + 1
+    );
+    assert!(history.reward.is_some());
+}
+
+#[tokio::test]
+async fn test_signer_sends_without_waiting_for_inclusion() {
+    let node = TestNode::start().await;
+    let signer = node.signer();
+    let client = signer.client().clone();
+    let recipient = fresh_address();
+
+    let mut hashes = Vec::new();
+    for amount in [100, 200, 300] {
+        hashes.push(signer.send(transfer(recipient, amount)).await.unwrap());
+    }
+
+    for hash in &hashes {
+        let receipt = client.wait_for_receipt(hash, TIMEOUT).await.unwrap();
+        assert!(receipt.success(), "{:?}", receipt.error);
+        assert_eq!(receipt.from, signer.address());
+
+        let info = client.transaction(hash).await.unwrap().unwrap();
+        assert_eq!(info.block_number, Some(receipt.block_number));
+        let block = client
+            .block_by_number(BlockNumber::Number(receipt.block_number), false)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(block.transactions.hashes().contains(hash));
+    }
+    assert_eq!(client.balance(&recipient).await.unwrap(), 600);
+    assert_eq!(
+        client.transaction_count(&signer.address()).await.unwrap(),
+        3
+    );
+}
+
+#[tokio::test]
+async fn test_signer_recovers_from_a_stale_nonce() {
+    let node = TestNode::start().await;
+    let signer = node.signer();
+    let other = node.signer();
+    let recipient = fresh_address();
+
+    signer
+        .send_and_wait(transfer(recipient, 1), TIMEOUT)
+        .await
+        .unwrap();
+    // Another sender with the same key uses the nonce the first one cached
+    other
+        .send_and_wait(transfer(recipient, 2), TIMEOUT)
+        .await
+        .unwrap();
+
+    let receipt = signer
+        .send_and_wait(transfer(recipient, 3), TIMEOUT)
+        .await
+        .unwrap();
+    assert!(receipt.success());
+    let client = signer.client();
+    assert_eq!(client.balance(&recipient).await.unwrap(), 6);
+    assert_eq!(
+        client.transaction_count(&signer.address()).await.unwrap(),
+        3
+    );
+}
+
+#[tokio::test]
+async fn test_resubmission_is_not_sent_twice() {
+    let node = TestNode::start().await;
+    let signer = node.signer();
+    let client = signer.client().clone();
+    let recipient = fresh_address();
+    // Fixed fees make signing the same payload and nonce reproducible
+    let fees = TxOptions {
+        gas_limit: Some(100_000),
+        max_fee_per_gas: Some(1_000),
+        max_priority_fee_per_gas: Some(1),
+        ..Default::default()
+    };
+
+    let first = signer
+        .send_and_wait(transfer(recipient, 1), TIMEOUT)
+        .await
+        .unwrap();
+    assert!(first.success());
+
+    // Resending the same signed transaction succeeds without a second copy
+    let tx = signer
+        .fill(
+            transfer(recipient, 2),
+            TxOptions {
+                nonce: Some(1),
+                ..fees
+            },
+        )
+        .await
+        .unwrap();
+    assert_eq!(client.send_transaction(&tx).await.unwrap(), tx.hash());
+    assert_eq!(client.send_transaction(&tx).await.unwrap(), tx.hash());
+    client.wait_for_receipt(&tx.hash(), TIMEOUT).await.unwrap();
+
+    // The signer still holds nonce 1, which that transaction used; the node
+    // rejects the nonce, but the transaction is found on chain and not sent
+    // again
+    let hash = signer
+        .send_with(transfer(recipient, 2), fees)
+        .await
+        .unwrap();
+    assert_eq!(hash, tx.hash());
+    let hash = signer.send(transfer(recipient, 4)).await.unwrap();
+    client.wait_for_receipt(&hash, TIMEOUT).await.unwrap();
+
+    assert_eq!(client.balance(&recipient).await.unwrap(), 7);
+    assert_eq!(
+        client.transaction_count(&signer.address()).await.unwrap(),
+        3
+    );
+}
+
+#[tokio::test]
+async fn test_dry_runs_and_estimates() {
+    let node = TestNode::start().await;
+    let client = node.client();
+    let recipient = fresh_address();
+    let tx = Transaction::new(
+        node.signing_key.verifying_key(),
+        transfer(recipient, 500),
+        100_000,
+        1_000,
+        1,
+        0,
+    );
+
+    let simulation = client.simulate_transaction(&tx).await.unwrap();
+    assert!(simulation.valid && simulation.success);
+    assert!(simulation
+        .state_diff
+        .iter()
+        .any(|diff| diff.address == recipient && diff.balance.to == 500));
+    // Only Move calls run read-only
+    let error = client.call(&tx).await.unwrap_err();
+    assert_eq!(error.rpc_code(), Some(-32000));
+
+    let gas = client.estimate_gas(&tx).await.unwrap();
+    let estimate = client.estimate_fee(&tx).await.unwrap();
+    assert_eq!(estimate.gas_used, gas);
+    assert!(estimate.max_fee_per_gas >= estimate.base_fee_per_gas);
+
+    // Nothing was committed
+    assert_eq!(client.balance(&recipient).await.unwrap(), 0);
+}
+
+#[tokio::test]
+async fn test_pool_queries() {
+    let node = TestNode::start().await;
+    let signer = node.signer();
+    let client = signer.client().clone();
+    let address = signer.address();
+
+    // A nonce gap keeps the transaction queued and out of blocks
+    let hash = signer
+        .send_with(
+            transfer(fresh_address(), 1),
+            TxOptions {
+                nonce: Some(5),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+
+    let status = client.txpool_status().await.unwrap();
+    assert_eq!((status.pending, status.queued), (0, 1));
+    let content = client.txpool_content().await.unwrap();
+    assert_eq!(content.queued[&address][&5].hash, hash);
+    let inspect = client.txpool_inspect().await.unwrap();
+    assert!(inspect.queued[&address].contains_key(&5));
+
+    let pooled = client.pending_transaction(&hash).await.unwrap().unwrap();
+    assert_eq!(pooled.status, PoolStatus::Queued);
+    assert_eq!(pooled.transaction.nonce, 5);
+    let pending = client.pending_transactions(&address).await.unwrap();
+    assert_eq!(pending.len(), 1);
+    assert!(client.receipt(&hash).await.unwrap().is_none());
+    // Queued transactions do not advance the next usable nonce
+    assert_eq!(client.next_nonce(&address).await.unwrap(), 0);
+}
+
+#[tokio::test]
+async fn test_subscriptions() {
+    let node = TestNode::start().await;
+    let ws = node.ws().await;
+    let signer = node.signer();
+    let recipient = fresh_address();
+
+    assert_eq!(
+        ws.request::<String>("eth_chainId", serde_json::json!([]))
+            .await
+            .unwrap(),
+        "0x539"
+    );
+
+    let mut heads = ws.subscribe_new_heads().await.unwrap();
+    let mut pending = ws.subscribe_pending_transactions().await.unwrap();
+    let mut balances = ws.subscribe_balance_changes(&recipient).await.unwrap();
+    let mut tips = ws.subscribe_balance_changes(&PROPOSER).await.unwrap();
+
+    let options = TxOptions {
+        max_priority_fee_per_gas: Some(1),
+        ..TxOptions::default()
+    };
+    let hash = signer
+        .send_with(transfer(recipient, 42), options)
+        .await
+        .unwrap();
+
+    let pending_hash = tokio::time::timeout(TIMEOUT, pending.next())
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap();
+    assert_eq!(pending_hash, hash);
+    let change = tokio::time::timeout(TIMEOUT, balances.next())
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap();
+    assert_eq!((change.address, change.balance), (recipient, 42));
+    // The proposer's tip changes its balance too
+    let tip = tokio::time::timeout(TIMEOUT, tips.next())
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap();
+    let receipt = node.client().receipt(&hash).await.unwrap().unwrap();
+    assert_eq!((tip.address, tip.balance), (PROPOSER, receipt.gas_used));
+    let head = tokio::time::timeout(TIMEOUT, heads.next())
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap();
+    assert!(head.number > 0);
+
+    assert!(heads.unsubscribe().await.unwrap());
+    assert!(pending.unsubscribe().await.unwrap());
+    assert!(balances.unsubscribe().await.unwrap());
+    assert!(tips.unsubscribe().await.unwrap());
+}
+
+#[tokio::test]
+async fn test_errors() {
+    let node = TestNode::start().await;
+    let client = node.client();
+
+    // Rejected by the node: signed by a key that owns nothing
+    let (poor_key, poor_pubkey) = generate_keypair();
+    let tx = Transaction::new(
+        poor_pubkey,
+        transfer(fresh_address(), 1),
+        100_000,
+        1_000,
+        1,
+        0,
+    )
+    .sign(&poor_key);
+    let error = client.send_transaction(&tx).await.unwrap_err();
+    assert!(error.rpc_code().is_some(), "{}", error);
+    assert!(!error.is_transient());
+
+    let error = client
+        .wait_for_receipt(&[7; 32], Duration::from_millis(300))
+        .await
+        .unwrap_err();
+    assert!(matches!(error, ClientError::ReceiptTimeout { .. }));
+
+    // Nothing listens on a port that was just released
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    drop(listener);
+    let unreachable = Client::new(format!("http://{}", addr)).with_retry(RetryPolicy {
+        max_retries: 2,
+        initial_backoff: Duration::from_millis(10),
+        max_backoff: Duration::from_millis(20),
+    });
+    let error = unreachable.block_number().await.unwrap_err();
+    assert!(error.is_transient(), "{}", error);
+    assert!(WsClient::connect(&format!("ws://{}/ws", addr))
+        .await
+        .is_err());
+}
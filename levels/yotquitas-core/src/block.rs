@@ -1,5 +1,5 @@
 use crate::crypto::{double_sha256, Hash};
-use crate::transaction::Transaction;
+use crate::transaction::{Address, Transaction};
 use serde::{Deserialize, Serialize};
 
 /// Block header structure
@@ -9,6 +9,12 @@ pub struct BlockHeader {
     pub timestamp: u64,
     pub previous_hash: Hash,
     pub merkle_root: Hash,
+    /// Price per unit of gas burned by every transaction in the block
+    pub base_fee_per_gas: u64,
+    /// Gas used by all transactions in the block
+    pub gas_used: u64,
+    /// Address the priority tips of the block are paid to
+    pub proposer: Address,
 }
 
 impl BlockHeader {
@@ -19,9 +25,20 @@ impl BlockHeader {
             timestamp,
             previous_hash,
             merkle_root: [0u8; 32], // Will be computed when transactions are added
+            base_fee_per_gas: 0,
+            gas_used: 0,
+            proposer: [0u8; 32],
         }
     }
 
+    /// Set the fee market fields of the header
+    pub fn with_fees(mut self, base_fee_per_gas: u64, gas_used: u64, proposer: Address) -> Self {
+        self.base_fee_per_gas = base_fee_per_gas;
+        self.gas_used = gas_used;
+        self.proposer = proposer;
+        self
+    }
+
     /// Compute the hash of the block header
     pub fn hash(&self) -> Hash {
         let mut bytes = Vec::new();
@@ -29,6 +46,9 @@ impl BlockHeader {
         bytes.extend_from_slice(&self.timestamp.to_le_bytes());
         bytes.extend_from_slice(&self.previous_hash);
         bytes.extend_from_slice(&self.merkle_root);
+        bytes.extend_from_slice(&self.base_fee_per_gas.to_le_bytes());
+        bytes.extend_from_slice(&self.gas_used.to_le_bytes());
+        bytes.extend_from_slice(&self.proposer);
         double_sha256(&bytes)
     }
 }
@@ -157,6 +177,11 @@ mod tests {
         // Different index should have different hash
         let header3 = BlockHeader::new(1, 1000, [0u8; 32]);
         assert_ne!(header1.hash(), header3.hash());
+
+        // Fee market fields are covered by the hash
+        let header4 = BlockHeader::new(0, 1000, [0u8; 32]).with_fees(7, 21_000, [1u8; 32]);
+        assert_eq!(header4.base_fee_per_gas, 7);
+        assert_ne!(header1.hash(), header4.hash());
     }
 
     #[test]
@@ -168,8 +193,10 @@ mod tests {
                 to: [0u8; 32],
                 amount: 100,
             },
+            25_000,
             1,
             0,
+            0,
         );
 
         let header = BlockHeader::new(0, 1000, [0u8; 32]);
@@ -194,11 +221,13 @@ mod tests {
                 to: [0u8; 32],
                 amount: 100,
             },
+            25_000,
             1,
             0,
+            0,
         );
 
-        let root = compute_merkle_root(&[tx.clone()]);
+        let root = compute_merkle_root(std::slice::from_ref(&tx));
         assert_eq!(root, tx.hash());
     }
 
@@ -211,8 +240,10 @@ mod tests {
                 to: [0u8; 32],
                 amount: 100,
             },
+            25_000,
             1,
             0,
+            0,
         );
         let tx2 = Transaction::new(
             pubkey,
@@ -220,7 +251,9 @@ mod tests {
                 to: [1u8; 32],
                 amount: 200,
             },
+            25_000,
             1,
+            0,
             1,
         );
 
@@ -239,8 +272,10 @@ mod tests {
                 to: [0u8; 32],
                 amount: 100,
             },
+            25_000,
             1,
             0,
+            0,
         )
         .sign(&signing_key);
 
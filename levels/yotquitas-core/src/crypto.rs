@@ -23,6 +23,11 @@ pub fn double_sha256(data: &[u8]) -> Hash {
     sha256(&sha256(data))
 }
 
+/// Derive the address of an account from its public key
+pub fn public_key_address(public_key: &VerifyingKey) -> Hash {
+    sha256(&public_key.to_bytes())
+}
+
 /// Generate a new Ed25519 key pair
 pub fn generate_keypair() -> (SigningKey, VerifyingKey) {
     use rand::rngs::OsRng;
@@ -51,6 +56,14 @@ pub fn decode_hex(hex_str: &str) -> Result<Vec<u8>, hex::FromHexError> {
     hex::decode(hex_str)
 }
 
+/// Decode a hex-encoded 32-byte hash or address, with or without `0x`
+pub fn parse_hash(hash_hex: &str) -> Result<Hash, String> {
+    hex::decode(hash_hex.trim_start_matches("0x"))
+        .map_err(|e| format!("invalid hex {:?}: {}", hash_hex, e))?
+        .try_into()
+        .map_err(|_| format!("{:?} is not 32 bytes", hash_hex))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -102,5 +115,13 @@ mod tests {
         let decoded = decode_hex(&encoded).unwrap();
         assert_eq!(decoded, data);
     }
+
+    #[test]
+    fn test_parse_hash() {
+        assert_eq!(parse_hash(&format!("0x{}", "ab".repeat(32))).unwrap(), [0xab; 32]);
+        assert_eq!(parse_hash(&"cd".repeat(32)).unwrap(), [0xcd; 32]);
+        assert!(parse_hash("0xab").is_err());
+        assert!(parse_hash("0xzz").is_err());
+    }
 }
 
@@ -0,0 +1,335 @@
+//! Passphrase-encrypted key files
+//!
+//! A keystore holds one Ed25519 signing key encrypted with AES-256-GCM under
+//! a key derived from a passphrase with scrypt. It is stored as JSON along
+//! with the address of the key, so the file can be identified without the
+//! passphrase.
+
+use crate::crypto::public_key_address;
+use crate::transaction::Address;
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Nonce};
+use ed25519_dalek::SigningKey;
+use rand::rngs::OsRng;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::Path;
+
+/// Version of the keystore format
+pub const KEYSTORE_VERSION: u32 = 1;
+
+const CIPHER: &str = "aes-256-gcm";
+const KDF: &str = "scrypt";
+
+/// Errors from creating, reading or unlocking a keystore
+#[derive(Debug)]
+pub enum KeystoreError {
+    Io(std::io::Error),
+    /// The file is not a keystore this version can read
+    Format(String),
+    /// The passphrase does not unlock the key
+    WrongPassphrase,
+    /// No usable passphrase was given
+    Passphrase(String),
+}
+
+impl fmt::Display for KeystoreError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            KeystoreError::Io(e) => write!(f, "keystore I/O error: {}", e),
+            KeystoreError::Format(e) => write!(f, "invalid keystore: {}", e),
+            KeystoreError::WrongPassphrase => write!(f, "wrong passphrase"),
+            KeystoreError::Passphrase(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for KeystoreError {}
+
+impl From<std::io::Error> for KeystoreError {
+    fn from(e: std::io::Error) -> Self {
+        KeystoreError::Io(e)
+    }
+}
+
+/// Cost parameters of the scrypt key derivation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScryptParams {
+    /// Base 2 logarithm of the CPU/memory cost
+    pub log_n: u8,
+    pub r: u32,
+    pub p: u32,
+}
+
+impl Default for ScryptParams {
+    fn default() -> Self {
+        Self {
+            log_n: 15,
+            r: 8,
+            p: 1,
+        }
+    }
+}
+
+/// Encrypted key and the parameters needed to decrypt it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeystoreCrypto {
+    pub cipher: String,
+    /// Hex-encoded encrypted key, authentication tag included
+    pub ciphertext: String,
+    /// Hex-encoded 12-byte AES-GCM nonce
+    pub nonce: String,
+    pub kdf: String,
+    pub kdfparams: ScryptParams,
+    /// Hex-encoded scrypt salt
+    pub salt: String,
+}
+
+/// Passphrase-encrypted signing key
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Keystore {
+    pub version: u32,
+    /// Hex-encoded address of the stored key
+    pub address: String,
+    pub crypto: KeystoreCrypto,
+}
+
+impl Keystore {
+    /// Encrypt `signing_key` under `passphrase` with the default scrypt cost
+    pub fn encrypt(signing_key: &SigningKey, passphrase: &str) -> Result<Self, KeystoreError> {
+        Self::encrypt_with_params(signing_key, passphrase, ScryptParams::default())
+    }
+
+    /// Encrypt `signing_key` under `passphrase` with the given scrypt cost
+    pub fn encrypt_with_params(
+        signing_key: &SigningKey,
+        passphrase: &str,
+        params: ScryptParams,
+    ) -> Result<Self, KeystoreError> {
+        let mut salt = [0u8; 32];
+        let mut nonce = [0u8; 12];
+        OsRng.fill_bytes(&mut salt);
+        OsRng.fill_bytes(&mut nonce);
+
+        let cipher = derive_cipher(passphrase, &salt, params)?;
+        let ciphertext = cipher
+            .encrypt(Nonce::from_slice(&nonce), signing_key.to_bytes().as_slice())
+            .map_err(|_| KeystoreError::Format("encryption failed".to_string()))?;
+
+        Ok(Self {
+            version: KEYSTORE_VERSION,
+            address: hex::encode(public_key_address(&signing_key.verifying_key())),
+            crypto: KeystoreCrypto {
+                cipher: CIPHER.to_string(),
+                ciphertext: hex::encode(ciphertext),
+                nonce: hex::encode(nonce),
+                kdf: KDF.to_string(),
+                kdfparams: params,
+                salt: hex::encode(salt),
+            },
+        })
+    }
+
+    /// Decrypt the signing key with `passphrase`
+    pub fn decrypt(&self, passphrase: &str) -> Result<SigningKey, KeystoreError> {
+        if self.version != KEYSTORE_VERSION {
+            return Err(KeystoreError::Format(format!(
+                "unsupported version {}",
+                self.version
+            )));
+        }
+        if self.crypto.cipher != CIPHER || self.crypto.kdf != KDF {
+            return Err(KeystoreError::Format(format!(
+                "unsupported cipher {} with kdf {}",
+                self.crypto.cipher, self.crypto.kdf
+            )));
+        }
+        let salt = decode_field("salt", &self.crypto.salt)?;
+        let nonce = decode_field("nonce", &self.crypto.nonce)?;
+        if nonce.len() != 12 {
+            return Err(KeystoreError::Format("nonce must be 12 bytes".to_string()));
+        }
+        let ciphertext = decode_field("ciphertext", &self.crypto.ciphertext)?;
+
+        let cipher = derive_cipher(passphrase, &salt, self.crypto.kdfparams)?;
+        let secret = cipher
+            .decrypt(Nonce::from_slice(&nonce), ciphertext.as_slice())
+            .map_err(|_| KeystoreError::WrongPassphrase)?;
+        let secret: [u8; 32] = secret
+            .try_into()
+            .map_err(|_| KeystoreError::Format("key must be 32 bytes".to_string()))?;
+        let signing_key = SigningKey::from_bytes(&secret);
+
+        if hex::encode(public_key_address(&signing_key.verifying_key())) != self.address {
+            return Err(KeystoreError::Format(
+                "key does not match the stored address".to_string(),
+            ));
+        }
+        Ok(signing_key)
+    }
+
+    /// Address of the stored key
+    pub fn address(&self) -> Result<Address, KeystoreError> {
+        decode_field("address", &self.address)?
+            .try_into()
+            .map_err(|_| KeystoreError::Format("address must be 32 bytes".to_string()))
+    }
+
+    /// Encrypt `signing_key` under `passphrase` into a new keystore file at
+    /// `path`, refusing an empty passphrase
+    pub fn create(
+        path: &Path,
+        signing_key: &SigningKey,
+        passphrase: &str,
+    ) -> Result<Self, KeystoreError> {
+        if passphrase.is_empty() {
+            return Err(KeystoreError::Passphrase(
+                "the keystore passphrase must not be empty".to_string(),
+            ));
+        }
+        let keystore = Self::encrypt(signing_key, passphrase)?;
+        keystore.save(path)?;
+        Ok(keystore)
+    }
+
+    /// Read a keystore file
+    pub fn load(path: &Path) -> Result<Self, KeystoreError> {
+        let data = std::fs::read(path)?;
+        serde_json::from_slice(&data).map_err(|e| KeystoreError::Format(e.to_string()))
+    }
+
+    /// Write the keystore to a new file readable only by its owner
+    pub fn save(&self, path: &Path) -> Result<(), KeystoreError> {
+        use std::io::Write;
+
+        let data =
+            serde_json::to_vec_pretty(self).map_err(|e| KeystoreError::Format(e.to_string()))?;
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        options.open(path)?.write_all(&data)?;
+        Ok(())
+    }
+}
+
+/// Derive the AES key from `passphrase` and `salt`
+fn derive_cipher(
+    passphrase: &str,
+    salt: &[u8],
+    params: ScryptParams,
+) -> Result<Aes256Gcm, KeystoreError> {
+    let params = scrypt::Params::new(params.log_n, params.r, params.p, 32)
+        .map_err(|e| KeystoreError::Format(format!("invalid scrypt parameters: {}", e)))?;
+    let mut key = [0u8; 32];
+    scrypt::scrypt(passphrase.as_bytes(), salt, &params, &mut key)
+        .map_err(|e| KeystoreError::Format(format!("key derivation failed: {}", e)))?;
+    Aes256Gcm::new_from_slice(&key).map_err(|_| KeystoreError::Format("invalid key".to_string()))
+}
+
+/// Read a passphrase file, ignoring a trailing newline
+pub fn read_passphrase_file(path: &Path) -> Result<String, KeystoreError> {
+    let content = std::fs::read_to_string(path).map_err(|e| {
+        KeystoreError::Passphrase(format!("failed to read password file {:?}: {}", path, e))
+    })?;
+    Ok(content.trim_end_matches(['\r', '\n']).to_string())
+}
+
+fn decode_field(name: &str, value: &str) -> Result<Vec<u8>, KeystoreError> {
+    hex::decode(value).map_err(|e| KeystoreError::Format(format!("{} is not hex: {}", name, e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::generate_keypair;
+
+    /// Cheap parameters so the tests run quickly
+    const TEST_PARAMS: ScryptParams = ScryptParams {
+        log_n: 4,
+        r: 8,
+        p: 1,
+    };
+
+    #[test]
+    fn test_keystore_round_trip() {
+        let (signing_key, verifying_key) = generate_keypair();
+        let keystore = Keystore::encrypt_with_params(&signing_key, "hunter2", TEST_PARAMS).unwrap();
+        assert_eq!(
+            keystore.address().unwrap(),
+            public_key_address(&verifying_key)
+        );
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("key.json");
+        keystore.save(&path).unwrap();
+        // Existing files are never overwritten
+        assert!(keystore.save(&path).is_err());
+
+        let loaded = Keystore::load(&path).unwrap();
+        assert_eq!(loaded, keystore);
+        assert_eq!(
+            loaded.decrypt("hunter2").unwrap().to_bytes(),
+            signing_key.to_bytes()
+        );
+        assert!(matches!(
+            loaded.decrypt("hunter3"),
+            Err(KeystoreError::WrongPassphrase)
+        ));
+    }
+
+    #[test]
+    fn test_create_with_passphrase_file() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("key.json");
+        let password_file = temp_dir.path().join("password");
+        let (signing_key, _) = generate_keypair();
+
+        std::fs::write(&password_file, "\n").unwrap();
+        let passphrase = read_passphrase_file(&password_file).unwrap();
+        assert!(matches!(
+            Keystore::create(&path, &signing_key, &passphrase),
+            Err(KeystoreError::Passphrase(_))
+        ));
+        assert!(!path.exists());
+
+        std::fs::write(&password_file, "correct horse\r\n").unwrap();
+        let passphrase = read_passphrase_file(&password_file).unwrap();
+        assert_eq!(passphrase, "correct horse");
+        Keystore::create(&path, &signing_key, &passphrase).unwrap();
+        let loaded = Keystore::load(&path).unwrap();
+        assert_eq!(
+            loaded.decrypt("correct horse").unwrap().to_bytes(),
+            signing_key.to_bytes()
+        );
+        // An existing keystore is never replaced
+        assert!(matches!(
+            Keystore::create(&path, &signing_key, &passphrase),
+            Err(KeystoreError::Io(_))
+        ));
+    }
+
+    #[test]
+    fn test_tampered_keystore_is_rejected() {
+        let (signing_key, _) = generate_keypair();
+        let keystore = Keystore::encrypt_with_params(&signing_key, "pass", TEST_PARAMS).unwrap();
+
+        let mut other_address = keystore.clone();
+        other_address.address = hex::encode([7u8; 32]);
+        assert!(matches!(
+            other_address.decrypt("pass"),
+            Err(KeystoreError::Format(_))
+        ));
+
+        let mut future = keystore.clone();
+        future.version = 2;
+        assert!(matches!(
+            future.decrypt("pass"),
+            Err(KeystoreError::Format(_))
+        ));
+    }
+}
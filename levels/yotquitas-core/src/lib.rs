@@ -6,9 +6,11 @@
 pub mod block;
 pub mod transaction;
 pub mod crypto;
+pub mod keystore;
 
 // Re-export commonly used types
 pub use block::{Block, BlockHeader, compute_merkle_root};
 pub use transaction::{Transaction, TransactionPayload, Address};
-pub use crypto::{Hash, PublicKey, Signature, sha256, double_sha256, generate_keypair, sign, verify, encode_hex, decode_hex};
+pub use crypto::{Hash, PublicKey, Signature, sha256, double_sha256, generate_keypair, public_key_address, sign, verify, encode_hex, decode_hex, parse_hash};
+pub use keystore::{Keystore, KeystoreError, read_passphrase_file};
 pub use ed25519_dalek::SigningKey;
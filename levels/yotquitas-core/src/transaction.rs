@@ -1,4 +1,4 @@
-use crate::crypto::{Hash, PublicKey, Signature, sha256, sign, verify};
+use crate::crypto::{Hash, PublicKey, Signature, public_key_address, sha256, sign, verify};
 use ed25519_dalek::{SigningKey, VerifyingKey};
 use serde::{Deserialize, Serialize};
 
@@ -44,8 +44,8 @@ mod pubkey_serde {
                 A: de::SeqAccess<'de>,
             {
                 let mut bytes = [0u8; 32];
-                for i in 0..32 {
-                    bytes[i] = seq
+                for (i, byte) in bytes.iter_mut().enumerate() {
+                    *byte = seq
                         .next_element()?
                         .ok_or_else(|| de::Error::invalid_length(i, &self))?;
                 }
@@ -122,8 +122,8 @@ mod option_signature_serde {
                         A: serde::de::SeqAccess<'de>,
                     {
                         let mut bytes = [0u8; 64];
-                        for i in 0..64 {
-                            bytes[i] = seq
+                        for (i, byte) in bytes.iter_mut().enumerate() {
+                            *byte = seq
                                 .next_element()?
                                 .ok_or_else(|| de::Error::invalid_length(i, &self))?;
                         }
@@ -139,8 +139,8 @@ mod option_signature_serde {
                 A: serde::de::SeqAccess<'de>,
             {
                 let mut bytes = [0u8; 64];
-                for i in 0..64 {
-                    bytes[i] = seq
+                for (i, byte) in bytes.iter_mut().enumerate() {
+                    *byte = seq
                         .next_element()?
                         .ok_or_else(|| de::Error::invalid_length(i, &self))?;
                 }
@@ -178,7 +178,12 @@ pub struct Transaction {
     )]
     pub signature: Option<Signature>,
     pub payload: TransactionPayload,
-    pub fee: u64,
+    /// Most gas the sender allows execution to use
+    pub gas_limit: u64,
+    /// Most the sender pays per unit of gas, base fee and tip included
+    pub max_fee_per_gas: u64,
+    /// Most the sender tips the block proposer per unit of gas
+    pub max_priority_fee_per_gas: u64,
     pub nonce: u64,
 }
 
@@ -187,18 +192,38 @@ impl Transaction {
     pub fn new(
         sender_pubkey: PublicKey,
         payload: TransactionPayload,
-        fee: u64,
+        gas_limit: u64,
+        max_fee_per_gas: u64,
+        max_priority_fee_per_gas: u64,
         nonce: u64,
     ) -> Self {
         Self {
             sender_pubkey,
             signature: None,
             payload,
-            fee,
+            gas_limit,
+            max_fee_per_gas,
+            max_priority_fee_per_gas,
             nonce,
         }
     }
 
+    /// Largest fee the transaction can be charged, `gas_limit * max_fee_per_gas`
+    ///
+    /// Returns `None` if the product overflows.
+    pub fn max_fee(&self) -> Option<u64> {
+        self.gas_limit.checked_mul(self.max_fee_per_gas)
+    }
+
+    /// Price paid per unit of gas in a block with the given base fee
+    ///
+    /// The base fee plus the priority tip, capped at `max_fee_per_gas`.
+    pub fn effective_gas_price(&self, base_fee_per_gas: u64) -> u64 {
+        base_fee_per_gas
+            .saturating_add(self.max_priority_fee_per_gas)
+            .min(self.max_fee_per_gas)
+    }
+
     /// Sign the transaction with a signing key
     pub fn sign(mut self, signing_key: &SigningKey) -> Self {
         let tx_bytes = self.to_bytes_for_signing();
@@ -226,8 +251,7 @@ impl Transaction {
 
     /// Get sender address (hash of public key)
     pub fn sender_address(&self) -> Address {
-        let pubkey_bytes = self.sender_pubkey.to_bytes();
-        sha256(&pubkey_bytes)
+        public_key_address(&self.sender_pubkey)
     }
 
     /// Serialize transaction for signing (without signature)
@@ -237,7 +261,9 @@ impl Transaction {
             sender_pubkey: self.sender_pubkey,
             signature: None,
             payload: self.payload.clone(),
-            fee: self.fee,
+            gas_limit: self.gas_limit,
+            max_fee_per_gas: self.max_fee_per_gas,
+            max_priority_fee_per_gas: self.max_priority_fee_per_gas,
             nonce: self.nonce,
         };
         // Use serde_json for consistent serialization
@@ -263,8 +289,14 @@ mod tests {
             amount: 100,
         };
 
-        let tx = Transaction::new(pubkey, payload, 1, 0);
-        assert_eq!(tx.fee, 1);
+        let tx = Transaction::new(pubkey, payload, 25_000, 3, 1, 0);
+        assert_eq!(tx.gas_limit, 25_000);
+        assert_eq!(tx.max_fee_per_gas, 3);
+        assert_eq!(tx.max_priority_fee_per_gas, 1);
+        assert_eq!(tx.max_fee(), Some(75_000));
+        // The tip is added to the base fee until the max fee caps it
+        assert_eq!(tx.effective_gas_price(1), 2);
+        assert_eq!(tx.effective_gas_price(5), 3);
         assert_eq!(tx.nonce, 0);
         assert!(tx.signature.is_none());
     }
@@ -277,7 +309,7 @@ mod tests {
             amount: 100,
         };
 
-        let tx = Transaction::new(pubkey, payload, 1, 0);
+        let tx = Transaction::new(pubkey, payload, 25_000, 1, 0, 0);
         let signed_tx = tx.sign(&signing_key);
 
         assert!(signed_tx.signature.is_some());
@@ -294,7 +326,7 @@ mod tests {
             amount: 100,
         };
 
-        let tx = Transaction::new(pubkey, payload, 1, 0);
+        let tx = Transaction::new(pubkey, payload, 25_000, 1, 0, 0);
         let signed_tx = tx.sign(&signing_key);
 
         // Correct signature should verify
@@ -314,8 +346,8 @@ mod tests {
             amount: 100,
         };
 
-        let tx1 = Transaction::new(pubkey, payload.clone(), 1, 0);
-        let tx2 = Transaction::new(pubkey, payload, 1, 0);
+        let tx1 = Transaction::new(pubkey, payload.clone(), 25_000, 1, 0, 0);
+        let tx2 = Transaction::new(pubkey, payload, 25_000, 1, 0, 0);
 
         // Same transaction should have same hash
         assert_eq!(tx1.hash(), tx2.hash());
@@ -327,7 +359,9 @@ mod tests {
                 to: [0u8; 32],
                 amount: 100,
             },
+            25_000,
             1,
+            0,
             1,
         );
         assert_ne!(tx1.hash(), tx3.hash());
@@ -341,7 +375,7 @@ mod tests {
             amount: 100,
         };
 
-        let tx = Transaction::new(pubkey, payload, 1, 0);
+        let tx = Transaction::new(pubkey, payload, 25_000, 1, 0, 0);
         let signed_tx = tx.sign(&signing_key);
 
         // Serialize to JSON string (like your example)
@@ -363,7 +397,12 @@ mod tests {
             deserialized_tx.signature.as_ref().map(|s| s.to_bytes())
         );
         assert_eq!(signed_tx.payload, deserialized_tx.payload);
-        assert_eq!(signed_tx.fee, deserialized_tx.fee);
+        assert_eq!(signed_tx.gas_limit, deserialized_tx.gas_limit);
+        assert_eq!(signed_tx.max_fee_per_gas, deserialized_tx.max_fee_per_gas);
+        assert_eq!(
+            signed_tx.max_priority_fee_per_gas,
+            deserialized_tx.max_priority_fee_per_gas
+        );
         assert_eq!(signed_tx.nonce, deserialized_tx.nonce);
 
         // Verify signature still works after deserialization
@@ -1,23 +1,52 @@
 use crate::node::Node;
-use anyhow::Result;
 use axum::{
-    extract::State,
+    body::Bytes,
+    extract::{DefaultBodyLimit, State},
     http::StatusCode,
-    response::Json,
-    routing::post,
-    Router,
+    middleware,
+    response::{IntoResponse, Json, Response},
+    routing::{get, post},
+    Extension, Router,
 };
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 use std::sync::Arc;
-use yotquitas_core::{Address, Transaction};
+use tower::limit::GlobalConcurrencyLimitLayer;
+use yotquitas_core::{Address, Hash, Transaction};
+
+mod admin;
+mod limits;
+mod monitoring;
+mod rest;
+pub mod types;
+mod ws;
+
+pub use admin::{create_admin_router, load_or_create_token, AdminConfig, LogLevelHandle};
+pub use limits::RpcConfig;
+
+use types::{
+    inspect_transaction, to_hex, to_quantity, RpcAccount, RpcBlock, RpcFeeEstimate, RpcFeeHistory,
+    RpcPooledTransaction, RpcReceipt, RpcSimulation, RpcSupply, RpcTransaction, RpcTxpool,
+    RpcTxpoolStatus,
+};
 
 /// JSON-RPC request
 #[derive(Debug, Deserialize)]
 pub struct JsonRpcRequest {
     jsonrpc: String,
     method: String,
+    #[serde(default)]
     params: serde_json::Value,
-    id: serde_json::Value,
+    /// Absent for notifications, which must not be answered
+    #[serde(default, deserialize_with = "deserialize_id")]
+    id: Option<serde_json::Value>,
+}
+
+/// Keep an explicit `"id": null` distinct from a missing id
+fn deserialize_id<'de, D>(deserializer: D) -> Result<Option<serde_json::Value>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    serde_json::Value::deserialize(deserializer).map(Some)
 }
 
 /// JSON-RPC response
@@ -31,8 +60,30 @@ pub struct JsonRpcResponse {
     id: serde_json::Value,
 }
 
+impl JsonRpcResponse {
+    /// Build a successful response
+    fn success(id: serde_json::Value, result: serde_json::Value) -> Self {
+        Self {
+            jsonrpc: "2.0".to_string(),
+            result: Some(result),
+            error: None,
+            id,
+        }
+    }
+
+    /// Build an error response
+    fn failure(id: serde_json::Value, error: JsonRpcError) -> Self {
+        Self {
+            jsonrpc: "2.0".to_string(),
+            result: None,
+            error: Some(error),
+            id,
+        }
+    }
+}
+
 /// JSON-RPC error
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, PartialEq)]
 pub struct JsonRpcError {
     code: i32,
     message: String,
@@ -40,191 +91,1595 @@ pub struct JsonRpcError {
     data: Option<serde_json::Value>,
 }
 
+impl JsonRpcError {
+    pub const PARSE_ERROR: i32 = -32700;
+    pub const INVALID_REQUEST: i32 = -32600;
+    pub const METHOD_NOT_FOUND: i32 = -32601;
+    pub const INVALID_PARAMS: i32 = -32602;
+    pub const INTERNAL_ERROR: i32 = -32603;
+    /// Implementation-defined server error, used when a transaction is rejected
+    pub const SERVER_ERROR: i32 = -32000;
+    /// The client exceeded a request limit of the server
+    pub const LIMIT_EXCEEDED: i32 = -32005;
+
+    fn new(code: i32, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            data: None,
+        }
+    }
+
+    /// The request body is not valid JSON
+    fn parse_error() -> Self {
+        Self::new(Self::PARSE_ERROR, "Parse error")
+    }
+
+    /// The JSON is not a valid request object
+    fn invalid_request(reason: impl std::fmt::Display) -> Self {
+        Self::new(
+            Self::INVALID_REQUEST,
+            format!("Invalid request: {}", reason),
+        )
+    }
+
+    fn method_not_found(method: &str) -> Self {
+        Self::new(
+            Self::METHOD_NOT_FOUND,
+            format!("Method not found: {}", method),
+        )
+    }
+
+    fn invalid_params(reason: impl std::fmt::Display) -> Self {
+        Self::new(Self::INVALID_PARAMS, format!("Invalid params: {}", reason))
+    }
+
+    /// The node failed while serving a well-formed request
+    fn internal(error: impl std::fmt::Display) -> Self {
+        Self::new(Self::INTERNAL_ERROR, format!("Internal error: {}", error))
+    }
+
+    /// The node refused the request, e.g. a transaction failed validation
+    fn server(error: impl std::fmt::Display) -> Self {
+        Self::new(Self::SERVER_ERROR, error.to_string())
+    }
+
+    fn limit_exceeded(reason: impl std::fmt::Display) -> Self {
+        Self::new(Self::LIMIT_EXCEEDED, format!("Limit exceeded: {}", reason))
+    }
+}
+
+/// Result of a single JSON-RPC method call
+type RpcResult = Result<serde_json::Value, JsonRpcError>;
+
+/// Block selector accepted by `eth_getBlockByNumber`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BlockNumber {
+    Earliest,
+    Latest,
+    Number(u64),
+}
+
+impl BlockNumber {
+    /// Parse a hex quantity, a plain number or one of the block tags
+    fn parse(value: &serde_json::Value) -> Option<Self> {
+        if let Some(number) = value.as_u64() {
+            return Some(Self::Number(number));
+        }
+
+        match value.as_str()? {
+            "earliest" => Some(Self::Earliest),
+            // There is no separate pending block and blocks are final once
+            // committed, so all of these resolve to the chain head.
+            "latest" | "pending" | "safe" | "finalized" => Some(Self::Latest),
+            s => {
+                let digits = s.strip_prefix("0x")?;
+                u64::from_str_radix(digits, 16).ok().map(Self::Number)
+            }
+        }
+    }
+}
+
 /// Create the API router
-pub fn create_router(node: Arc<Node>) -> Router {
-    Router::new()
+pub fn create_router(node: Arc<Node>, config: RpcConfig) -> anyhow::Result<Router> {
+    let cors = config.cors_layer()?;
+    let limiter = Arc::new(limits::RateLimiter::new(
+        config.rate_limit,
+        config.rate_limit_burst,
+    ));
+    let rate_limit = config.rate_limit;
+    let monitoring = monitoring::routes().with_state(node.clone());
+
+    let mut router = Router::new()
         .route("/", post(handle_jsonrpc))
+        .route("/ws", get(ws::handle_websocket))
+        .merge(rest::routes())
         .with_state(node)
+        .layer(DefaultBodyLimit::max(config.max_body_size))
+        .layer(GlobalConcurrencyLimitLayer::new(config.max_concurrency))
+        .layer(Extension(Arc::new(config)));
+    if rate_limit > 0 {
+        router = router.layer(middleware::from_fn_with_state(limiter, limits::rate_limit));
+    }
+    if let Some(cors) = cors {
+        router = router.layer(cors);
+    }
+    // Merged after the limits so probes and scrapes are answered under load
+    Ok(router.merge(monitoring))
 }
 
 /// Handle JSON-RPC requests
+///
+/// Accepts a single call or a batch. Every outcome, including malformed
+/// JSON, is reported as a JSON-RPC error object with HTTP 200; a request
+/// made up only of notifications gets an empty body.
 async fn handle_jsonrpc(
     State(node): State<Arc<Node>>,
-    Json(request): Json<JsonRpcRequest>,
-) -> Result<Json<JsonRpcResponse>, StatusCode> {
-    let response = match request.method.as_str() {
-        "eth_sendRawTransaction" => {
-            handle_send_raw_transaction(node, request.params, request.id).await
+    Extension(config): Extension<Arc<RpcConfig>>,
+    body: Bytes,
+) -> Response {
+    let payload: serde_json::Value = match serde_json::from_slice(&body) {
+        Ok(payload) => payload,
+        Err(_) => {
+            return Json(JsonRpcResponse::failure(
+                serde_json::Value::Null,
+                JsonRpcError::parse_error(),
+            ))
+            .into_response()
         }
-        "eth_getBlockByNumber" => {
-            handle_get_block_by_number(node, request.params, request.id).await
+    };
+
+    match payload {
+        serde_json::Value::Array(calls) => {
+            if let Some(error) = batch_error(&calls, config.max_batch_size) {
+                return Json(JsonRpcResponse::failure(serde_json::Value::Null, error))
+                    .into_response();
+            }
+
+            let mut responses = Vec::with_capacity(calls.len());
+            for call in calls {
+                if let Some(response) = handle_call(&node, call).await {
+                    responses.push(response);
+                }
+            }
+
+            if responses.is_empty() {
+                StatusCode::OK.into_response()
+            } else {
+                Json(responses).into_response()
+            }
         }
-        "aequitas_getAccountBalance" => {
-            handle_get_account_balance(node, request.params, request.id).await
+        call => match handle_call(&node, call).await {
+            Some(response) => Json(response).into_response(),
+            None => StatusCode::OK.into_response(),
+        },
+    }
+}
+
+/// Reject empty batches and batches over the configured size
+fn batch_error(calls: &[serde_json::Value], max_batch_size: usize) -> Option<JsonRpcError> {
+    if calls.is_empty() {
+        Some(JsonRpcError::invalid_request("empty batch"))
+    } else if calls.len() > max_batch_size {
+        Some(JsonRpcError::limit_exceeded(format!(
+            "batch of {} calls exceeds the maximum of {}",
+            calls.len(),
+            max_batch_size
+        )))
+    } else {
+        None
+    }
+}
+
+/// Validate and execute one call, returning `None` for notifications
+async fn handle_call(node: &Node, call: serde_json::Value) -> Option<JsonRpcResponse> {
+    let request = match parse_call(call) {
+        Ok(request) => request,
+        Err(response) => return Some(*response),
+    };
+
+    let result = dispatch(node, &request.method, &request.params).await;
+    respond(request.id, result)
+}
+
+/// Validate the envelope of one call
+///
+/// Invalid calls are answered even if they lack an id.
+fn parse_call(call: serde_json::Value) -> Result<JsonRpcRequest, Box<JsonRpcResponse>> {
+    // Echo the id in invalid-request errors when it can be recovered
+    let id_hint = match call.get("id") {
+        Some(id @ (serde_json::Value::String(_) | serde_json::Value::Number(_))) => id.clone(),
+        _ => serde_json::Value::Null,
+    };
+
+    let request: JsonRpcRequest = serde_json::from_value(call).map_err(|e| {
+        Box::new(JsonRpcResponse::failure(
+            id_hint.clone(),
+            JsonRpcError::invalid_request(e),
+        ))
+    })?;
+
+    if request.jsonrpc != "2.0" {
+        return Err(Box::new(JsonRpcResponse::failure(
+            id_hint,
+            JsonRpcError::invalid_request("jsonrpc must be \"2.0\""),
+        )));
+    }
+    if let Some(id) = &request.id {
+        if !(id.is_string() || id.is_number() || id.is_null()) {
+            return Err(Box::new(JsonRpcResponse::failure(
+                serde_json::Value::Null,
+                JsonRpcError::invalid_request("id must be a string, number or null"),
+            )));
         }
-        _ => Ok(JsonRpcResponse {
-            jsonrpc: "2.0".to_string(),
-            result: None,
-            error: Some(JsonRpcError {
-                code: -32601,
-                message: "Method not found".to_string(),
-                data: None,
-            }),
-            id: request.id,
-        }),
+    }
+    if !(request.params.is_array() || request.params.is_object() || request.params.is_null()) {
+        return Err(Box::new(JsonRpcResponse::failure(
+            id_hint,
+            JsonRpcError::invalid_request("params must be an array or an object"),
+        )));
+    }
+
+    Ok(request)
+}
+
+/// Wrap a method result in a response, or nothing for a notification
+fn respond(id: Option<serde_json::Value>, result: RpcResult) -> Option<JsonRpcResponse> {
+    let id = id?;
+    Some(match result {
+        Ok(result) => JsonRpcResponse::success(id, result),
+        Err(error) => JsonRpcResponse::failure(id, error),
+    })
+}
+
+/// Route a call to its method handler, recording its latency
+async fn dispatch(node: &Node, method: &str, params: &serde_json::Value) -> RpcResult {
+    let started = std::time::Instant::now();
+    let result = call_method(node, method, params).await;
+
+    // Unknown method names are grouped so callers cannot inflate the label set
+    let label = match &result {
+        Err(error) if error.code == JsonRpcError::METHOD_NOT_FOUND => "unknown",
+        _ => method,
     };
+    node.metrics().record_rpc(label, started.elapsed());
+    result
+}
 
-    response.map(|r| Json(r)).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+/// Route a method to its handler
+async fn call_method(node: &Node, method: &str, params: &serde_json::Value) -> RpcResult {
+    match method {
+        "eth_sendRawTransaction" => handle_send_raw_transaction(node, params).await,
+        "aequitas_simulateTransaction" => handle_simulate_transaction(node, params).await,
+        "eth_call" => handle_eth_call(node, params).await,
+        "eth_estimateGas" => handle_estimate_gas(node, params).await,
+        "aequitas_estimateFee" => handle_estimate_fee(node, params).await,
+        "eth_gasPrice" => handle_gas_price(node).await,
+        "eth_maxPriorityFeePerGas" => handle_max_priority_fee(node).await,
+        "eth_feeHistory" => handle_fee_history(node, params).await,
+        "eth_getBlockByNumber" => handle_get_block_by_number(node, params).await,
+        "eth_getBlockByHash" => handle_get_block_by_hash(node, params).await,
+        "eth_getTransactionByHash" => handle_get_transaction_by_hash(node, params).await,
+        "eth_getTransactionReceipt" => handle_get_transaction_receipt(node, params).await,
+        "aequitas_getAccountBalance" => handle_get_account_balance(node, params).await,
+        "aequitas_getAccount" => handle_get_account(node, params).await,
+        "aequitas_getSupply" => handle_get_supply(node).await,
+        "aequitas_getPendingTransactions" => handle_get_pending_transactions(node, params),
+        "aequitas_getPendingTransaction" => handle_get_pending_transaction(node, params),
+        "txpool_status" => handle_txpool_status(node),
+        "txpool_content" => handle_txpool_content(node, |tx| RpcTransaction::new(tx, None)),
+        "txpool_inspect" => handle_txpool_content(node, inspect_transaction),
+        "eth_getTransactionCount" => handle_get_transaction_count(node, params).await,
+        "eth_blockNumber" => handle_block_number(node).await,
+        "eth_chainId" => Ok(serde_json::json!(to_quantity(node.chain_id()))),
+        "net_version" => Ok(serde_json::json!(node.network_id().to_string())),
+        "web3_clientVersion" => Ok(serde_json::json!(client_version())),
+        _ => Err(JsonRpcError::method_not_found(method)),
+    }
 }
 
-/// Handle eth_sendRawTransaction
-async fn handle_send_raw_transaction(
-    node: Arc<Node>,
-    params: serde_json::Value,
-    id: serde_json::Value,
-) -> Result<JsonRpcResponse> {
-    let tx_hex = params
+/// Get the positional param at `index`, treating `null` as absent
+fn param(params: &serde_json::Value, index: usize) -> Option<&serde_json::Value> {
+    params
         .as_array()
-        .and_then(|arr| arr.get(0))
-        .and_then(|v| v.as_str())
-        .ok_or_else(|| anyhow::anyhow!("Invalid params"))?;
+        .and_then(|arr| arr.get(index))
+        .filter(|value| !value.is_null())
+}
+
+/// Serialize a result view
+fn to_result<T: Serialize>(value: T) -> RpcResult {
+    serde_json::to_value(value).map_err(JsonRpcError::internal)
+}
+
+/// Decode, validate and pool a hex-encoded signed transaction
+///
+/// Returns the transaction hash once the transaction has been accepted into
+/// the mempool; it is included by the next block that can execute it.
+async fn submit_raw_transaction(node: &Node, tx_hex: &str) -> Result<String, JsonRpcError> {
+    let tx = decode_raw_transaction(tx_hex)?;
+
+    let tx_hash = node
+        .submit_transaction(tx)
+        .await
+        .map_err(JsonRpcError::server)?;
+
+    Ok(to_hex(&tx_hash))
+}
 
-    // Decode hex transaction
+/// Decode a hex-encoded JSON transaction
+fn decode_raw_transaction(tx_hex: &str) -> Result<Transaction, JsonRpcError> {
     let tx_bytes = hex::decode(tx_hex.trim_start_matches("0x"))
-        .map_err(|_| anyhow::anyhow!("Invalid hex"))?;
-
-    let tx: Transaction = serde_json::from_slice(&tx_bytes)
-        .map_err(|_| anyhow::anyhow!("Invalid transaction"))?;
-
-    // Process transaction
-    match node.process_transaction(tx.clone()).await {
-        Ok(_) => {
-            let tx_hash = hex::encode(tx.hash());
-            Ok(JsonRpcResponse {
-                jsonrpc: "2.0".to_string(),
-                result: Some(serde_json::json!(format!("0x{}", tx_hash))),
-                error: None,
-                id,
-            })
+        .map_err(|_| JsonRpcError::invalid_params("transaction is not valid hex"))?;
+
+    serde_json::from_slice(&tx_bytes)
+        .map_err(|e| JsonRpcError::invalid_params(format!("invalid transaction: {}", e)))
+}
+
+/// Parse a transaction given as raw hex or as a JSON object
+///
+/// Objects may leave out the signature so that unsigned transactions can be
+/// simulated.
+fn transaction_param(
+    params: &serde_json::Value,
+    index: usize,
+) -> Result<Transaction, JsonRpcError> {
+    match param(params, index) {
+        Some(serde_json::Value::String(tx_hex)) => decode_raw_transaction(tx_hex),
+        Some(serde_json::Value::Object(object)) => {
+            let mut object = object.clone();
+            object.entry("signature").or_insert(serde_json::Value::Null);
+            serde_json::from_value(serde_json::Value::Object(object))
+                .map_err(|e| JsonRpcError::invalid_params(format!("invalid transaction: {}", e)))
         }
-        Err(e) => Ok(JsonRpcResponse {
-            jsonrpc: "2.0".to_string(),
-            result: None,
-            error: Some(JsonRpcError {
-                code: -32000,
-                message: e.to_string(),
-                data: None,
-            }),
-            id,
-        }),
+        _ => Err(JsonRpcError::invalid_params(
+            "expected a transaction object or a hex-encoded transaction",
+        )),
     }
 }
 
-/// Handle eth_getBlockByNumber
-async fn handle_get_block_by_number(
-    node: Arc<Node>,
-    params: serde_json::Value,
-    id: serde_json::Value,
-) -> Result<JsonRpcResponse> {
-    let block_num = params
+/// Handle aequitas_simulateTransaction
+async fn handle_simulate_transaction(node: &Node, params: &serde_json::Value) -> RpcResult {
+    let tx = transaction_param(params, 0)?;
+    check_latest_state(params, 1)?;
+
+    let simulation = node
+        .simulate_transaction(&tx)
+        .await
+        .map_err(JsonRpcError::internal)?;
+    to_result(RpcSimulation::new(&simulation, &tx))
+}
+
+/// Handle eth_call
+async fn handle_eth_call(node: &Node, params: &serde_json::Value) -> RpcResult {
+    let tx = transaction_param(params, 0)?;
+    check_latest_state(params, 1)?;
+
+    let result = node.call(&tx).await.map_err(JsonRpcError::server)?;
+    if !result.success {
+        return Err(JsonRpcError::server(
+            result
+                .error
+                .unwrap_or_else(|| "execution failed".to_string()),
+        ));
+    }
+    Ok(serde_json::json!(to_hex(&result.return_data)))
+}
+
+/// Handle eth_estimateGas
+async fn handle_estimate_gas(node: &Node, params: &serde_json::Value) -> RpcResult {
+    let tx = transaction_param(params, 0)?;
+    check_latest_state(params, 1)?;
+
+    let gas = node.estimate_gas(&tx).await.map_err(JsonRpcError::server)?;
+    Ok(serde_json::json!(to_quantity(gas)))
+}
+
+/// Handle aequitas_estimateFee
+async fn handle_estimate_fee(node: &Node, params: &serde_json::Value) -> RpcResult {
+    let tx = transaction_param(params, 0)?;
+    check_latest_state(params, 1)?;
+
+    let gas_used = node.estimate_gas(&tx).await.map_err(JsonRpcError::server)?;
+    let base_fee = node
+        .base_fee_per_gas()
+        .await
+        .map_err(JsonRpcError::internal)?;
+    let tip = node
+        .suggest_priority_fee()
+        .await
+        .map_err(JsonRpcError::internal)?;
+    let gas_price = base_fee.saturating_add(tip);
+    let fee = gas_used
+        .checked_mul(gas_price)
+        .ok_or_else(|| JsonRpcError::server("Fee overflows"))?;
+    to_result(RpcFeeEstimate {
+        gas_used: to_quantity(gas_used),
+        base_fee_per_gas: to_quantity(base_fee),
+        max_priority_fee_per_gas: to_quantity(tip),
+        max_fee_per_gas: to_quantity(base_fee.saturating_mul(2).saturating_add(tip)),
+        gas_price: to_quantity(gas_price),
+        fee: to_quantity(fee),
+    })
+}
+
+/// Handle eth_gasPrice
+async fn handle_gas_price(node: &Node) -> RpcResult {
+    let gas_price = node
+        .suggest_gas_price()
+        .await
+        .map_err(JsonRpcError::internal)?;
+    Ok(serde_json::json!(to_quantity(gas_price)))
+}
+
+/// Handle eth_maxPriorityFeePerGas
+async fn handle_max_priority_fee(node: &Node) -> RpcResult {
+    let tip = node
+        .suggest_priority_fee()
+        .await
+        .map_err(JsonRpcError::internal)?;
+    Ok(serde_json::json!(to_quantity(tip)))
+}
+
+/// Handle eth_feeHistory
+async fn handle_fee_history(node: &Node, params: &serde_json::Value) -> RpcResult {
+    let block_count = match param(params, 0).and_then(BlockNumber::parse) {
+        Some(BlockNumber::Number(count)) if count > 0 => count,
+        _ => {
+            return Err(JsonRpcError::invalid_params(
+                "expected a positive block count",
+            ))
+        }
+    };
+    let newest_block = match param(params, 1).and_then(BlockNumber::parse) {
+        Some(BlockNumber::Earliest) => 0,
+        Some(BlockNumber::Latest) => u64::MAX,
+        Some(BlockNumber::Number(number)) => number,
+        None => {
+            return Err(JsonRpcError::invalid_params(
+                "expected a hex block number or a block tag",
+            ))
+        }
+    };
+    let percentiles = match param(params, 2) {
+        None => Vec::new(),
+        Some(value) => reward_percentiles_param(value)?,
+    };
+
+    let history = node
+        .fee_history(block_count, newest_block, &percentiles)
+        .await
+        .map_err(JsonRpcError::internal)?;
+    to_result(RpcFeeHistory::new(&history, param(params, 2).is_some()))
+}
+
+/// Parse increasing reward percentiles between 0 and 100
+fn reward_percentiles_param(value: &serde_json::Value) -> Result<Vec<f64>, JsonRpcError> {
+    let invalid = || {
+        JsonRpcError::invalid_params("reward percentiles must be increasing numbers from 0 to 100")
+    };
+    let percentiles = value
         .as_array()
-        .and_then(|arr| arr.get(0))
+        .ok_or_else(invalid)?
+        .iter()
+        .map(|percentile| percentile.as_f64().filter(|p| (0.0..=100.0).contains(p)))
+        .collect::<Option<Vec<f64>>>()
+        .ok_or_else(invalid)?;
+    if percentiles.windows(2).any(|pair| pair[0] > pair[1]) {
+        return Err(invalid());
+    }
+    Ok(percentiles)
+}
+
+/// Handle eth_sendRawTransaction
+async fn handle_send_raw_transaction(node: &Node, params: &serde_json::Value) -> RpcResult {
+    let tx_hex = param(params, 0)
         .and_then(|v| v.as_str())
-        .ok_or_else(|| anyhow::anyhow!("Invalid params"))?;
-
-    // For MVP, only support "latest"
-    if block_num == "latest" || block_num == "\"latest\"" {
-        match node.get_latest_block().await {
-            Ok(Some(block)) => {
-                let block_json = serde_json::to_value(&block)
-                    .map_err(|_| anyhow::anyhow!("Serialization error"))?;
-                Ok(JsonRpcResponse {
-                    jsonrpc: "2.0".to_string(),
-                    result: Some(block_json),
-                    error: None,
-                    id,
-                })
-            }
-            Ok(None) => Ok(JsonRpcResponse {
-                jsonrpc: "2.0".to_string(),
-                result: Some(serde_json::Value::Null),
-                error: None,
-                id,
-            }),
-            Err(e) => Ok(JsonRpcResponse {
-                jsonrpc: "2.0".to_string(),
-                result: None,
-                error: Some(JsonRpcError {
-                    code: -32000,
-                    message: e.to_string(),
-                    data: None,
-                }),
-                id,
-            }),
-        }
-    } else {
-        Ok(JsonRpcResponse {
-            jsonrpc: "2.0".to_string(),
-            result: None,
-            error: Some(JsonRpcError {
-                code: -32602,
-                message: "Invalid params: only 'latest' is supported".to_string(),
-                data: None,
-            }),
-            id,
-        })
+        .ok_or_else(|| JsonRpcError::invalid_params("expected a hex-encoded transaction"))?;
+
+    let tx_hash = submit_raw_transaction(node, tx_hex).await?;
+    Ok(serde_json::json!(tx_hash))
+}
+
+/// Read the optional "full transactions" flag at `index` in the params
+fn full_transactions_flag(params: &serde_json::Value, index: usize) -> Option<bool> {
+    match param(params, index) {
+        None => Some(false),
+        Some(value) => value.as_bool(),
     }
 }
 
-/// Handle aequitas_getAccountBalance
-async fn handle_get_account_balance(
-    node: Arc<Node>,
-    params: serde_json::Value,
-    id: serde_json::Value,
-) -> Result<JsonRpcResponse> {
-    let address_hex = params
-        .as_array()
-        .and_then(|arr| arr.get(0))
+/// Block lookup key
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BlockId {
+    Number(BlockNumber),
+    Hash(Hash),
+}
+
+/// Look up a block and render it, with full transaction bodies if `full` is set
+async fn query_block(
+    node: &Node,
+    id: BlockId,
+    full: bool,
+) -> Result<Option<RpcBlock>, JsonRpcError> {
+    let block = match id {
+        BlockId::Number(BlockNumber::Earliest) => node.get_block_by_number(0),
+        BlockId::Number(BlockNumber::Latest) => node.get_latest_block().await,
+        BlockId::Number(BlockNumber::Number(number)) => node.get_block_by_number(number),
+        BlockId::Hash(hash) => node.get_block_by_hash(&hash),
+    };
+
+    Ok(block
+        .map_err(JsonRpcError::internal)?
+        .map(|block| RpcBlock::new(&block, full)))
+}
+
+/// Handle eth_getBlockByNumber
+async fn handle_get_block_by_number(node: &Node, params: &serde_json::Value) -> RpcResult {
+    let block_number = param(params, 0)
+        .and_then(BlockNumber::parse)
+        .ok_or_else(|| {
+            JsonRpcError::invalid_params(
+                "expected a hex block number or one of \
+                 'earliest', 'latest', 'pending', 'safe', 'finalized'",
+            )
+        })?;
+
+    let full = full_transactions_flag(params, 1)
+        .ok_or_else(|| JsonRpcError::invalid_params("full transactions flag must be a boolean"))?;
+
+    to_result(query_block(node, BlockId::Number(block_number), full).await?)
+}
+
+/// Handle eth_getBlockByHash
+async fn handle_get_block_by_hash(node: &Node, params: &serde_json::Value) -> RpcResult {
+    let hash = hash_param(params, 0)?;
+
+    let full = full_transactions_flag(params, 1)
+        .ok_or_else(|| JsonRpcError::invalid_params("full transactions flag must be a boolean"))?;
+
+    to_result(query_block(node, BlockId::Hash(hash), full).await?)
+}
+
+/// Decode a `0x`-prefixed 32-byte hash
+fn decode_hash(hash_hex: &str) -> Result<Hash, JsonRpcError> {
+    let hash_bytes = hex::decode(hash_hex.trim_start_matches("0x"))
+        .map_err(|_| JsonRpcError::invalid_params("hash is not valid hex"))?;
+
+    Hash::try_from(hash_bytes).map_err(|_| JsonRpcError::invalid_params("hash must be 32 bytes"))
+}
+
+/// Read a 32-byte hash at `index` in the params
+fn hash_param(params: &serde_json::Value, index: usize) -> Result<Hash, JsonRpcError> {
+    let hash_hex = param(params, index)
         .and_then(|v| v.as_str())
-        .ok_or_else(|| anyhow::anyhow!("Invalid params"))?;
+        .ok_or_else(|| JsonRpcError::invalid_params("expected a hash"))?;
+
+    decode_hash(hash_hex)
+}
+
+/// Look up a committed transaction with its location
+fn query_transaction(node: &Node, hash: &Hash) -> Result<Option<RpcTransaction>, JsonRpcError> {
+    Ok(node
+        .get_transaction(hash)
+        .map_err(JsonRpcError::internal)?
+        .map(|(tx, block, index)| {
+            RpcTransaction::new(&tx, Some((&block.hash(), block.index(), index)))
+        }))
+}
+
+/// Look up the receipt of a committed transaction
+fn query_receipt(node: &Node, hash: &Hash) -> Result<Option<RpcReceipt>, JsonRpcError> {
+    Ok(node
+        .get_receipt(hash)
+        .map_err(JsonRpcError::internal)?
+        .map(|receipt| RpcReceipt::from(&receipt)))
+}
+
+/// Handle eth_getTransactionByHash
+async fn handle_get_transaction_by_hash(node: &Node, params: &serde_json::Value) -> RpcResult {
+    let hash = hash_param(params, 0)?;
+    to_result(query_transaction(node, &hash)?)
+}
 
-    // Decode address
+/// Handle eth_getTransactionReceipt
+async fn handle_get_transaction_receipt(node: &Node, params: &serde_json::Value) -> RpcResult {
+    let hash = hash_param(params, 0)?;
+    to_result(query_receipt(node, &hash)?)
+}
+
+/// Decode a `0x`-prefixed 32-byte address
+fn decode_address(address_hex: &str) -> Result<Address, JsonRpcError> {
     let address_bytes = hex::decode(address_hex.trim_start_matches("0x"))
-        .map_err(|_| anyhow::anyhow!("Invalid hex"))?;
+        .map_err(|_| JsonRpcError::invalid_params("address is not valid hex"))?;
 
-    if address_bytes.len() != 32 {
-        return Ok(JsonRpcResponse {
-            jsonrpc: "2.0".to_string(),
-            result: None,
-            error: Some(JsonRpcError {
-                code: -32602,
-                message: "Invalid address length".to_string(),
-                data: None,
-            }),
-            id,
+    address_bytes
+        .try_into()
+        .map_err(|_| JsonRpcError::invalid_params("address must be 32 bytes"))
+}
+
+/// Read a 32-byte address at `index` in the params
+fn address_param(params: &serde_json::Value, index: usize) -> Result<Address, JsonRpcError> {
+    let address_hex = param(params, index)
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| JsonRpcError::invalid_params("expected an address"))?;
+
+    decode_address(address_hex)
+}
+
+/// Check that an optional block tag at `index` refers to the current state
+///
+/// Only the latest state is kept, so historical queries are rejected.
+fn check_latest_state(params: &serde_json::Value, index: usize) -> Result<(), JsonRpcError> {
+    match param(params, index) {
+        None => Ok(()),
+        Some(value) => match value.as_str() {
+            Some("latest" | "pending" | "safe" | "finalized") => Ok(()),
+            _ => Err(JsonRpcError::invalid_params(
+                "only the latest state is available",
+            )),
+        },
+    }
+}
+
+/// Handle aequitas_getAccountBalance
+async fn handle_get_account_balance(node: &Node, params: &serde_json::Value) -> RpcResult {
+    let address = address_param(params, 0)?;
+
+    let balance = node.get_balance(&address).map_err(JsonRpcError::internal)?;
+    Ok(serde_json::json!(format!("0x{:x}", balance)))
+}
+
+/// Handle eth_getTransactionCount
+async fn handle_get_transaction_count(node: &Node, params: &serde_json::Value) -> RpcResult {
+    let address = address_param(params, 0)?;
+    check_latest_state(params, 1)?;
+
+    let nonce = node.get_nonce(&address).map_err(JsonRpcError::internal)?;
+    Ok(serde_json::json!(to_quantity(nonce)))
+}
+
+/// Read an account's state at the chain head
+async fn query_account(node: &Node, address: &Address) -> Result<RpcAccount, JsonRpcError> {
+    let account = node
+        .get_account(address)
+        .await
+        .map_err(JsonRpcError::internal)?;
+    Ok(RpcAccount::from(&account))
+}
+
+/// Handle aequitas_getAccount
+async fn handle_get_account(node: &Node, params: &serde_json::Value) -> RpcResult {
+    let address = address_param(params, 0)?;
+    to_result(query_account(node, &address).await?)
+}
+
+/// Handle aequitas_getPendingTransactions
+fn handle_get_pending_transactions(node: &Node, params: &serde_json::Value) -> RpcResult {
+    let address = address_param(params, 0)?;
+    let transactions: Vec<RpcTransaction> = node
+        .pending_transactions(&address)
+        .iter()
+        .map(|tx| RpcTransaction::new(tx, None))
+        .collect();
+    to_result(transactions)
+}
+
+/// Handle aequitas_getPendingTransaction
+fn handle_get_pending_transaction(node: &Node, params: &serde_json::Value) -> RpcResult {
+    let hash = hash_param(params, 0)?;
+    let pooled = node
+        .pooled_transaction(&hash)
+        .map_err(JsonRpcError::internal)?;
+    to_result(pooled.as_ref().map(RpcPooledTransaction::new))
+}
+
+/// Handle txpool_status
+fn handle_txpool_status(node: &Node) -> RpcResult {
+    let status = node.mempool_status().map_err(JsonRpcError::internal)?;
+    to_result(RpcTxpoolStatus::new(&status))
+}
+
+/// Handle txpool_content and txpool_inspect, which differ in how each
+/// transaction is rendered
+fn handle_txpool_content<T: Serialize>(node: &Node, view: impl Fn(&Transaction) -> T) -> RpcResult {
+    let content = node.mempool_content().map_err(JsonRpcError::internal)?;
+    to_result(RpcTxpool::new(&content, view))
+}
+
+/// Handle aequitas_getSupply
+async fn handle_get_supply(node: &Node) -> RpcResult {
+    let (supply, block_number) = node.get_supply().await.map_err(JsonRpcError::internal)?;
+    to_result(RpcSupply::new(&supply, block_number))
+}
+
+/// Handle eth_blockNumber
+async fn handle_block_number(node: &Node) -> RpcResult {
+    let number = node.block_number().await.map_err(JsonRpcError::internal)?;
+    Ok(serde_json::json!(to_quantity(number)))
+}
+
+/// Version string reported by `web3_clientVersion`
+fn client_version() -> String {
+    format!(
+        "yotquitas-node/v{}/{}-{}",
+        env!("CARGO_PKG_VERSION"),
+        std::env::consts::OS,
+        std::env::consts::ARCH
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::node::test_utils::{signed_transfer, test_node, unsigned_transfer};
+    use axum::body::Body;
+    use axum::http::Request;
+    use serde_json::json;
+    use tempfile::TempDir;
+    use tower::ServiceExt;
+
+    /// Post a raw body to the JSON-RPC endpoint, returning status and body
+    async fn post_raw(node: Arc<Node>, body: &str) -> (StatusCode, String) {
+        let (status, _, body) = send(node, RpcConfig::default(), rpc_request(body)).await;
+        (status, body)
+    }
+
+    fn rpc_request(body: &str) -> Request<Body> {
+        Request::post("/")
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    }
+
+    /// Send a request through a router with the given limits
+    async fn send(
+        node: Arc<Node>,
+        config: RpcConfig,
+        request: Request<Body>,
+    ) -> (StatusCode, axum::http::HeaderMap, String) {
+        let response = create_router(node, config)
+            .unwrap()
+            .oneshot(request)
+            .await
+            .unwrap();
+        let status = response.status();
+        let headers = response.headers().clone();
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, headers, String::from_utf8(bytes.to_vec()).unwrap())
+    }
+
+    /// Post a JSON body and parse the JSON reply
+    async fn post_json(node: Arc<Node>, body: serde_json::Value) -> serde_json::Value {
+        let (status, body) = post_raw(node, &body.to_string()).await;
+        assert_eq!(status, StatusCode::OK);
+        serde_json::from_str(&body).unwrap()
+    }
+
+    fn setup() -> (TempDir, Arc<Node>) {
+        let temp_dir = TempDir::new().unwrap();
+        let (node, _) = test_node(&temp_dir).unwrap();
+        (temp_dir, Arc::new(node))
+    }
+
+    #[test]
+    fn test_parse_block_number() {
+        assert_eq!(
+            BlockNumber::parse(&json!("0x0")),
+            Some(BlockNumber::Number(0))
+        );
+        assert_eq!(
+            BlockNumber::parse(&json!("0x1f")),
+            Some(BlockNumber::Number(31))
+        );
+        assert_eq!(BlockNumber::parse(&json!(7)), Some(BlockNumber::Number(7)));
+        assert_eq!(
+            BlockNumber::parse(&json!("earliest")),
+            Some(BlockNumber::Earliest)
+        );
+        for tag in ["latest", "pending", "safe", "finalized"] {
+            assert_eq!(BlockNumber::parse(&json!(tag)), Some(BlockNumber::Latest));
+        }
+        assert_eq!(BlockNumber::parse(&json!("12")), None);
+        assert_eq!(BlockNumber::parse(&json!("0xzz")), None);
+        assert_eq!(BlockNumber::parse(&json!(true)), None);
+    }
+
+    #[test]
+    fn test_full_transactions_flag() {
+        assert_eq!(full_transactions_flag(&json!(["latest"]), 1), Some(false));
+        assert_eq!(
+            full_transactions_flag(&json!(["latest", true]), 1),
+            Some(true)
+        );
+        assert_eq!(full_transactions_flag(&json!(["latest", "yes"]), 1), None);
+    }
+
+    #[test]
+    fn test_address_param() {
+        let address = format!("0x{}", "ab".repeat(32));
+        assert_eq!(address_param(&json!([address]), 0), Ok([0xab; 32]));
+        assert!(address_param(&json!(["0xabcd"]), 0).is_err());
+        assert!(address_param(&json!(["0xzz"]), 0).is_err());
+        assert!(address_param(&json!([]), 0).is_err());
+    }
+
+    #[test]
+    fn test_check_latest_state() {
+        assert!(check_latest_state(&json!(["0x00"]), 1).is_ok());
+        assert!(check_latest_state(&json!(["0x00", "latest"]), 1).is_ok());
+        assert!(check_latest_state(&json!(["0x00", "pending"]), 1).is_ok());
+        assert!(check_latest_state(&json!(["0x00", "0x1"]), 1).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_single_call() {
+        let (_dir, node) = setup();
+        let response = post_json(
+            node,
+            json!({"jsonrpc": "2.0", "method": "eth_chainId", "params": [], "id": 1}),
+        )
+        .await;
+        assert_eq!(
+            response,
+            json!({"jsonrpc": "2.0", "result": "0x539", "id": 1})
+        );
+    }
+
+    #[tokio::test]
+    async fn test_params_may_be_omitted() {
+        let (_dir, node) = setup();
+        let response = post_json(
+            node,
+            json!({"jsonrpc": "2.0", "method": "eth_blockNumber", "id": "a"}),
+        )
+        .await;
+        assert_eq!(response["result"], "0x0");
+        assert_eq!(response["id"], "a");
+    }
+
+    #[tokio::test]
+    async fn test_node_info_methods() {
+        let (_dir, node) = setup();
+        let call = |method: &str| {
+            post_json(
+                node.clone(),
+                json!({"jsonrpc": "2.0", "method": method, "params": [], "id": 1}),
+            )
+        };
+        assert_eq!(call("eth_blockNumber").await["result"], "0x0");
+        assert_eq!(call("net_version").await["result"], "1337");
+        let version = call("web3_clientVersion").await;
+        let version = version["result"].as_str().unwrap();
+        assert!(
+            version.starts_with(&format!("yotquitas-node/v{}/", env!("CARGO_PKG_VERSION"))),
+            "{}",
+            version
+        );
+    }
+
+    #[tokio::test]
+    async fn test_account_methods() {
+        let temp_dir = TempDir::new().unwrap();
+        let (node, signing_key) = test_node(&temp_dir).unwrap();
+        let node = Arc::new(node);
+        let sender = to_hex(&yotquitas_core::public_key_address(
+            &signing_key.verifying_key(),
+        ));
+        let nonce_request = json!({"jsonrpc": "2.0", "method": "eth_getTransactionCount", "params": [sender, "latest"], "id": 1});
+        let account_request =
+            json!({"jsonrpc": "2.0", "method": "aequitas_getAccount", "params": [sender], "id": 1});
+
+        assert_eq!(
+            post_json(node.clone(), nonce_request.clone()).await["result"],
+            "0x0"
+        );
+        let tx = signed_transfer(&signing_key, [9u8; 32], 100, 0);
+        node.process_transaction(tx).await.unwrap();
+
+        assert_eq!(
+            post_json(node.clone(), nonce_request).await["result"],
+            "0x1"
+        );
+        let response = post_json(node.clone(), account_request).await;
+        assert_eq!(
+            response["result"],
+            json!({
+                "address": sender,
+                "balance": "0xeef2c",
+                "lockedBalance": "0x0",
+                "unlockedBalance": "0xeef2c",
+                "nonce": "0x1",
+                "blockNumber": "0x1",
+            })
+        );
+        // Unknown accounts are empty rather than missing
+        let response = post_json(
+            node,
+            json!({"jsonrpc": "2.0", "method": "aequitas_getAccount", "params": [to_hex(&[3u8; 32])], "id": 1}),
+        )
+        .await;
+        assert_eq!(response["result"]["balance"], "0x0");
+        assert_eq!(response["result"]["nonce"], "0x0");
+    }
+
+    #[tokio::test]
+    async fn test_get_sealed_transaction_and_receipt() {
+        let temp_dir = TempDir::new().unwrap();
+        let (node, signing_key) = test_node(&temp_dir).unwrap();
+        let node = Arc::new(node);
+        let tx = signed_transfer(&signing_key, [9u8; 32], 100, 0);
+        let receipt = node.process_transaction(tx.clone()).await.unwrap();
+        let hash = to_hex(&tx.hash());
+        let block_hash = to_hex(&receipt.block_hash);
+        let call = |method: &str, hash: &str| {
+            post_json(
+                node.clone(),
+                json!({"jsonrpc": "2.0", "method": method, "params": [hash], "id": 1}),
+            )
+        };
+
+        let response = call("eth_getTransactionByHash", &hash).await;
+        let result = &response["result"];
+        assert_eq!(result["hash"], hash);
+        assert_eq!(result["from"], to_hex(&receipt.from));
+        assert_eq!(result["nonce"], "0x0");
+        assert_eq!(result["blockHash"], block_hash);
+        assert_eq!(result["blockNumber"], "0x1");
+        assert_eq!(result["transactionIndex"], "0x0");
+
+        let response = call("eth_getTransactionReceipt", &hash).await;
+        let result = &response["result"];
+        assert_eq!(result["transactionHash"], hash);
+        assert_eq!(result["blockHash"], block_hash);
+        assert_eq!(result["blockNumber"], "0x1");
+        assert_eq!(result["transactionIndex"], "0x0");
+        assert_eq!(result["status"], "0x1");
+        assert_eq!(result["gasUsed"], "0x52b0");
+        assert_eq!(result["feePaid"], "0x52b0");
+
+        // Unknown transactions are null
+        let unknown = to_hex(&[7u8; 32]);
+        for method in ["eth_getTransactionByHash", "eth_getTransactionReceipt"] {
+            let response = call(method, &unknown).await;
+            assert_eq!(response["result"], serde_json::Value::Null, "{}", method);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_parse_error() {
+        let (_dir, node) = setup();
+        let (status, body) = post_raw(node, r#"{"jsonrpc": "2.0", "method": "#).await;
+        assert_eq!(status, StatusCode::OK);
+        let response: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(response["error"]["code"], JsonRpcError::PARSE_ERROR);
+        assert_eq!(response["id"], serde_json::Value::Null);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_request() {
+        let (_dir, node) = setup();
+
+        // Method is not a string
+        let response = post_json(
+            node.clone(),
+            json!({"jsonrpc": "2.0", "method": 1, "id": 3}),
+        )
+        .await;
+        assert_eq!(response["error"]["code"], JsonRpcError::INVALID_REQUEST);
+        assert_eq!(response["id"], 3);
+
+        // Wrong protocol version
+        let response = post_json(
+            node.clone(),
+            json!({"jsonrpc": "1.0", "method": "eth_chainId", "id": 4}),
+        )
+        .await;
+        assert_eq!(response["error"]["code"], JsonRpcError::INVALID_REQUEST);
+        assert_eq!(response["id"], 4);
+
+        // Missing protocol version
+        let response = post_json(node.clone(), json!({"method": "eth_chainId", "id": 5})).await;
+        assert_eq!(response["error"]["code"], JsonRpcError::INVALID_REQUEST);
+
+        // Params must be structured
+        let response = post_json(
+            node.clone(),
+            json!({"jsonrpc": "2.0", "method": "eth_chainId", "params": "bar", "id": 6}),
+        )
+        .await;
+        assert_eq!(response["error"]["code"], JsonRpcError::INVALID_REQUEST);
+
+        // Not an object at all
+        let response = post_json(node, json!(1)).await;
+        assert_eq!(response["error"]["code"], JsonRpcError::INVALID_REQUEST);
+        assert_eq!(response["id"], serde_json::Value::Null);
+    }
+
+    #[tokio::test]
+    async fn test_method_not_found() {
+        let (_dir, node) = setup();
+        let response = post_json(
+            node,
+            json!({"jsonrpc": "2.0", "method": "eth_mining", "params": [], "id": 1}),
+        )
+        .await;
+        assert_eq!(response["error"]["code"], JsonRpcError::METHOD_NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_params() {
+        let (_dir, node) = setup();
+        for (method, params) in [
+            ("eth_getBlockByNumber", json!(["bogus"])),
+            ("eth_getBlockByNumber", json!([])),
+            ("eth_getBlockByNumber", json!(["latest", "yes"])),
+            ("eth_getBlockByHash", json!(["0x1234"])),
+            ("eth_getTransactionReceipt", json!([42])),
+            ("eth_sendRawTransaction", json!(["0xzz"])),
+            ("eth_sendRawTransaction", json!(["0x1234"])),
+            ("aequitas_getAccountBalance", json!({"address": "0x00"})),
+            (
+                "eth_getTransactionCount",
+                json!([format!("0x{}", "00".repeat(32)), "0x1"]),
+            ),
+        ] {
+            let response = post_json(
+                node.clone(),
+                json!({"jsonrpc": "2.0", "method": method, "params": params, "id": 1}),
+            )
+            .await;
+            assert_eq!(
+                response["error"]["code"],
+                JsonRpcError::INVALID_PARAMS,
+                "{} {}",
+                method,
+                params
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rejected_transaction_is_server_error() {
+        let temp_dir = TempDir::new().unwrap();
+        let (node, signing_key) = test_node(&temp_dir).unwrap();
+        // A funded sender offering less than the base fee fails validation
+        let tx = Transaction {
+            max_fee_per_gas: 0,
+            ..unsigned_transfer(&signing_key, [0u8; 32], 1, 0)
+        }
+        .sign(&signing_key);
+        let raw = hex::encode(serde_json::to_vec(&tx).unwrap());
+        let response = post_json(
+            Arc::new(node),
+            json!({"jsonrpc": "2.0", "method": "eth_sendRawTransaction", "params": [raw], "id": 1}),
+        )
+        .await;
+        assert_eq!(response["error"]["code"], JsonRpcError::SERVER_ERROR);
+        assert_eq!(
+            response["error"]["message"],
+            "Max fee per gas 0 is below the base fee of 1"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_notification_gets_no_response() {
+        let (_dir, node) = setup();
+        let (status, body) = post_raw(
+            node,
+            r#"{"jsonrpc": "2.0", "method": "eth_chainId", "params": []}"#,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(body.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_null_id_is_not_a_notification() {
+        let (_dir, node) = setup();
+        let response = post_json(
+            node,
+            json!({"jsonrpc": "2.0", "method": "eth_chainId", "id": null}),
+        )
+        .await;
+        assert_eq!(response["result"], "0x539");
+        assert_eq!(response["id"], serde_json::Value::Null);
+    }
+
+    #[tokio::test]
+    async fn test_batch() {
+        let (_dir, node) = setup();
+        let response = post_json(
+            node,
+            json!([
+                {"jsonrpc": "2.0", "method": "eth_chainId", "id": 1},
+                {"jsonrpc": "2.0", "method": "net_version"},
+                {"jsonrpc": "2.0", "method": "eth_unknown", "id": 2},
+                {"foo": "boo"},
+                {"jsonrpc": "2.0", "method": "net_version", "id": "three"}
+            ]),
+        )
+        .await;
+
+        let responses = response.as_array().unwrap();
+        assert_eq!(responses.len(), 4);
+        assert_eq!(responses[0]["result"], "0x539");
+        assert_eq!(
+            responses[1]["error"]["code"],
+            JsonRpcError::METHOD_NOT_FOUND
+        );
+        assert_eq!(responses[2]["error"]["code"], JsonRpcError::INVALID_REQUEST);
+        assert_eq!(responses[3]["result"], "1337");
+        assert_eq!(responses[3]["id"], "three");
+    }
+
+    #[tokio::test]
+    async fn test_empty_batch() {
+        let (_dir, node) = setup();
+        let response = post_json(node, json!([])).await;
+        assert_eq!(response["error"]["code"], JsonRpcError::INVALID_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_batch_of_notifications() {
+        let (_dir, node) = setup();
+        let (status, body) = post_raw(
+            node,
+            r#"[{"jsonrpc": "2.0", "method": "eth_chainId"}, {"jsonrpc": "2.0", "method": "net_version"}]"#,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(body.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_batch_size_limit() {
+        let (_dir, node) = setup();
+        let config = RpcConfig {
+            max_batch_size: 2,
+            ..RpcConfig::default()
+        };
+        let call = json!({"jsonrpc": "2.0", "method": "eth_chainId", "id": 1});
+
+        let batch = json!([call, call]).to_string();
+        let (_, _, body) = send(node.clone(), config.clone(), rpc_request(&batch)).await;
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&body)
+                .unwrap()
+                .as_array()
+                .unwrap()
+                .len(),
+            2
+        );
+
+        let batch = json!([call, call, call]).to_string();
+        let (_, _, body) = send(node, config, rpc_request(&batch)).await;
+        let response: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(response["error"]["code"], JsonRpcError::LIMIT_EXCEEDED);
+    }
+
+    #[tokio::test]
+    async fn test_body_size_limit() {
+        let (_dir, node) = setup();
+        let config = RpcConfig {
+            max_body_size: 64,
+            ..RpcConfig::default()
+        };
+        let raw = "ab".repeat(64);
+        let body =
+            json!({"jsonrpc": "2.0", "method": "eth_sendRawTransaction", "params": [raw], "id": 1});
+        let (status, _, _) = send(node, config, rpc_request(&body.to_string())).await;
+        assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit() {
+        let (_dir, node) = setup();
+        let config = RpcConfig {
+            rate_limit: 1,
+            rate_limit_burst: 2,
+            ..RpcConfig::default()
+        };
+        let router = create_router(node, config).unwrap();
+        let body = json!({"jsonrpc": "2.0", "method": "eth_chainId", "id": 1}).to_string();
+        let peer: std::net::SocketAddr = "10.0.0.1:30303".parse().unwrap();
+
+        let mut statuses = Vec::new();
+        for _ in 0..3 {
+            let mut request = rpc_request(&body);
+            request
+                .extensions_mut()
+                .insert(axum::extract::ConnectInfo(peer));
+            let response = router.clone().oneshot(request).await.unwrap();
+            statuses.push(response.status());
+        }
+        assert_eq!(
+            statuses,
+            [
+                StatusCode::OK,
+                StatusCode::OK,
+                StatusCode::TOO_MANY_REQUESTS
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_monitoring_is_not_rate_limited() {
+        let (_dir, node) = setup();
+        let config = RpcConfig {
+            rate_limit: 1,
+            rate_limit_burst: 1,
+            ..RpcConfig::default()
+        };
+        let router = create_router(node, config).unwrap();
+        let peer: std::net::SocketAddr = "10.0.0.1:30303".parse().unwrap();
+        let request = |uri: &str| {
+            let mut request = Request::get(uri).body(Body::empty()).unwrap();
+            request
+                .extensions_mut()
+                .insert(axum::extract::ConnectInfo(peer));
+            request
+        };
+
+        let body = json!({"jsonrpc": "2.0", "method": "eth_chainId", "id": 1}).to_string();
+        for expected in [StatusCode::OK, StatusCode::TOO_MANY_REQUESTS] {
+            let mut rpc = rpc_request(&body);
+            rpc.extensions_mut()
+                .insert(axum::extract::ConnectInfo(peer));
+            let response = router.clone().oneshot(rpc).await.unwrap();
+            assert_eq!(response.status(), expected);
+        }
+
+        for uri in ["/health", "/ready", "/metrics"] {
+            let response = router.clone().oneshot(request(uri)).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK, "{}", uri);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_cors() {
+        let (_dir, node) = setup();
+        let config = RpcConfig {
+            cors: vec!["https://wallet.example".to_string()],
+            ..RpcConfig::default()
+        };
+        let body = json!({"jsonrpc": "2.0", "method": "eth_chainId", "id": 1}).to_string();
+
+        let mut allowed = rpc_request(&body);
+        allowed
+            .headers_mut()
+            .insert("origin", "https://wallet.example".parse().unwrap());
+        let (_, headers, _) = send(node.clone(), config.clone(), allowed).await;
+        assert_eq!(
+            headers["access-control-allow-origin"],
+            "https://wallet.example"
+        );
+
+        let mut denied = rpc_request(&body);
+        denied
+            .headers_mut()
+            .insert("origin", "https://evil.example".parse().unwrap());
+        let (_, headers, _) = send(node.clone(), config, denied).await;
+        assert!(headers.get("access-control-allow-origin").is_none());
+
+        // Without configured origins no CORS headers are sent
+        let mut request = rpc_request(&body);
+        request
+            .headers_mut()
+            .insert("origin", "https://wallet.example".parse().unwrap());
+        let (_, headers, _) = send(node, RpcConfig::default(), request).await;
+        assert!(headers.get("access-control-allow-origin").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_simulate_transaction() {
+        let temp_dir = TempDir::new().unwrap();
+        let (node, signing_key) = test_node(&temp_dir).unwrap();
+        let node = Arc::new(node);
+        let sender = yotquitas_core::sha256(&signing_key.verifying_key().to_bytes());
+        let unsigned = unsigned_transfer(&signing_key, [9u8; 32], 100, 0);
+
+        // Unsigned transactions can be simulated as JSON objects
+        let response = post_json(
+            node.clone(),
+            json!({"jsonrpc": "2.0", "method": "aequitas_simulateTransaction", "params": [unsigned], "id": 1}),
+        )
+        .await;
+        let result = &response["result"];
+        assert_eq!(result["valid"], true);
+        assert_eq!(result["success"], true);
+        assert_eq!(result["gasUsed"], "0x52b0");
+        let state_diff = result["stateDiff"].as_array().unwrap();
+        assert_eq!(state_diff.len(), 2);
+        // Diffs are sorted by address, and the sender's address is random
+        let diff_of = |address: &[u8; 32]| {
+            state_diff
+                .iter()
+                .find(|diff| diff["address"] == to_hex(address))
+                .unwrap()
+        };
+        assert_eq!(diff_of(&[9u8; 32])["balance"]["to"], "0x64");
+        assert_eq!(diff_of(&sender)["nonce"]["to"], "0x1");
+
+        // Nothing was persisted
+        assert_eq!(node.get_balance(&[9u8; 32]).unwrap(), 0);
+        assert_eq!(node.get_nonce(&sender).unwrap(), 0);
+        assert_eq!(node.block_number().await.unwrap(), 0);
+
+        // A transaction the node would reject is reported, not executed
+        let mut stale = unsigned.clone();
+        stale.nonce = 3;
+        let raw = hex::encode(serde_json::to_vec(&stale.sign(&signing_key)).unwrap());
+        let response = post_json(
+            node,
+            json!({"jsonrpc": "2.0", "method": "aequitas_simulateTransaction", "params": [raw], "id": 1}),
+        )
+        .await;
+        assert_eq!(response["result"]["valid"], false);
+        assert_eq!(
+            response["result"]["error"],
+            "Invalid nonce: expected 0, got 3"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_eth_call() {
+        let temp_dir = TempDir::new().unwrap();
+        let (node, signing_key) = test_node(&temp_dir).unwrap();
+        let node = Arc::new(node);
+        let call = Transaction::new(
+            signing_key.verifying_key(),
+            yotquitas_core::TransactionPayload::MoveCall {
+                module: "coin".to_string(),
+                function: "balance".to_string(),
+                args: vec![],
+            },
+            0,
+            0,
+            0,
+            0,
+        );
+
+        let response = post_json(
+            node.clone(),
+            json!({"jsonrpc": "2.0", "method": "eth_call", "params": [call, "latest"], "id": 1}),
+        )
+        .await;
+        assert_eq!(response["result"], "0x");
+
+        let transfer = unsigned_transfer(&signing_key, [9u8; 32], 100, 0);
+        let response = post_json(
+            node,
+            json!({"jsonrpc": "2.0", "method": "eth_call", "params": [transfer], "id": 1}),
+        )
+        .await;
+        assert_eq!(response["error"]["code"], JsonRpcError::SERVER_ERROR);
+    }
+
+    #[tokio::test]
+    async fn test_estimate_fee() {
+        let temp_dir = TempDir::new().unwrap();
+        let (node, signing_key) = test_node(&temp_dir).unwrap();
+        let tx = Transaction {
+            gas_limit: 0,
+            max_fee_per_gas: 0,
+            ..unsigned_transfer(&signing_key, [9u8; 32], 100, 0)
+        };
+        let node = Arc::new(node);
+
+        let response = post_json(
+            node.clone(),
+            json!({"jsonrpc": "2.0", "method": "eth_estimateGas", "params": [tx], "id": 1}),
+        )
+        .await;
+        assert_eq!(response["result"], "0x52b0");
+
+        let response = post_json(
+            node.clone(),
+            json!({"jsonrpc": "2.0", "method": "aequitas_estimateFee", "params": [tx, "latest"], "id": 1}),
+        )
+        .await;
+        assert_eq!(
+            response["result"],
+            json!({
+                "gasUsed": "0x52b0",
+                "baseFeePerGas": "0x1",
+                "maxPriorityFeePerGas": "0x1",
+                "maxFeePerGas": "0x3",
+                "gasPrice": "0x2",
+                "fee": "0xa560",
+            })
+        );
+
+        let response = post_json(
+            node.clone(),
+            json!({"jsonrpc": "2.0", "method": "eth_gasPrice", "id": 1}),
+        )
+        .await;
+        assert_eq!(response["result"], "0x2");
+
+        let response = post_json(
+            node.clone(),
+            json!({"jsonrpc": "2.0", "method": "eth_maxPriorityFeePerGas", "id": 1}),
+        )
+        .await;
+        assert_eq!(response["result"], "0x1");
+    }
+
+    #[tokio::test]
+    async fn test_fee_history() {
+        let (_dir, node) = setup();
+
+        let response = post_json(
+            node.clone(),
+            json!({"jsonrpc": "2.0", "method": "eth_feeHistory", "params": ["0x4", "latest", [25, 75]], "id": 1}),
+        )
+        .await;
+        assert_eq!(
+            response["result"],
+            json!({
+                "oldestBlock": "0x0",
+                "baseFeePerGas": ["0x1", "0x1"],
+                "gasUsedRatio": [0.0],
+                "reward": [["0x0", "0x0"]],
+            })
+        );
+
+        let response = post_json(
+            node.clone(),
+            json!({"jsonrpc": "2.0", "method": "eth_feeHistory", "params": [1, "earliest"], "id": 1}),
+        )
+        .await;
+        assert!(response["result"].get("reward").is_none());
+
+        for params in [
+            json!([0, "latest"]),
+            json!(["0x1", "latest", [75, 25]]),
+            json!(["0x1"]),
+        ] {
+            let response = post_json(
+                node.clone(),
+                json!({"jsonrpc": "2.0", "method": "eth_feeHistory", "params": params, "id": 1}),
+            )
+            .await;
+            assert_eq!(response["error"]["code"], JsonRpcError::INVALID_PARAMS);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_replace_pending_transaction() {
+        let temp_dir = TempDir::new().unwrap();
+        let (node, signing_key) = test_node(&temp_dir).unwrap();
+        let node = Arc::new(node);
+        let sender = yotquitas_core::sha256(&signing_key.verifying_key().to_bytes());
+        let send = |max_fee_per_gas, max_priority_fee_per_gas| {
+            let tx = Transaction {
+                max_fee_per_gas,
+                max_priority_fee_per_gas,
+                ..unsigned_transfer(&signing_key, [9u8; 32], 100, 0)
+            }
+            .sign(&signing_key);
+            let raw = hex::encode(serde_json::to_vec(&tx).unwrap());
+            json!({"jsonrpc": "2.0", "method": "eth_sendRawTransaction", "params": [raw], "id": 1})
+        };
+        let pending = json!({
+            "jsonrpc": "2.0",
+            "method": "aequitas_getPendingTransactions",
+            "params": [to_hex(&sender)],
+            "id": 1,
         });
+
+        let first = post_json(node.clone(), send(10, 2)).await;
+        assert!(first["result"].is_string());
+        // A fee bump below 10% is refused
+        let underpriced = post_json(node.clone(), send(10, 3)).await;
+        assert_eq!(underpriced["error"]["code"], JsonRpcError::SERVER_ERROR);
+        let replacement = post_json(node.clone(), send(11, 3)).await;
+
+        let response = post_json(node.clone(), pending.clone()).await;
+        let transactions = response["result"].as_array().unwrap();
+        assert_eq!(transactions.len(), 1);
+        assert_eq!(transactions[0]["hash"], replacement["result"]);
+        assert_eq!(transactions[0]["maxFeePerGas"], "0xb");
+        assert_eq!(transactions[0]["blockNumber"], serde_json::Value::Null);
+
+        node.produce_block().await.unwrap();
+        let response = post_json(node, pending).await;
+        assert_eq!(response["result"], json!([]));
     }
 
-    let address: Address = address_bytes
-        .try_into()
-        .map_err(|_| anyhow::anyhow!("Invalid address"))?;
+    #[tokio::test]
+    async fn test_txpool_methods() {
+        let temp_dir = TempDir::new().unwrap();
+        let (node, signing_key) = test_node(&temp_dir).unwrap();
+        let node = Arc::new(node);
+        let sender = to_hex(&yotquitas_core::sha256(
+            &signing_key.verifying_key().to_bytes(),
+        ));
+        let transfer = |nonce| {
+            Transaction {
+                max_fee_per_gas: 10,
+                max_priority_fee_per_gas: 1,
+                ..unsigned_transfer(&signing_key, [9u8; 32], 100, nonce)
+            }
+            .sign(&signing_key)
+        };
+        let call = |method: &str, params: serde_json::Value| json!({"jsonrpc": "2.0", "method": method, "params": params, "id": 1});
+        node.submit_transaction(transfer(0)).await.unwrap();
+        // Nonce 2 waits for nonce 1
+        let queued = node.submit_transaction(transfer(2)).await.unwrap();
 
-    match node.get_balance(&address) {
-        Ok(balance) => Ok(JsonRpcResponse {
-            jsonrpc: "2.0".to_string(),
-            result: Some(serde_json::json!(format!("0x{:x}", balance))),
-            error: None,
-            id,
-        }),
-        Err(e) => Ok(JsonRpcResponse {
-            jsonrpc: "2.0".to_string(),
-            result: None,
-            error: Some(JsonRpcError {
-                code: -32000,
-                message: e.to_string(),
-                data: None,
-            }),
-            id,
-        }),
+        let status = post_json(node.clone(), call("txpool_status", json!([]))).await;
+        assert_eq!(status["result"], json!({"pending": "0x1", "queued": "0x1"}));
+
+        let content = post_json(node.clone(), call("txpool_content", json!([]))).await;
+        assert_eq!(content["result"]["pending"][&sender]["0"]["nonce"], "0x0");
+        assert_eq!(
+            content["result"]["queued"][&sender]["2"]["hash"],
+            to_hex(&queued)
+        );
+
+        let inspect = post_json(node.clone(), call("txpool_inspect", json!([]))).await;
+        assert_eq!(
+            inspect["result"]["queued"][&sender]["2"],
+            format!("{}: 100 + 25000 gas × 10", to_hex(&[9u8; 32]))
+        );
+
+        let pooled = post_json(
+            node.clone(),
+            call("aequitas_getPendingTransaction", json!([to_hex(&queued)])),
+        )
+        .await;
+        assert_eq!(pooled["result"]["status"], "queued");
+        assert_eq!(pooled["result"]["nonce"], "0x2");
+        let unknown = post_json(
+            node.clone(),
+            call(
+                "aequitas_getPendingTransaction",
+                json!([to_hex(&[0u8; 32])]),
+            ),
+        )
+        .await;
+        assert_eq!(unknown["result"], serde_json::Value::Null);
+
+        node.produce_block().await.unwrap();
+        node.submit_transaction(transfer(1)).await.unwrap();
+        let pooled = post_json(
+            node.clone(),
+            call("aequitas_getPendingTransaction", json!([to_hex(&queued)])),
+        )
+        .await;
+        assert_eq!(pooled["result"]["status"], "pending");
+        let status = post_json(node, call("txpool_status", json!([]))).await;
+        assert_eq!(status["result"], json!({"pending": "0x2", "queued": "0x0"}));
+    }
+
+    #[tokio::test]
+    async fn test_get_supply() {
+        let temp_dir = TempDir::new().unwrap();
+        let (node, signing_key) = test_node(&temp_dir).unwrap();
+        let node = Arc::new(node);
+        let request = json!({"jsonrpc": "2.0", "method": "aequitas_getSupply", "id": 1});
+
+        let response = post_json(node.clone(), request.clone()).await;
+        assert_eq!(
+            response["result"],
+            json!({
+                "minted": "0xf4240",
+                "burned": "0x0",
+                "totalSupply": "0xf4240",
+                "blockNumber": "0x0",
+            })
+        );
+
+        let tx = signed_transfer(&signing_key, [9u8; 32], 100, 0);
+        node.process_transaction(tx).await.unwrap();
+
+        // The transfer burns 21168 gas at the initial base fee of 1
+        let response = post_json(node, request).await;
+        assert_eq!(response["result"]["burned"], "0x52b0");
+        assert_eq!(response["result"]["totalSupply"], "0xeef90");
+        assert_eq!(response["result"]["blockNumber"], "0x1");
     }
 }
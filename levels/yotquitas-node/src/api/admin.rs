@@ -0,0 +1,343 @@
+//! Operator-only `admin_*` JSON-RPC methods
+//!
+//! Served by a separate router meant for a loopback listener. Every request
+//! must carry `Authorization: Bearer <token>` with the token from the admin
+//! token file.
+
+use super::types::to_quantity;
+use super::{client_version, param, parse_call, respond, JsonRpcError, JsonRpcResponse, RpcResult};
+use crate::node::Node;
+use anyhow::Result;
+use axum::{
+    body::Bytes,
+    extract::{Request, State},
+    http::{header, StatusCode},
+    middleware::{self, Next},
+    response::{IntoResponse, Json, Response},
+    routing::post,
+    Router,
+};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Applies a tracing filter directive such as `info` or `yotquitas_node=debug`
+pub type LogLevelHandle = Arc<dyn Fn(&str) -> Result<()> + Send + Sync>;
+
+/// Settings of the admin endpoint
+#[derive(Clone)]
+pub struct AdminConfig {
+    /// Bearer token every request must present
+    pub token: String,
+    /// Directory that `admin_snapshot` writes database snapshots into
+    pub snapshot_dir: PathBuf,
+    /// Changes the log filter at runtime, if the subscriber supports it
+    pub log_level: Option<LogLevelHandle>,
+}
+
+/// Shared state of the admin handlers
+struct AdminState {
+    node: Arc<Node>,
+    config: AdminConfig,
+}
+
+/// Create the admin router
+pub fn create_admin_router(node: Arc<Node>, config: AdminConfig) -> Router {
+    let state = Arc::new(AdminState { node, config });
+    Router::new()
+        .route("/", post(handle_admin))
+        .layer(middleware::from_fn_with_state(state.clone(), authenticate))
+        .with_state(state)
+}
+
+/// Read the admin token from `path`, generating a random one if the file is missing
+pub fn load_or_create_token(path: &Path) -> Result<String> {
+    if path.exists() {
+        let token = std::fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("Failed to read admin token file {:?}: {}", path, e))?
+            .trim()
+            .to_string();
+        if token.is_empty() {
+            anyhow::bail!("Admin token file {:?} is empty", path);
+        }
+        return Ok(token);
+    }
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let token = hex::encode(rand::random::<[u8; 32]>());
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options
+        .open(path)
+        .map_err(|e| anyhow::anyhow!("Failed to create admin token file {:?}: {}", path, e))?;
+    file.write_all(token.as_bytes())?;
+
+    tracing::info!("Generated admin token in {:?}", path);
+    Ok(token)
+}
+
+/// Reject requests without the admin bearer token
+async fn authenticate(
+    State(state): State<Arc<AdminState>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let presented = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+
+    match presented {
+        Some(token) if constant_time_eq(token.as_bytes(), state.config.token.as_bytes()) => {
+            next.run(request).await
+        }
+        _ => (
+            StatusCode::UNAUTHORIZED,
+            [(header::WWW_AUTHENTICATE, "Bearer")],
+        )
+            .into_response(),
+    }
+}
+
+/// Compare secrets without leaking the position of the first difference
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// Handle one admin call; batches are not accepted
+async fn handle_admin(State(state): State<Arc<AdminState>>, body: Bytes) -> Response {
+    let call: serde_json::Value = match serde_json::from_slice(&body) {
+        Ok(call) => call,
+        Err(_) => {
+            return Json(JsonRpcResponse::failure(
+                serde_json::Value::Null,
+                JsonRpcError::parse_error(),
+            ))
+            .into_response()
+        }
+    };
+    if call.is_array() {
+        return Json(JsonRpcResponse::failure(
+            serde_json::Value::Null,
+            JsonRpcError::invalid_request("batches are not supported by the admin endpoint"),
+        ))
+        .into_response();
+    }
+
+    let request = match parse_call(call) {
+        Ok(request) => request,
+        Err(response) => return Json(*response).into_response(),
+    };
+    let result = dispatch_admin(&state, &request.method, &request.params).await;
+    match respond(request.id, result) {
+        Some(response) => Json(response).into_response(),
+        None => StatusCode::OK.into_response(),
+    }
+}
+
+/// Route an admin method to its handler
+async fn dispatch_admin(state: &AdminState, method: &str, params: &serde_json::Value) -> RpcResult {
+    let node = &state.node;
+    match method {
+        "admin_nodeInfo" => handle_node_info(node).await,
+        "admin_peers" => Ok(serde_json::json!(node.peers())),
+        "admin_addPeer" => Ok(serde_json::json!(node.add_peer(&peer_param(params)?))),
+        "admin_removePeer" => Ok(serde_json::json!(node.remove_peer(&peer_param(params)?))),
+        "admin_setLogLevel" => handle_set_log_level(state, params),
+        "admin_pauseBlockProduction" => Ok(serde_json::json!(node.pause_block_production())),
+        "admin_resumeBlockProduction" => Ok(serde_json::json!(node.resume_block_production())),
+        "admin_snapshot" => handle_snapshot(state).await,
+        "admin_flushMempool" => node
+            .flush_mempool()
+            .map(|flushed| serde_json::json!(flushed))
+            .map_err(JsonRpcError::internal),
+        _ => Err(JsonRpcError::method_not_found(method)),
+    }
+}
+
+/// Parse a `host:port` peer address
+fn peer_param(params: &serde_json::Value) -> Result<String, JsonRpcError> {
+    let peer = param(params, 0)
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| JsonRpcError::invalid_params("expected a peer address"))?;
+    match peer.rsplit_once(':') {
+        Some((host, port)) if !host.is_empty() && port.parse::<u16>().is_ok() => {
+            Ok(peer.to_string())
+        }
+        _ => Err(JsonRpcError::invalid_params(
+            "peer address must be host:port",
+        )),
+    }
+}
+
+/// Handle admin_nodeInfo
+async fn handle_node_info(node: &Node) -> RpcResult {
+    let block_number = node.block_number().await.map_err(JsonRpcError::internal)?;
+    Ok(serde_json::json!({
+        "clientVersion": client_version(),
+        "chainId": to_quantity(node.chain_id()),
+        "networkId": node.network_id().to_string(),
+        "blockNumber": to_quantity(block_number),
+        "paused": node.is_paused(),
+        "peers": node.peers().len(),
+    }))
+}
+
+/// Handle admin_setLogLevel
+fn handle_set_log_level(state: &AdminState, params: &serde_json::Value) -> RpcResult {
+    let directive = param(params, 0)
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| JsonRpcError::invalid_params("expected a log filter such as 'debug'"))?;
+    let set_log_level = state
+        .config
+        .log_level
+        .as_ref()
+        .ok_or_else(|| JsonRpcError::server("log level cannot be changed at runtime"))?;
+    set_log_level(directive).map_err(JsonRpcError::invalid_params)?;
+    tracing::info!("Log level set to {}", directive);
+    Ok(serde_json::json!(true))
+}
+
+/// Handle admin_snapshot
+async fn handle_snapshot(state: &AdminState) -> RpcResult {
+    let (path, height) = state
+        .node
+        .snapshot(&state.config.snapshot_dir)
+        .await
+        .map_err(JsonRpcError::internal)?;
+    Ok(serde_json::json!({
+        "path": path.display().to_string(),
+        "blockNumber": to_quantity(height),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::node::test_utils::test_node;
+    use axum::body::Body;
+    use serde_json::json;
+    use std::sync::Mutex;
+    use tempfile::TempDir;
+    use tower::ServiceExt;
+
+    const TOKEN: &str = "secret";
+
+    async fn call(
+        router: &Router,
+        token: Option<&str>,
+        method: &str,
+        params: serde_json::Value,
+    ) -> (StatusCode, serde_json::Value) {
+        let mut request = axum::http::Request::post("/").header("content-type", "application/json");
+        if let Some(token) = token {
+            request = request.header("authorization", format!("Bearer {}", token));
+        }
+        let body = json!({"jsonrpc": "2.0", "method": method, "params": params, "id": 1});
+        let response = router
+            .clone()
+            .oneshot(request.body(Body::from(body.to_string())).unwrap())
+            .await
+            .unwrap();
+        let status = response.status();
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (
+            status,
+            serde_json::from_slice(&bytes).unwrap_or(serde_json::Value::Null),
+        )
+    }
+
+    #[test]
+    fn test_load_or_create_token() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("admin.token");
+        let token = load_or_create_token(&path).unwrap();
+        assert_eq!(token.len(), 64);
+        assert_eq!(load_or_create_token(&path).unwrap(), token);
+    }
+
+    #[tokio::test]
+    async fn test_admin_methods() {
+        let temp_dir = TempDir::new().unwrap();
+        let (node, _) = test_node(&temp_dir).unwrap();
+        let node = Arc::new(node);
+        let levels = Arc::new(Mutex::new(Vec::new()));
+        let recorded = levels.clone();
+        let router = create_admin_router(
+            node.clone(),
+            AdminConfig {
+                token: TOKEN.to_string(),
+                snapshot_dir: temp_dir.path().join("snapshots"),
+                log_level: Some(Arc::new(move |level: &str| {
+                    recorded.lock().unwrap().push(level.to_string());
+                    Ok(())
+                })),
+            },
+        );
+
+        let (status, _) = call(&router, None, "admin_peers", json!([])).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let (status, _) = call(&router, Some("wrong"), "admin_peers", json!([])).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+        let (status, added) = call(
+            &router,
+            Some(TOKEN),
+            "admin_addPeer",
+            json!(["10.0.0.2:30303"]),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(added["result"], true);
+        let (_, peers) = call(&router, Some(TOKEN), "admin_peers", json!([])).await;
+        assert_eq!(peers["result"], json!(["10.0.0.2:30303"]));
+        let (_, invalid) = call(&router, Some(TOKEN), "admin_addPeer", json!(["nowhere"])).await;
+        assert_eq!(invalid["error"]["code"], JsonRpcError::INVALID_PARAMS);
+
+        let (_, paused) = call(
+            &router,
+            Some(TOKEN),
+            "admin_pauseBlockProduction",
+            json!([]),
+        )
+        .await;
+        assert_eq!(paused["result"], true);
+        assert!(node.is_paused());
+        let (_, info) = call(&router, Some(TOKEN), "admin_nodeInfo", json!([])).await;
+        assert_eq!(info["result"]["paused"], true);
+        assert_eq!(info["result"]["peers"], 1);
+        call(
+            &router,
+            Some(TOKEN),
+            "admin_resumeBlockProduction",
+            json!([]),
+        )
+        .await;
+        assert!(!node.is_paused());
+
+        let (_, set) = call(&router, Some(TOKEN), "admin_setLogLevel", json!(["debug"])).await;
+        assert_eq!(set["result"], true);
+        assert_eq!(*levels.lock().unwrap(), ["debug"]);
+
+        let (_, snapshot) = call(&router, Some(TOKEN), "admin_snapshot", json!([])).await;
+        assert_eq!(snapshot["result"]["blockNumber"], "0x0");
+        assert!(Path::new(snapshot["result"]["path"].as_str().unwrap()).exists());
+
+        let (_, flushed) = call(&router, Some(TOKEN), "admin_flushMempool", json!([])).await;
+        assert_eq!(flushed["result"], 0);
+
+        let (_, unknown) = call(&router, Some(TOKEN), "eth_chainId", json!([])).await;
+        assert_eq!(unknown["error"]["code"], JsonRpcError::METHOD_NOT_FOUND);
+    }
+}
@@ -0,0 +1,287 @@
+//! Protection for the RPC server: CORS, per-IP rate limiting and size caps
+
+use super::{JsonRpcError, JsonRpcResponse};
+use anyhow::Result;
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::{header, HeaderValue, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Json, Response},
+};
+use std::collections::{BTreeMap, HashMap};
+use std::net::{IpAddr, Ipv6Addr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tower_http::cors::{AllowOrigin, CorsLayer};
+
+/// Clients tracked by the rate limiter; beyond this the least recently seen
+/// client is forgotten
+pub const MAX_TRACKED_CLIENTS: usize = 65_536;
+
+/// Limits applied to the RPC server, taken from the `[node]` config section
+#[derive(Debug, Clone)]
+pub struct RpcConfig {
+    /// Allowed browser origins; empty disables CORS, `"*"` allows any origin
+    pub cors: Vec<String>,
+    /// Largest accepted HTTP body or WebSocket message, in bytes
+    pub max_body_size: usize,
+    /// Most calls accepted in one JSON-RPC batch
+    pub max_batch_size: usize,
+    /// Requests served at once; further requests wait for a free slot
+    pub max_concurrency: usize,
+    /// Sustained requests per second allowed from one IP; 0 disables rate limiting
+    pub rate_limit: u32,
+    /// Requests one IP may make in a burst above the sustained rate
+    pub rate_limit_burst: u32,
+}
+
+impl Default for RpcConfig {
+    fn default() -> Self {
+        Self {
+            cors: Vec::new(),
+            max_body_size: 1024 * 1024,
+            max_batch_size: 100,
+            max_concurrency: 256,
+            rate_limit: 0,
+            rate_limit_burst: 0,
+        }
+    }
+}
+
+impl RpcConfig {
+    /// Build the CORS layer for the configured origins, if any
+    pub fn cors_layer(&self) -> Result<Option<CorsLayer>> {
+        if self.cors.is_empty() {
+            return Ok(None);
+        }
+
+        let origin = if self.cors.iter().any(|origin| origin == "*") {
+            AllowOrigin::any()
+        } else {
+            let origins = self
+                .cors
+                .iter()
+                .map(|origin| {
+                    HeaderValue::from_str(origin)
+                        .map_err(|_| anyhow::anyhow!("Invalid CORS origin: {}", origin))
+                })
+                .collect::<Result<Vec<_>>>()?;
+            AllowOrigin::list(origins)
+        };
+
+        Ok(Some(
+            CorsLayer::new()
+                .allow_origin(origin)
+                .allow_methods([Method::GET, Method::POST])
+                .allow_headers([header::CONTENT_TYPE, header::AUTHORIZATION]),
+        ))
+    }
+}
+
+/// Token bucket of one client
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+    /// Position of the client in the recently seen order
+    last_seen: u64,
+}
+
+/// Buckets of the tracked clients, ordered by when they were last seen
+#[derive(Debug, Default)]
+struct Buckets {
+    by_client: HashMap<IpAddr, Bucket>,
+    by_last_seen: BTreeMap<u64, IpAddr>,
+    next_seen: u64,
+}
+
+/// Per-IP token-bucket rate limiter
+///
+/// Each client starts with a full bucket of `burst` tokens that refills at
+/// `rate` tokens per second; a request spends one token. IPv6 clients are
+/// limited per /64 network, the smallest block usually assigned to one
+/// host, so rotating addresses within it does not earn fresh buckets. At
+/// most [`MAX_TRACKED_CLIENTS`] are tracked.
+#[derive(Debug)]
+pub struct RateLimiter {
+    rate: f64,
+    burst: f64,
+    buckets: Mutex<Buckets>,
+}
+
+impl RateLimiter {
+    /// Create a limiter; a burst below one request is raised to the rate
+    pub fn new(rate: u32, burst: u32) -> Self {
+        Self {
+            rate: rate as f64,
+            burst: burst.max(rate).max(1) as f64,
+            buckets: Mutex::new(Buckets::default()),
+        }
+    }
+
+    /// Try to spend a token for `ip` at time `now`
+    pub fn check(&self, ip: IpAddr, now: Instant) -> bool {
+        self.check_tracking(ip, now, MAX_TRACKED_CLIENTS)
+    }
+
+    fn check_tracking(&self, ip: IpAddr, now: Instant, max_clients: usize) -> bool {
+        let client = client_key(ip);
+        let mut buckets = self.buckets.lock().unwrap();
+        let Buckets {
+            by_client,
+            by_last_seen,
+            next_seen,
+        } = &mut *buckets;
+        let last_seen = *next_seen;
+        *next_seen += 1;
+
+        if !by_client.contains_key(&client) && by_client.len() >= max_clients {
+            if let Some((_, oldest)) = by_last_seen.pop_first() {
+                by_client.remove(&oldest);
+            }
+        }
+
+        let bucket = by_client.entry(client).or_insert(Bucket {
+            tokens: self.burst,
+            updated: now,
+            last_seen,
+        });
+        by_last_seen.remove(&bucket.last_seen);
+        by_last_seen.insert(last_seen, client);
+        bucket.last_seen = last_seen;
+
+        let elapsed = now.saturating_duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.rate).min(self.burst);
+        bucket.updated = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// Address a client is limited by: IPv4 addresses as they are, IPv6
+/// addresses by their /64 network
+fn client_key(ip: IpAddr) -> IpAddr {
+    match ip {
+        IpAddr::V4(_) => ip,
+        IpAddr::V6(v6) => match v6.to_ipv4_mapped() {
+            Some(v4) => IpAddr::V4(v4),
+            None => IpAddr::V6(Ipv6Addr::from(u128::from(v6) & !u128::from(u64::MAX))),
+        },
+    }
+}
+
+/// Middleware rejecting clients that exceed their rate limit with HTTP 429
+///
+/// Requests without a peer address (e.g. in-process tests) are not limited.
+pub async fn rate_limit(
+    State(limiter): State<Arc<RateLimiter>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let peer = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip());
+
+    if let Some(ip) = peer {
+        if !limiter.check(ip, Instant::now()) {
+            let response = JsonRpcResponse::failure(
+                serde_json::Value::Null,
+                JsonRpcError::limit_exceeded("rate limit exceeded"),
+            );
+            return (StatusCode::TOO_MANY_REQUESTS, Json(response)).into_response();
+        }
+    }
+
+    next.run(request).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_token_bucket() {
+        let limiter = RateLimiter::new(2, 3);
+        let client: IpAddr = [10, 0, 0, 1].into();
+        let other: IpAddr = [10, 0, 0, 2].into();
+        let start = Instant::now();
+
+        // The full burst is available up front
+        for _ in 0..3 {
+            assert!(limiter.check(client, start));
+        }
+        assert!(!limiter.check(client, start));
+        // Other clients have their own bucket
+        assert!(limiter.check(other, start));
+
+        // Two tokens per second refill, capped at the burst
+        assert!(limiter.check(client, start + Duration::from_millis(500)));
+        assert!(!limiter.check(client, start + Duration::from_millis(500)));
+        let later = start + Duration::from_secs(60);
+        for _ in 0..3 {
+            assert!(limiter.check(client, later));
+        }
+        assert!(!limiter.check(client, later));
+    }
+
+    #[test]
+    fn test_tracked_clients_are_bounded() {
+        let limiter = RateLimiter::new(1, 1);
+        let now = Instant::now();
+        let (a, b, c): (IpAddr, IpAddr, IpAddr) = (
+            [10, 0, 0, 1].into(),
+            [10, 0, 0, 2].into(),
+            [10, 0, 0, 3].into(),
+        );
+
+        assert!(limiter.check_tracking(a, now, 2));
+        assert!(limiter.check_tracking(b, now, 2));
+        // `a` was seen last, so `b` is forgotten to make room for `c`
+        assert!(!limiter.check_tracking(a, now, 2));
+        assert!(limiter.check_tracking(c, now, 2));
+        assert_eq!(limiter.buckets.lock().unwrap().by_client.len(), 2);
+        assert!(!limiter.check_tracking(a, now, 2));
+        assert!(limiter.check_tracking(b, now, 2));
+        assert!(!limiter.check_tracking(b, now, 2));
+    }
+
+    #[test]
+    fn test_ipv6_clients_share_their_network_bucket() {
+        let limiter = RateLimiter::new(1, 1);
+        let now = Instant::now();
+        let first: IpAddr = "2001:db8:1:2::1".parse().unwrap();
+        let same_network: IpAddr = "2001:db8:1:2:ffff::9".parse().unwrap();
+        let other_network: IpAddr = "2001:db8:1:3::1".parse().unwrap();
+
+        assert!(limiter.check(first, now));
+        assert!(!limiter.check(same_network, now));
+        assert!(limiter.check(other_network, now));
+
+        let mapped: IpAddr = "::ffff:10.0.0.1".parse().unwrap();
+        assert_eq!(client_key(mapped), IpAddr::from([10, 0, 0, 1]));
+    }
+
+    #[test]
+    fn test_cors_layer() {
+        assert!(RpcConfig::default().cors_layer().unwrap().is_none());
+
+        let any = RpcConfig {
+            cors: vec!["*".to_string()],
+            ..RpcConfig::default()
+        };
+        assert!(any.cors_layer().unwrap().is_some());
+
+        let invalid = RpcConfig {
+            cors: vec!["http://bad\norigin".to_string()],
+            ..RpcConfig::default()
+        };
+        assert!(invalid.cors_layer().is_err());
+    }
+}
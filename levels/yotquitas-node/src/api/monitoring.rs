@@ -0,0 +1,123 @@
+//! Liveness, readiness and Prometheus metrics endpoints
+
+use crate::node::Node;
+use axum::{
+    extract::State,
+    http::{header, StatusCode},
+    response::{IntoResponse, Json, Response},
+    routing::get,
+    Router,
+};
+use std::sync::Arc;
+
+/// Content type of the Prometheus text exposition format
+const PROMETHEUS_CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// Monitoring routes, merged into the main router outside its limits
+pub fn routes() -> Router<Arc<Node>> {
+    Router::new()
+        .route("/health", get(health))
+        .route("/ready", get(ready))
+        .route("/metrics", get(metrics))
+}
+
+/// Liveness: the process serves requests and the database answers reads
+async fn health(State(node): State<Arc<Node>>) -> Response {
+    match node.check_database() {
+        Ok(()) => Json(serde_json::json!({ "status": "ok" })).into_response(),
+        Err(e) => (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(serde_json::json!({ "status": "error", "error": e.to_string() })),
+        )
+            .into_response(),
+    }
+}
+
+/// Readiness: the chain has a head and blocks are being produced
+///
+/// A single node is always in sync with itself, so readiness only depends on
+/// the chain head and on block production not being paused.
+async fn ready(State(node): State<Arc<Node>>) -> Response {
+    let block_number = node.block_number().await;
+    let paused = node.is_paused();
+    let ready = block_number.is_ok() && !paused;
+
+    let status = if ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    let body = serde_json::json!({
+        "ready": ready,
+        "blockNumber": block_number.ok(),
+        "blockProductionPaused": paused,
+    });
+    (status, Json(body)).into_response()
+}
+
+/// Prometheus scrape endpoint
+async fn metrics(State(node): State<Arc<Node>>) -> Response {
+    match node.gauges().await {
+        Ok(gauges) => (
+            [(header::CONTENT_TYPE, PROMETHEUS_CONTENT_TYPE)],
+            node.metrics().render(&gauges),
+        )
+            .into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::{create_router, RpcConfig};
+    use crate::node::test_utils::test_node;
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+    use std::sync::Arc;
+    use tempfile::TempDir;
+    use tower::ServiceExt;
+
+    async fn get(router: &axum::Router, uri: &str) -> (StatusCode, String) {
+        let response = router
+            .clone()
+            .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let status = response.status();
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, String::from_utf8(bytes.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_probes_and_metrics() {
+        let temp_dir = TempDir::new().unwrap();
+        let (node, _) = test_node(&temp_dir).unwrap();
+        let node = Arc::new(node);
+        let router = create_router(node.clone(), RpcConfig::default()).unwrap();
+
+        assert_eq!(get(&router, "/health").await.0, StatusCode::OK);
+        assert_eq!(get(&router, "/ready").await.0, StatusCode::OK);
+        node.pause_block_production();
+        let (status, body) = get(&router, "/ready").await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert!(body.contains("\"blockProductionPaused\":true"));
+
+        let request = Request::post("/")
+            .header("content-type", "application/json")
+            .body(Body::from(
+                r#"{"jsonrpc": "2.0", "method": "eth_blockNumber", "id": 1}"#,
+            ))
+            .unwrap();
+        router.clone().oneshot(request).await.unwrap();
+
+        let (status, text) = get(&router, "/metrics").await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(text.contains("yotquitas_block_height 0\n"));
+        assert!(text.contains("yotquitas_block_production_paused 1\n"));
+        assert!(text.contains(
+            "yotquitas_rpc_request_duration_seconds_count{method=\"eth_blockNumber\"} 1\n"
+        ));
+    }
+}
@@ -0,0 +1,372 @@
+//! RESTful HTTP API under `/v1`
+//!
+//! The routes are thin wrappers around the same query functions used by the
+//! JSON-RPC methods, so both transports always return the same views.
+
+use super::types::{BlockTransactions, RpcAccount, RpcBlock, RpcReceipt, RpcTransaction};
+use super::{
+    decode_address, decode_hash, query_account, query_block, query_receipt, query_transaction,
+    submit_raw_transaction, BlockId, BlockNumber, JsonRpcError,
+};
+use crate::node::Node;
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Json, Response},
+    routing::{get, post},
+    Router,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use utoipa::{OpenApi, ToSchema};
+
+/// OpenAPI description of the REST API, generated from the handler and view types
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "Yotquitas Node REST API"),
+    paths(
+        get_block,
+        get_transaction,
+        get_receipt,
+        get_account,
+        submit_transaction
+    ),
+    components(schemas(
+        RpcBlock,
+        BlockTransactions,
+        RpcTransaction,
+        RpcReceipt,
+        RpcAccount,
+        SubmitTransaction,
+        SubmittedTransaction,
+        ErrorResponse
+    ))
+)]
+pub struct ApiDoc;
+
+/// REST routes, to be merged into the main router
+pub fn routes() -> Router<Arc<Node>> {
+    Router::new()
+        .route("/v1/blocks/:id", get(get_block))
+        .route("/v1/transactions", post(submit_transaction))
+        .route("/v1/transactions/:hash", get(get_transaction))
+        .route("/v1/transactions/:hash/receipt", get(get_receipt))
+        .route("/v1/accounts/:address", get(get_account))
+        .route("/v1/openapi.json", get(openapi))
+}
+
+/// Error body returned by the REST API
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ErrorResponse {
+    pub error: String,
+}
+
+/// Error converted into an HTTP status and [`ErrorResponse`]
+#[derive(Debug)]
+struct RestError {
+    status: StatusCode,
+    message: String,
+}
+
+impl RestError {
+    fn not_found(what: &str) -> Self {
+        Self {
+            status: StatusCode::NOT_FOUND,
+            message: format!("{} not found", what),
+        }
+    }
+}
+
+impl From<JsonRpcError> for RestError {
+    fn from(error: JsonRpcError) -> Self {
+        let status = match error.code {
+            JsonRpcError::INVALID_PARAMS => StatusCode::BAD_REQUEST,
+            JsonRpcError::SERVER_ERROR => StatusCode::UNPROCESSABLE_ENTITY,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        Self {
+            status,
+            message: error.message,
+        }
+    }
+}
+
+impl IntoResponse for RestError {
+    fn into_response(self) -> Response {
+        (
+            self.status,
+            Json(ErrorResponse {
+                error: self.message,
+            }),
+        )
+            .into_response()
+    }
+}
+
+/// Parse a block height (decimal or hex), a block tag or a block hash
+fn parse_block_id(id: &str) -> Result<BlockId, JsonRpcError> {
+    if id.starts_with("0x") && id.len() == 66 {
+        return decode_hash(id).map(BlockId::Hash);
+    }
+    if let Ok(number) = id.parse::<u64>() {
+        return Ok(BlockId::Number(BlockNumber::Number(number)));
+    }
+    BlockNumber::parse(&serde_json::json!(id))
+        .map(BlockId::Number)
+        .ok_or_else(|| {
+            JsonRpcError::invalid_params("expected a block height, block tag or block hash")
+        })
+}
+
+/// Query string of `GET /v1/blocks/{id}`
+#[derive(Debug, Deserialize)]
+struct BlockQuery {
+    #[serde(default)]
+    full: bool,
+}
+
+/// Get a block by height, tag or hash
+#[utoipa::path(
+    get,
+    path = "/v1/blocks/{id}",
+    params(
+        ("id" = String, Path, description = "Block height (decimal or 0x hex), tag such as 'latest', or 0x block hash"),
+        ("full" = Option<bool>, Query, description = "Include full transaction bodies instead of hashes")
+    ),
+    responses(
+        (status = 200, description = "Block found", body = RpcBlock),
+        (status = 400, description = "Malformed block id", body = ErrorResponse),
+        (status = 404, description = "Block not found", body = ErrorResponse)
+    )
+)]
+async fn get_block(
+    State(node): State<Arc<Node>>,
+    Path(id): Path<String>,
+    Query(query): Query<BlockQuery>,
+) -> Result<Json<RpcBlock>, RestError> {
+    let id = parse_block_id(&id)?;
+    query_block(&node, id, query.full)
+        .await?
+        .map(Json)
+        .ok_or_else(|| RestError::not_found("Block"))
+}
+
+/// Get a committed transaction by hash
+#[utoipa::path(
+    get,
+    path = "/v1/transactions/{hash}",
+    params(("hash" = String, Path, description = "0x transaction hash")),
+    responses(
+        (status = 200, description = "Transaction found", body = RpcTransaction),
+        (status = 400, description = "Malformed hash", body = ErrorResponse),
+        (status = 404, description = "Transaction not found", body = ErrorResponse)
+    )
+)]
+async fn get_transaction(
+    State(node): State<Arc<Node>>,
+    Path(hash): Path<String>,
+) -> Result<Json<RpcTransaction>, RestError> {
+    let hash = decode_hash(&hash)?;
+    query_transaction(&node, &hash)?
+        .map(Json)
+        .ok_or_else(|| RestError::not_found("Transaction"))
+}
+
+/// Get the receipt of a committed transaction
+#[utoipa::path(
+    get,
+    path = "/v1/transactions/{hash}/receipt",
+    params(("hash" = String, Path, description = "0x transaction hash")),
+    responses(
+        (status = 200, description = "Receipt found", body = RpcReceipt),
+        (status = 400, description = "Malformed hash", body = ErrorResponse),
+        (status = 404, description = "Receipt not found", body = ErrorResponse)
+    )
+)]
+async fn get_receipt(
+    State(node): State<Arc<Node>>,
+    Path(hash): Path<String>,
+) -> Result<Json<RpcReceipt>, RestError> {
+    let hash = decode_hash(&hash)?;
+    query_receipt(&node, &hash)?
+        .map(Json)
+        .ok_or_else(|| RestError::not_found("Receipt"))
+}
+
+/// Get an account's balance and nonce
+#[utoipa::path(
+    get,
+    path = "/v1/accounts/{address}",
+    params(("address" = String, Path, description = "0x account address")),
+    responses(
+        (status = 200, description = "Account state at the chain head", body = RpcAccount),
+        (status = 400, description = "Malformed address", body = ErrorResponse)
+    )
+)]
+async fn get_account(
+    State(node): State<Arc<Node>>,
+    Path(address): Path<String>,
+) -> Result<Json<RpcAccount>, RestError> {
+    let address = decode_address(&address)?;
+    Ok(Json(query_account(&node, &address).await?))
+}
+
+/// Body of `POST /v1/transactions`
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SubmitTransaction {
+    /// Hex-encoded JSON of a signed transaction, as for `eth_sendRawTransaction`
+    pub raw_transaction: String,
+}
+
+/// Reply to `POST /v1/transactions`
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SubmittedTransaction {
+    pub hash: String,
+}
+
+/// Submit a signed transaction
+#[utoipa::path(
+    post,
+    path = "/v1/transactions",
+    request_body = SubmitTransaction,
+    responses(
+        (status = 200, description = "Transaction accepted", body = SubmittedTransaction),
+        (status = 400, description = "Malformed transaction", body = ErrorResponse),
+        (status = 422, description = "Transaction rejected", body = ErrorResponse)
+    )
+)]
+async fn submit_transaction(
+    State(node): State<Arc<Node>>,
+    Json(request): Json<SubmitTransaction>,
+) -> Result<Json<SubmittedTransaction>, RestError> {
+    let hash = submit_raw_transaction(&node, &request.raw_transaction).await?;
+    Ok(Json(SubmittedTransaction { hash }))
+}
+
+/// Serve the OpenAPI document
+async fn openapi() -> Json<utoipa::openapi::OpenApi> {
+    Json(ApiDoc::openapi())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::{create_router, RpcConfig};
+    use crate::node::test_utils::{signed_transfer, test_node};
+    use axum::body::Body;
+    use axum::http::Request;
+    use tempfile::TempDir;
+    use tower::ServiceExt;
+
+    async fn send(node: Arc<Node>, request: Request<Body>) -> (StatusCode, serde_json::Value) {
+        let response = create_router(node, RpcConfig::default())
+            .unwrap()
+            .oneshot(request)
+            .await
+            .unwrap();
+        let status = response.status();
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, serde_json::from_slice(&bytes).unwrap())
+    }
+
+    async fn get(node: Arc<Node>, uri: &str) -> (StatusCode, serde_json::Value) {
+        send(node, Request::get(uri).body(Body::empty()).unwrap()).await
+    }
+
+    #[test]
+    fn test_parse_block_id() {
+        assert_eq!(
+            parse_block_id("12"),
+            Ok(BlockId::Number(BlockNumber::Number(12)))
+        );
+        assert_eq!(
+            parse_block_id("0xc"),
+            Ok(BlockId::Number(BlockNumber::Number(12)))
+        );
+        assert_eq!(
+            parse_block_id("latest"),
+            Ok(BlockId::Number(BlockNumber::Latest))
+        );
+        let hash = format!("0x{}", "ab".repeat(32));
+        assert_eq!(parse_block_id(&hash), Ok(BlockId::Hash([0xab; 32])));
+        assert!(parse_block_id("tomorrow").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_rest_routes() {
+        let temp_dir = TempDir::new().unwrap();
+        let (node, signing_key) = test_node(&temp_dir).unwrap();
+        let node = Arc::new(node);
+
+        let (status, genesis) = get(node.clone(), "/v1/blocks/0").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(genesis["number"], "0x0");
+
+        let (status, _) = get(node.clone(), "/v1/blocks/9").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, _) = get(node.clone(), "/v1/blocks/tomorrow").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let tx = signed_transfer(&signing_key, [9u8; 32], 100, 0);
+        let raw = hex::encode(serde_json::to_vec(&tx).unwrap());
+        let request = Request::post("/v1/transactions")
+            .header("content-type", "application/json")
+            .body(Body::from(
+                serde_json::json!({ "rawTransaction": raw }).to_string(),
+            ))
+            .unwrap();
+        let (status, submitted) = send(node.clone(), request).await;
+        assert_eq!(status, StatusCode::OK);
+        let hash = submitted["hash"].as_str().unwrap().to_string();
+        node.produce_block().await.unwrap();
+
+        let (status, stored) = get(node.clone(), &format!("/v1/transactions/{}", hash)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(stored["blockNumber"], "0x1");
+
+        let (status, receipt) =
+            get(node.clone(), &format!("/v1/transactions/{}/receipt", hash)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(receipt["status"], "0x1");
+
+        let (status, block) = get(
+            node.clone(),
+            &format!(
+                "/v1/blocks/{}?full=true",
+                stored["blockHash"].as_str().unwrap()
+            ),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(block["transactions"][0]["hash"], hash.as_str());
+
+        let (status, account) =
+            get(node.clone(), &format!("/v1/accounts/0x{}", "09".repeat(32))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(account["balance"], "0x64");
+        assert_eq!(account["lockedBalance"], "0x0");
+        assert_eq!(account["unlockedBalance"], "0x64");
+    }
+
+    #[tokio::test]
+    async fn test_openapi_document() {
+        let temp_dir = TempDir::new().unwrap();
+        let (node, _) = test_node(&temp_dir).unwrap();
+
+        let (status, doc) = get(Arc::new(node), "/v1/openapi.json").await;
+        assert_eq!(status, StatusCode::OK);
+        for path in [
+            "/v1/blocks/{id}",
+            "/v1/transactions",
+            "/v1/transactions/{hash}",
+            "/v1/transactions/{hash}/receipt",
+            "/v1/accounts/{address}",
+        ] {
+            assert!(doc["paths"].get(path).is_some(), "missing {}", path);
+        }
+        assert!(doc["components"]["schemas"].get("RpcBlock").is_some());
+    }
+}
@@ -0,0 +1,440 @@
+//! JSON views of core types returned by the RPC API
+//!
+//! Hashes and addresses are rendered as `0x`-prefixed hex strings and
+//! numeric quantities as `0x`-prefixed hex numbers, following the
+//! Ethereum JSON-RPC conventions.
+
+use crate::fee::{FeeHistory, BLOCK_GAS_LIMIT};
+use crate::mempool::{MempoolContent, MempoolStatus, PooledTransaction};
+use crate::node::{Account, Simulation};
+use crate::receipt::Receipt;
+use crate::state::{AccountDiff, Supply};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use utoipa::ToSchema;
+use yotquitas_core::{Address, Block, Hash, Transaction, TransactionPayload};
+
+/// Encode a number as a hex quantity (e.g. `0x1a`)
+pub fn to_quantity(value: u64) -> String {
+    format!("0x{:x}", value)
+}
+
+/// Encode bytes as `0x`-prefixed hex
+pub fn to_hex(bytes: &[u8]) -> String {
+    format!("0x{}", hex::encode(bytes))
+}
+
+/// Block as returned by `eth_getBlockByNumber` / `eth_getBlockByHash`
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcBlock {
+    pub number: String,
+    pub hash: String,
+    pub parent_hash: String,
+    pub timestamp: String,
+    pub transactions_root: String,
+    pub base_fee_per_gas: String,
+    pub gas_used: String,
+    pub gas_limit: String,
+    /// Address the block's priority tips were paid to
+    pub proposer: String,
+    pub transactions: BlockTransactions,
+}
+
+/// Block body: either transaction hashes or full transaction objects
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(untagged)]
+pub enum BlockTransactions {
+    Hashes(Vec<String>),
+    Full(Vec<RpcTransaction>),
+}
+
+impl RpcBlock {
+    /// Build the view of a block, with full transaction bodies if `full` is set
+    pub fn new(block: &Block, full: bool) -> Self {
+        let block_hash = block.hash();
+        let transactions = if full {
+            BlockTransactions::Full(
+                block
+                    .transactions
+                    .iter()
+                    .enumerate()
+                    .map(|(i, tx)| {
+                        RpcTransaction::new(tx, Some((&block_hash, block.index(), i as u64)))
+                    })
+                    .collect(),
+            )
+        } else {
+            BlockTransactions::Hashes(
+                block
+                    .transactions
+                    .iter()
+                    .map(|tx| to_hex(&tx.hash()))
+                    .collect(),
+            )
+        };
+
+        Self {
+            number: to_quantity(block.index()),
+            hash: to_hex(&block_hash),
+            parent_hash: to_hex(&block.previous_hash()),
+            timestamp: to_quantity(block.header.timestamp),
+            transactions_root: to_hex(&block.header.merkle_root),
+            base_fee_per_gas: to_quantity(block.header.base_fee_per_gas),
+            gas_used: to_quantity(block.header.gas_used),
+            gas_limit: to_quantity(BLOCK_GAS_LIMIT),
+            proposer: to_hex(&block.header.proposer),
+            transactions,
+        }
+    }
+}
+
+/// Transaction with its inclusion location, if known
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcTransaction {
+    pub hash: String,
+    pub from: String,
+    pub nonce: String,
+    pub gas_limit: String,
+    pub max_fee_per_gas: String,
+    pub max_priority_fee_per_gas: String,
+    #[schema(value_type = Object)]
+    pub payload: TransactionPayload,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+    pub block_hash: Option<String>,
+    pub block_number: Option<String>,
+    pub transaction_index: Option<String>,
+}
+
+impl RpcTransaction {
+    /// Build the view of a transaction included at `(block_hash, block_number, index)`
+    pub fn new(tx: &Transaction, location: Option<(&Hash, u64, u64)>) -> Self {
+        Self {
+            hash: to_hex(&tx.hash()),
+            from: to_hex(&tx.sender_address()),
+            nonce: to_quantity(tx.nonce),
+            gas_limit: to_quantity(tx.gas_limit),
+            max_fee_per_gas: to_quantity(tx.max_fee_per_gas),
+            max_priority_fee_per_gas: to_quantity(tx.max_priority_fee_per_gas),
+            payload: tx.payload.clone(),
+            signature: tx.signature.map(|sig| to_hex(&sig.to_bytes())),
+            block_hash: location.map(|(hash, _, _)| to_hex(hash)),
+            block_number: location.map(|(_, number, _)| to_quantity(number)),
+            transaction_index: location.map(|(_, _, index)| to_quantity(index)),
+        }
+    }
+}
+
+/// Receipt as returned by `eth_getTransactionReceipt`
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcReceipt {
+    pub transaction_hash: String,
+    pub transaction_index: String,
+    pub block_hash: String,
+    pub block_number: String,
+    pub from: String,
+    /// `0x1` if execution succeeded, `0x0` otherwise
+    pub status: String,
+    pub gas_used: String,
+    pub effective_gas_price: String,
+    pub fee_paid: String,
+    pub return_data: String,
+    pub error: Option<String>,
+}
+
+impl From<&Receipt> for RpcReceipt {
+    fn from(receipt: &Receipt) -> Self {
+        Self {
+            transaction_hash: to_hex(&receipt.transaction_hash),
+            transaction_index: to_quantity(receipt.transaction_index),
+            block_hash: to_hex(&receipt.block_hash),
+            block_number: to_quantity(receipt.block_number),
+            from: to_hex(&receipt.from),
+            status: to_quantity(receipt.success as u64),
+            gas_used: to_quantity(receipt.gas_used),
+            effective_gas_price: to_quantity(receipt.effective_gas_price),
+            fee_paid: to_quantity(receipt.fee_paid),
+            return_data: to_hex(&receipt.return_data),
+            error: receipt.error.clone(),
+        }
+    }
+}
+
+/// Account state as returned by `aequitas_getAccount`
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcAccount {
+    pub address: String,
+    pub balance: String,
+    /// Part of the balance locked by a vesting schedule
+    pub locked_balance: String,
+    /// Part of the balance the account can spend
+    pub unlocked_balance: String,
+    pub nonce: String,
+    /// Height of the block the account state was read at
+    pub block_number: String,
+}
+
+impl From<&Account> for RpcAccount {
+    fn from(account: &Account) -> Self {
+        Self {
+            address: to_hex(&account.address),
+            balance: to_quantity(account.balance),
+            locked_balance: to_quantity(account.locked_balance),
+            unlocked_balance: to_quantity(account.balance.saturating_sub(account.locked_balance)),
+            nonce: to_quantity(account.nonce),
+            block_number: to_quantity(account.block_number),
+        }
+    }
+}
+
+/// Supply as returned by `aequitas_getSupply`
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcSupply {
+    /// Amount minted since genesis
+    pub minted: String,
+    /// Base fees burned since genesis
+    pub burned: String,
+    /// Amount held by all accounts, `minted - burned`
+    pub total_supply: String,
+    /// Height of the block the supply was read at
+    pub block_number: String,
+}
+
+impl RpcSupply {
+    pub fn new(supply: &Supply, block_number: u64) -> Self {
+        Self {
+            minted: to_quantity(supply.minted),
+            burned: to_quantity(supply.burned),
+            total_supply: to_quantity(supply.total()),
+            block_number: to_quantity(block_number),
+        }
+    }
+}
+
+/// Mempool counts as returned by `txpool_status`
+#[derive(Debug, Serialize)]
+pub struct RpcTxpoolStatus {
+    pub pending: String,
+    pub queued: String,
+}
+
+impl RpcTxpoolStatus {
+    pub fn new(status: &MempoolStatus) -> Self {
+        Self {
+            pending: to_quantity(status.pending as u64),
+            queued: to_quantity(status.queued as u64),
+        }
+    }
+}
+
+/// Pooled transactions keyed by sender and then nonce
+pub type TxpoolGroup<T> = BTreeMap<String, BTreeMap<u64, T>>;
+
+/// Mempool as returned by `txpool_content` and `txpool_inspect`
+#[derive(Debug, Serialize)]
+pub struct RpcTxpool<T> {
+    pub pending: TxpoolGroup<T>,
+    pub queued: TxpoolGroup<T>,
+}
+
+impl<T> RpcTxpool<T> {
+    /// Build the view of `content`, rendering each transaction with `view`
+    pub fn new(content: &MempoolContent, view: impl Fn(&Transaction) -> T) -> Self {
+        let group = |senders: &BTreeMap<Address, Vec<Transaction>>| {
+            senders
+                .iter()
+                .map(|(sender, txs)| {
+                    let by_nonce = txs.iter().map(|tx| (tx.nonce, view(tx))).collect();
+                    (to_hex(sender), by_nonce)
+                })
+                .collect()
+        };
+        Self {
+            pending: group(&content.pending),
+            queued: group(&content.queued),
+        }
+    }
+}
+
+/// One-line summary of a pooled transaction for `txpool_inspect`
+///
+/// Reads as `<target>: <value> + <gas limit> gas × <max fee per gas>`.
+pub fn inspect_transaction(tx: &Transaction) -> String {
+    let (target, value) = match &tx.payload {
+        TransactionPayload::Transfer { to, amount } => (to_hex(to), *amount),
+        TransactionPayload::MoveCall {
+            module, function, ..
+        } => (format!("{}::{}", module, function), 0),
+        TransactionPayload::DeployModule { .. } => ("contract creation".to_string(), 0),
+    };
+    format!(
+        "{}: {} + {} gas × {}",
+        target, value, tx.gas_limit, tx.max_fee_per_gas
+    )
+}
+
+/// Mempool transaction as returned by `aequitas_getPendingTransaction`
+#[derive(Debug, Serialize)]
+pub struct RpcPooledTransaction {
+    #[serde(flatten)]
+    pub transaction: RpcTransaction,
+    /// `pending` if it can go into the next block, `queued` if it waits
+    /// behind a nonce gap
+    pub status: &'static str,
+}
+
+impl RpcPooledTransaction {
+    pub fn new(pooled: &PooledTransaction) -> Self {
+        Self {
+            transaction: RpcTransaction::new(&pooled.tx, None),
+            status: if pooled.pending { "pending" } else { "queued" },
+        }
+    }
+}
+
+/// Payload of a `balanceChanges` subscription notification
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcBalanceChange {
+    pub address: String,
+    pub balance: String,
+    pub block_number: String,
+}
+
+/// Before and after values of a simulated change
+#[derive(Debug, Serialize)]
+pub struct RpcChange {
+    pub from: String,
+    pub to: String,
+}
+
+/// Account changed by a simulated transaction
+#[derive(Debug, Serialize)]
+pub struct RpcAccountDiff {
+    pub address: String,
+    pub balance: RpcChange,
+    pub nonce: RpcChange,
+}
+
+impl From<&AccountDiff> for RpcAccountDiff {
+    fn from(diff: &AccountDiff) -> Self {
+        Self {
+            address: to_hex(&diff.address),
+            balance: RpcChange {
+                from: to_quantity(diff.balance_before),
+                to: to_quantity(diff.balance_after),
+            },
+            nonce: RpcChange {
+                from: to_quantity(diff.nonce_before),
+                to: to_quantity(diff.nonce_after),
+            },
+        }
+    }
+}
+
+/// Result of `aequitas_simulateTransaction`
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcSimulation {
+    /// Head block the transaction was simulated on top of
+    pub block_number: String,
+    /// Whether the node would accept the transaction
+    pub valid: bool,
+    /// Whether execution would succeed
+    pub success: bool,
+    pub gas_used: String,
+    /// Fee that would be charged, `gas_used * effective_gas_price`
+    pub fee: String,
+    pub return_data: String,
+    pub error: Option<String>,
+    pub state_diff: Vec<RpcAccountDiff>,
+}
+
+impl RpcSimulation {
+    pub fn new(simulation: &Simulation, tx: &Transaction) -> Self {
+        let block_number = to_quantity(simulation.block_number);
+        let state_diff = simulation
+            .state_diff
+            .iter()
+            .map(RpcAccountDiff::from)
+            .collect();
+        match &simulation.outcome {
+            Ok(result) => Self {
+                block_number,
+                valid: true,
+                success: result.success,
+                gas_used: to_quantity(result.gas_used),
+                fee: to_quantity(result.fee(tx.effective_gas_price(simulation.base_fee_per_gas))),
+                return_data: to_hex(&result.return_data),
+                error: result.error.clone(),
+                state_diff,
+            },
+            Err(error) => Self {
+                block_number,
+                valid: false,
+                success: false,
+                gas_used: to_quantity(0),
+                fee: to_quantity(0),
+                return_data: to_hex(&[]),
+                error: Some(error.clone()),
+                state_diff,
+            },
+        }
+    }
+}
+
+/// Result of `aequitas_estimateFee`
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcFeeEstimate {
+    pub gas_used: String,
+    /// Base fee of the next block
+    pub base_fee_per_gas: String,
+    /// Recommended tip per unit of gas given recent blocks
+    pub max_priority_fee_per_gas: String,
+    /// Recommended max fee, leaving room for the base fee to double
+    pub max_fee_per_gas: String,
+    /// Expected price per unit of gas, base fee plus tip
+    pub gas_price: String,
+    /// Expected fee, `gas_used * gas_price`
+    pub fee: String,
+}
+
+/// Result of `eth_feeHistory`
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcFeeHistory {
+    pub oldest_block: String,
+    pub base_fee_per_gas: Vec<String>,
+    pub gas_used_ratio: Vec<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reward: Option<Vec<Vec<String>>>,
+}
+
+impl RpcFeeHistory {
+    /// Build the view of a fee history; rewards are left out unless percentiles were requested
+    pub fn new(history: &FeeHistory, with_rewards: bool) -> Self {
+        Self {
+            oldest_block: to_quantity(history.oldest_block),
+            base_fee_per_gas: history
+                .base_fee_per_gas
+                .iter()
+                .copied()
+                .map(to_quantity)
+                .collect(),
+            gas_used_ratio: history.gas_used_ratio.clone(),
+            reward: with_rewards.then(|| {
+                history
+                    .reward
+                    .iter()
+                    .map(|block| block.iter().copied().map(to_quantity).collect())
+                    .collect()
+            }),
+        }
+    }
+}
@@ -0,0 +1,365 @@
+//! WebSocket transport with `eth_subscribe` / `eth_unsubscribe`
+//!
+//! Ordinary JSON-RPC calls are served over the socket as well. Each
+//! connection forwards node events for its subscriptions through a bounded
+//! buffer; a subscriber that falls behind is disconnected rather than
+//! allowed to hold back the node.
+
+use super::types::{to_hex, to_quantity, RpcBalanceChange, RpcBlock};
+use super::{
+    address_param, batch_error, dispatch, param, parse_call, respond, JsonRpcError,
+    JsonRpcResponse, RpcConfig, RpcResult,
+};
+use crate::events::NodeEvent;
+use crate::node::Node;
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        State,
+    },
+    response::Response,
+    Extension,
+};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::sync::{broadcast, mpsc};
+use yotquitas_core::Address;
+
+/// Notifications queued per connection before the subscriber is dropped
+pub const SUBSCRIBER_BUFFER: usize = 256;
+
+/// What a subscription listens for
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum SubscriptionKind {
+    NewHeads,
+    NewPendingTransactions,
+    BalanceChanges(Address),
+}
+
+impl SubscriptionKind {
+    /// Parse the params of `eth_subscribe`
+    fn parse(params: &serde_json::Value) -> Result<Self, JsonRpcError> {
+        match param(params, 0).and_then(|v| v.as_str()) {
+            Some("newHeads") => Ok(Self::NewHeads),
+            Some("newPendingTransactions") => Ok(Self::NewPendingTransactions),
+            Some("balanceChanges") => Ok(Self::BalanceChanges(address_param(params, 1)?)),
+            _ => Err(JsonRpcError::invalid_params(
+                "expected 'newHeads', 'newPendingTransactions' or 'balanceChanges'",
+            )),
+        }
+    }
+
+    /// Render the notification payload for `event`, if this subscription wants it
+    fn notification(&self, event: &NodeEvent) -> Option<serde_json::Value> {
+        match (self, event) {
+            (Self::NewHeads, NodeEvent::NewHead(block)) => {
+                serde_json::to_value(RpcBlock::new(block, false)).ok()
+            }
+            (Self::NewPendingTransactions, NodeEvent::PendingTransaction(hash)) => {
+                Some(serde_json::json!(to_hex(hash)))
+            }
+            (
+                Self::BalanceChanges(watched),
+                NodeEvent::BalanceChanged {
+                    address,
+                    balance,
+                    block_number,
+                },
+            ) if watched == address => serde_json::to_value(RpcBalanceChange {
+                address: to_hex(address),
+                balance: to_quantity(*balance),
+                block_number: to_quantity(*block_number),
+            })
+            .ok(),
+            _ => None,
+        }
+    }
+}
+
+/// Active subscriptions of one connection, keyed by subscription id
+type Subscriptions = Arc<Mutex<HashMap<String, SubscriptionKind>>>;
+
+/// Upgrade an HTTP request to a JSON-RPC WebSocket
+pub async fn handle_websocket(
+    ws: WebSocketUpgrade,
+    State(node): State<Arc<Node>>,
+    Extension(config): Extension<Arc<RpcConfig>>,
+) -> Response {
+    ws.max_message_size(config.max_body_size)
+        .on_upgrade(move |socket| serve_socket(socket, node, config.max_batch_size))
+}
+
+/// Serve one WebSocket connection until either side closes it
+async fn serve_socket(mut socket: WebSocket, node: Arc<Node>, max_batch_size: usize) {
+    let subscriptions: Subscriptions = Arc::new(Mutex::new(HashMap::new()));
+    let (notifications, mut outgoing) = mpsc::channel(SUBSCRIBER_BUFFER);
+    let forwarder = tokio::spawn(forward_events(
+        node.subscribe(),
+        subscriptions.clone(),
+        notifications,
+    ));
+    let mut next_id: u64 = 1;
+
+    loop {
+        tokio::select! {
+            message = socket.recv() => {
+                let text = match message {
+                    Some(Ok(Message::Text(text))) => text,
+                    Some(Ok(Message::Close(_))) | None | Some(Err(_)) => break,
+                    // Pings are answered by axum; binary frames are ignored
+                    Some(Ok(_)) => continue,
+                };
+                let reply =
+                    handle_message(&node, &subscriptions, &mut next_id, max_batch_size, &text)
+                        .await;
+                if let Some(reply) = reply {
+                    if socket.send(Message::Text(reply)).await.is_err() {
+                        break;
+                    }
+                }
+            }
+            notification = outgoing.recv() => match notification {
+                Some(notification) => {
+                    if socket.send(Message::Text(notification)).await.is_err() {
+                        break;
+                    }
+                }
+                // The forwarder gave up on this subscriber
+                None => break,
+            },
+        }
+    }
+
+    forwarder.abort();
+}
+
+/// Push notifications for matching events into the connection's buffer
+async fn forward_events(
+    mut events: broadcast::Receiver<NodeEvent>,
+    subscriptions: Subscriptions,
+    notifications: mpsc::Sender<String>,
+) {
+    loop {
+        let event = match events.recv().await {
+            Ok(event) => event,
+            Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                tracing::warn!(
+                    "Dropping WebSocket subscriber that missed {} events",
+                    skipped
+                );
+                return;
+            }
+            Err(broadcast::error::RecvError::Closed) => return,
+        };
+
+        let pending: Vec<String> = subscriptions
+            .lock()
+            .unwrap()
+            .iter()
+            .filter_map(|(id, kind)| {
+                kind.notification(&event)
+                    .map(|result| subscription_message(id, result))
+            })
+            .collect();
+
+        for message in pending {
+            match notifications.try_send(message) {
+                Ok(()) => {}
+                Err(mpsc::error::TrySendError::Full(_)) => {
+                    tracing::warn!("Dropping WebSocket subscriber with a full buffer");
+                    return;
+                }
+                Err(mpsc::error::TrySendError::Closed(_)) => return,
+            }
+        }
+    }
+}
+
+/// Build an `eth_subscription` notification
+fn subscription_message(id: &str, result: serde_json::Value) -> String {
+    serde_json::json!({
+        "jsonrpc": "2.0",
+        "method": "eth_subscription",
+        "params": { "subscription": id, "result": result },
+    })
+    .to_string()
+}
+
+/// Handle one text frame holding a call or a batch of calls
+async fn handle_message(
+    node: &Node,
+    subscriptions: &Subscriptions,
+    next_id: &mut u64,
+    max_batch_size: usize,
+    text: &str,
+) -> Option<String> {
+    let payload: serde_json::Value = match serde_json::from_str(text) {
+        Ok(payload) => payload,
+        Err(_) => {
+            let response =
+                JsonRpcResponse::failure(serde_json::Value::Null, JsonRpcError::parse_error());
+            return serde_json::to_string(&response).ok();
+        }
+    };
+
+    match payload {
+        serde_json::Value::Array(calls) => {
+            if let Some(error) = batch_error(&calls, max_batch_size) {
+                let response = JsonRpcResponse::failure(serde_json::Value::Null, error);
+                return serde_json::to_string(&response).ok();
+            }
+
+            let mut responses = Vec::with_capacity(calls.len());
+            for call in calls {
+                if let Some(response) = handle_socket_call(node, subscriptions, next_id, call).await
+                {
+                    responses.push(response);
+                }
+            }
+            if responses.is_empty() {
+                None
+            } else {
+                serde_json::to_string(&responses).ok()
+            }
+        }
+        call => {
+            let response = handle_socket_call(node, subscriptions, next_id, call).await?;
+            serde_json::to_string(&response).ok()
+        }
+    }
+}
+
+/// Execute one call, handling the subscription methods locally
+async fn handle_socket_call(
+    node: &Node,
+    subscriptions: &Subscriptions,
+    next_id: &mut u64,
+    call: serde_json::Value,
+) -> Option<JsonRpcResponse> {
+    let request = match parse_call(call) {
+        Ok(request) => request,
+        Err(response) => return Some(*response),
+    };
+
+    let result = match request.method.as_str() {
+        "eth_subscribe" => subscribe(subscriptions, next_id, &request.params),
+        "eth_unsubscribe" => unsubscribe(subscriptions, &request.params),
+        method => dispatch(node, method, &request.params).await,
+    };
+    respond(request.id, result)
+}
+
+/// Handle eth_subscribe
+fn subscribe(
+    subscriptions: &Subscriptions,
+    next_id: &mut u64,
+    params: &serde_json::Value,
+) -> RpcResult {
+    let kind = SubscriptionKind::parse(params)?;
+    let id = to_quantity(*next_id);
+    *next_id += 1;
+    subscriptions.lock().unwrap().insert(id.clone(), kind);
+    Ok(serde_json::json!(id))
+}
+
+/// Handle eth_unsubscribe
+fn unsubscribe(subscriptions: &Subscriptions, params: &serde_json::Value) -> RpcResult {
+    let id = param(params, 0)
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| JsonRpcError::invalid_params("expected a subscription id"))?;
+    let removed = subscriptions.lock().unwrap().remove(id).is_some();
+    Ok(serde_json::json!(removed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use yotquitas_core::{Block, BlockHeader};
+
+    #[test]
+    fn test_parse_subscription_kind() {
+        assert_eq!(
+            SubscriptionKind::parse(&json!(["newHeads"])),
+            Ok(SubscriptionKind::NewHeads)
+        );
+        assert_eq!(
+            SubscriptionKind::parse(&json!(["newPendingTransactions"])),
+            Ok(SubscriptionKind::NewPendingTransactions)
+        );
+        let address = format!("0x{}", "11".repeat(32));
+        assert_eq!(
+            SubscriptionKind::parse(&json!(["balanceChanges", address])),
+            Ok(SubscriptionKind::BalanceChanges([0x11; 32]))
+        );
+        assert!(SubscriptionKind::parse(&json!(["balanceChanges"])).is_err());
+        assert!(SubscriptionKind::parse(&json!(["logs"])).is_err());
+    }
+
+    #[test]
+    fn test_notification_filtering() {
+        let head = NodeEvent::NewHead(Block::new(BlockHeader::new(3, 0, [0u8; 32]), vec![]));
+        let pending = NodeEvent::PendingTransaction([5u8; 32]);
+        let balance = NodeEvent::BalanceChanged {
+            address: [1u8; 32],
+            balance: 42,
+            block_number: 3,
+        };
+
+        let heads = SubscriptionKind::NewHeads;
+        assert_eq!(heads.notification(&head).unwrap()["number"], "0x3");
+        assert!(heads.notification(&pending).is_none());
+
+        let txs = SubscriptionKind::NewPendingTransactions;
+        assert_eq!(
+            txs.notification(&pending),
+            Some(json!(format!("0x{}", "05".repeat(32))))
+        );
+        assert!(txs.notification(&balance).is_none());
+
+        let watched = SubscriptionKind::BalanceChanges([1u8; 32]);
+        assert_eq!(watched.notification(&balance).unwrap()["balance"], "0x2a");
+        let other = SubscriptionKind::BalanceChanges([2u8; 32]);
+        assert!(other.notification(&balance).is_none());
+    }
+
+    #[test]
+    fn test_subscribe_and_unsubscribe() {
+        let subscriptions: Subscriptions = Arc::new(Mutex::new(HashMap::new()));
+        let mut next_id = 1;
+
+        let id = subscribe(&subscriptions, &mut next_id, &json!(["newHeads"])).unwrap();
+        assert_eq!(id, json!("0x1"));
+        assert_eq!(subscriptions.lock().unwrap().len(), 1);
+
+        assert_eq!(
+            unsubscribe(&subscriptions, &json!(["0x1"])),
+            Ok(json!(true))
+        );
+        assert_eq!(
+            unsubscribe(&subscriptions, &json!(["0x1"])),
+            Ok(json!(false))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_slow_subscriber_is_dropped() {
+        let (sender, events) = broadcast::channel(16);
+        let subscriptions: Subscriptions = Arc::new(Mutex::new(HashMap::from([(
+            "0x1".to_string(),
+            SubscriptionKind::NewPendingTransactions,
+        )])));
+        let (notifications, mut outgoing) = mpsc::channel(2);
+
+        let forwarder = tokio::spawn(forward_events(events, subscriptions, notifications));
+        for i in 0..3u8 {
+            sender.send(NodeEvent::PendingTransaction([i; 32])).unwrap();
+        }
+        forwarder.await.unwrap();
+
+        // The buffered notifications are delivered, then the channel closes
+        assert!(outgoing.recv().await.is_some());
+        assert!(outgoing.recv().await.is_some());
+        assert!(outgoing.recv().await.is_none());
+    }
+}
@@ -0,0 +1,308 @@
+//! Subcommands of the node binary other than `run`
+
+use anyhow::Result;
+use clap::{Args, Subcommand};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use yotquitas_core::{
+    generate_keypair, parse_hash, public_key_address, read_passphrase_file, Block, Keystore,
+};
+use yotquitas_node::api::types::{RpcAccount, RpcBlock, RpcReceipt, RpcTransaction};
+use yotquitas_node::config::{self, Config};
+use yotquitas_node::genesis::{Allocation, ConsensusParams, Genesis};
+use yotquitas_node::node::Account;
+use yotquitas_node::receipt::Receipt;
+use yotquitas_node::state::StateDB;
+
+/// Config written by `init`, the development config pointed at `genesis.toml`
+const DEFAULT_CONFIG: &str = include_str!("../../config/dev.toml");
+
+/// Supply allocated to the treasury by `init`: 1 billion AEQ (with 9 decimals)
+const DEFAULT_SUPPLY: u64 = 1_000_000_000_000_000_000;
+
+/// Options selecting the config file
+#[derive(Args, Debug)]
+pub struct ConfigArgs {
+    /// Path to the config file
+    #[arg(long)]
+    pub config: Option<PathBuf>,
+
+    /// Shorthand for `--config config/<TYPE>.toml`: 'dev' or 'mainnet'
+    #[arg(long, default_value = "dev", conflicts_with = "config")]
+    pub config_type: String,
+
+    /// Override `node.data_dir`
+    #[arg(long)]
+    pub data_dir: Option<String>,
+}
+
+impl ConfigArgs {
+    /// Locate and load the config, applying the `--data-dir` override
+    pub fn load(&self) -> Result<(PathBuf, Config)> {
+        let path = config::resolve_path(self.config.as_deref(), &self.config_type)?;
+        let mut config = Config::load(&path)?;
+        if let Some(data_dir) = &self.data_dir {
+            config.node.data_dir = data_dir.clone();
+        }
+        Ok((path, config))
+    }
+}
+
+#[derive(Args, Debug)]
+pub struct InitArgs {
+    /// Directory to write `config.toml` and `genesis.toml` into
+    #[arg(long, default_value = ".")]
+    dir: PathBuf,
+
+    /// Chain ID of the new genesis
+    #[arg(long, default_value_t = 1337)]
+    chain_id: u64,
+
+    /// Address funded with the initial supply
+    #[arg(long)]
+    treasury: String,
+
+    /// Overwrite existing files
+    #[arg(long)]
+    force: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct KeygenArgs {
+    /// Save the key to this encrypted keystore file instead of printing it
+    #[arg(long)]
+    keystore: Option<PathBuf>,
+
+    /// Read the keystore passphrase from this file instead of prompting
+    #[arg(long, requires = "keystore")]
+    password_file: Option<PathBuf>,
+}
+
+#[derive(Args, Debug)]
+pub struct InspectArgs {
+    #[command(flatten)]
+    config: ConfigArgs,
+
+    #[command(subcommand)]
+    target: InspectTarget,
+}
+
+#[derive(Subcommand, Debug)]
+enum InspectTarget {
+    /// A block by number, hash or `latest`
+    Block { id: String },
+    /// Balance, nonce and vesting lock of an account
+    Account { address: String },
+    /// A committed transaction and its receipt
+    Tx { hash: String },
+}
+
+/// Write a default config and a fresh genesis into a directory
+pub fn init(args: InitArgs) -> Result<()> {
+    let config_path = args.dir.join("config.toml");
+    let genesis_path = args.dir.join("genesis.toml");
+    if !args.force {
+        for path in [&config_path, &genesis_path] {
+            if path.exists() {
+                anyhow::bail!("{:?} already exists; pass --force to overwrite", path);
+            }
+        }
+    }
+
+    let treasury = parse_hash(&args.treasury)
+        .map_err(|e| anyhow::anyhow!("Invalid treasury address: {}", e))?;
+    let genesis = Genesis {
+        chain_id: args.chain_id,
+        timestamp: std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs(),
+        consensus: ConsensusParams::default(),
+        alloc: vec![Allocation {
+            address: treasury,
+            balance: DEFAULT_SUPPLY,
+            vesting: None,
+        }],
+        validators: vec![],
+    };
+
+    std::fs::create_dir_all(&args.dir)?;
+    std::fs::write(&config_path, default_config()?)?;
+    std::fs::write(&genesis_path, toml::to_string_pretty(&genesis)?)?;
+    println!("Wrote {}", config_path.display());
+    println!("Wrote {}", genesis_path.display());
+    println!("Genesis hash: {}", hex::encode(genesis.block().hash()));
+    Ok(())
+}
+
+/// The development config with its genesis file next to it
+fn default_config() -> Result<String> {
+    let mut config: toml::Table = DEFAULT_CONFIG.parse()?;
+    let genesis = config
+        .get_mut("genesis")
+        .and_then(toml::Value::as_table_mut)
+        .ok_or_else(|| anyhow::anyhow!("The default config has no [genesis] table"))?;
+    genesis.insert("file".to_string(), "genesis.toml".into());
+    Ok(toml::to_string_pretty(&config)?)
+}
+
+/// Generate a keypair and print its address
+pub fn keygen(args: KeygenArgs) -> Result<()> {
+    let (signing_key, verifying_key) = generate_keypair();
+    let address = hex::encode(public_key_address(&verifying_key));
+
+    let Some(keystore_path) = &args.keystore else {
+        println!("Address: {}", address);
+        println!("Public key: {}", hex::encode(verifying_key.to_bytes()));
+        println!("Secret key: {}", hex::encode(signing_key.to_bytes()));
+        eprintln!("Keep the secret key safe; anyone holding it controls the account");
+        return Ok(());
+    };
+
+    if keystore_path.exists() {
+        anyhow::bail!("Keystore {} already exists", keystore_path.display());
+    }
+    let passphrase = match &args.password_file {
+        Some(path) => read_passphrase_file(path)?,
+        None => {
+            let passphrase = rpassword::prompt_password("New passphrase: ")?;
+            if rpassword::prompt_password("Repeat passphrase: ")? != passphrase {
+                anyhow::bail!("Passphrases do not match");
+            }
+            passphrase
+        }
+    };
+    Keystore::create(keystore_path, &signing_key, &passphrase)?;
+    println!("Address: {}", address);
+    println!("Keystore: {}", keystore_path.display());
+    Ok(())
+}
+
+/// Print a block, account or transaction read from the data directory
+pub fn inspect(args: InspectArgs) -> Result<()> {
+    let data_dir = match &args.config.data_dir {
+        Some(data_dir) => data_dir.clone(),
+        None => args.config.load()?.1.node.data_dir,
+    };
+    let state = StateDB::open(&data_dir)
+        .map_err(|e| anyhow::anyhow!("Failed to open data directory {}: {}", data_dir, e))?;
+
+    match args.target {
+        InspectTarget::Block { id } => {
+            let hash = match id.as_str() {
+                "latest" => state.get_latest_block_hash()?,
+                _ if id.starts_with("0x") => Some(
+                    parse_hash(&id).map_err(|e| anyhow::anyhow!("Invalid block hash: {}", e))?,
+                ),
+                _ => {
+                    let height = id.parse().map_err(|_| {
+                        anyhow::anyhow!("Expected a block number, hash or 'latest'")
+                    })?;
+                    state.get_block_hash_by_height(height)?
+                }
+            };
+            let block = match hash {
+                Some(hash) => read_block(&state, &hash)?,
+                None => None,
+            };
+            print_json(&block.map(|block| RpcBlock::new(&block, true)))
+        }
+        InspectTarget::Account { address } => {
+            let address =
+                parse_hash(&address).map_err(|e| anyhow::anyhow!("Invalid address: {}", e))?;
+            let head = match state.get_latest_block_hash()? {
+                Some(hash) => read_block(&state, &hash)?,
+                None => None,
+            }
+            .ok_or_else(|| anyhow::anyhow!("The data directory holds no chain"))?;
+            // Locks are evaluated at the next block's time, as the node does
+            let timestamp = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)?
+                .as_secs()
+                .max(head.header.timestamp);
+            let locked_balance = state
+                .get_vesting(&address)?
+                .map_or(0, |schedule| schedule.locked_at(timestamp));
+            print_json(&RpcAccount::from(&Account {
+                address,
+                balance: state.get_balance(&address)?,
+                locked_balance,
+                nonce: state.get_nonce(&address)?,
+                block_number: head.index(),
+            }))
+        }
+        InspectTarget::Tx { hash } => {
+            let hash = parse_hash(&hash)
+                .map_err(|e| anyhow::anyhow!("Invalid transaction hash: {}", e))?;
+            let Some((height, index)) = state.get_transaction_location(&hash)? else {
+                return print_json(&None::<()>);
+            };
+            let block = match state.get_block_hash_by_height(height)? {
+                Some(block_hash) => read_block(&state, &block_hash)?,
+                None => None,
+            }
+            .ok_or_else(|| anyhow::anyhow!("Indexed block {} not found", height))?;
+            let tx = block.transactions.get(index as usize).ok_or_else(|| {
+                anyhow::anyhow!("Indexed transaction missing from block {}", height)
+            })?;
+            let receipt = match state.get_receipt(&hash)? {
+                Some(data) => Some(serde_json::from_slice::<Receipt>(&data)?),
+                None => None,
+            };
+
+            #[derive(Serialize)]
+            struct InspectedTransaction {
+                transaction: RpcTransaction,
+                receipt: Option<RpcReceipt>,
+            }
+            print_json(&InspectedTransaction {
+                transaction: RpcTransaction::new(tx, Some((&block.hash(), height, index))),
+                receipt: receipt.as_ref().map(RpcReceipt::from),
+            })
+        }
+    }
+}
+
+/// Read and decode a stored block
+fn read_block(state: &StateDB, hash: &[u8; 32]) -> Result<Option<Block>> {
+    match state.get_block(hash)? {
+        Some(data) => Ok(Some(serde_json::from_slice(&data)?)),
+        None => Ok(None),
+    }
+}
+
+fn print_json(value: &impl Serialize) -> Result<()> {
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
+}
+
+/// Print the hash of the genesis block a genesis file describes
+pub fn genesis_hash(file: &Path) -> Result<()> {
+    let genesis = Genesis::load(file)?;
+    println!("{}", hex::encode(genesis.block().hash()));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_init_writes_loadable_config() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let dir = temp_dir.path().join("node");
+        let args = || InitArgs {
+            dir: dir.clone(),
+            chain_id: 42,
+            treasury: hex::encode([5u8; 32]),
+            force: false,
+        };
+        init(args()).unwrap();
+        // Nothing is overwritten without --force
+        assert!(init(args()).is_err());
+
+        let config = Config::load(&dir.join("config.toml")).unwrap();
+        let genesis = Genesis::load(&config.genesis.file).unwrap();
+        assert_eq!(genesis.chain_id, 42);
+        assert_eq!(genesis.balances()[&[5u8; 32]], DEFAULT_SUPPLY);
+    }
+}
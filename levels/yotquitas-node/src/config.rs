@@ -0,0 +1,336 @@
+//! Node configuration
+//!
+//! Settings are read from a TOML file. Every key can be overridden by an
+//! environment variable named `YOTQUITAS_<SECTION>_<KEY>`, for example
+//! `YOTQUITAS_NODE_DATA_DIR` or `YOTQUITAS_MEMPOOL_TTL_SECS`. Lists such as
+//! `rpc_cors` are given as comma-separated values. Unknown sections, keys and
+//! `YOTQUITAS_*` variables are rejected rather than silently ignored, except
+//! the wallet's `YOTQUITAS_WALLET_*` variables.
+
+use crate::{api, mempool};
+use anyhow::Result;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+/// Prefix of environment variables that override config keys
+const ENV_PREFIX: &str = "YOTQUITAS_";
+
+/// Prefix of the wallet's environment variables, which are not config keys
+const WALLET_ENV_PREFIX: &str = "YOTQUITAS_WALLET_";
+
+/// Sections of the config file, in the order they are matched against
+/// environment variable names
+const SECTIONS: [&str; 6] = ["network", "node", "genesis", "logging", "admin", "mempool"];
+
+/// Configuration structure matching TOML files
+#[derive(Debug, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    pub network: NetworkConfig,
+    pub node: NodeConfig,
+    pub genesis: GenesisConfigToml,
+    pub logging: LoggingConfig,
+    /// Admin listener; disabled when the section is absent
+    pub admin: Option<AdminConfigToml>,
+    /// Mempool limits; defaults apply when the section is absent
+    pub mempool: Option<MempoolConfigToml>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct NetworkConfig {
+    pub network_id: u64,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct NodeConfig {
+    pub data_dir: String,
+    pub rpc_addr: String,
+    pub rpc_cors: Vec<String>,
+    pub rpc_max_body_size: usize,
+    pub rpc_max_batch_size: usize,
+    pub rpc_max_concurrency: usize,
+    pub rpc_rate_limit: u32,
+    pub rpc_rate_limit_burst: u32,
+    /// Address paid the priority tips of produced blocks
+    pub proposer_address: Option<String>,
+    /// Address paid the base fees; they are burned when unset
+    pub fee_collector_address: Option<String>,
+    /// Interval between block production rounds
+    pub block_time_ms: u64,
+}
+
+impl NodeConfig {
+    /// Limits for the RPC server
+    pub fn rpc_config(&self) -> api::RpcConfig {
+        api::RpcConfig {
+            cors: self.rpc_cors.clone(),
+            max_body_size: self.rpc_max_body_size,
+            max_batch_size: self.rpc_max_batch_size,
+            max_concurrency: self.rpc_max_concurrency,
+            rate_limit: self.rpc_rate_limit,
+            rate_limit_burst: self.rpc_rate_limit_burst,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct GenesisConfigToml {
+    /// Genesis file, relative to the directory of the config file
+    pub file: PathBuf,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct LoggingConfig {
+    pub level: String,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct AdminConfigToml {
+    pub addr: String,
+    pub token_file: String,
+    pub snapshot_dir: String,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct MempoolConfigToml {
+    pub max_transactions: usize,
+    pub max_bytes: usize,
+    pub ttl_secs: u64,
+    pub price_bump_percent: u64,
+}
+
+impl From<&MempoolConfigToml> for mempool::MempoolConfig {
+    fn from(config: &MempoolConfigToml) -> Self {
+        Self {
+            max_transactions: config.max_transactions,
+            max_bytes: config.max_bytes,
+            ttl: std::time::Duration::from_secs(config.ttl_secs),
+            price_bump_percent: config.price_bump_percent,
+        }
+    }
+}
+
+impl Config {
+    /// Load the config file at `path` and apply `YOTQUITAS_*` overrides
+    /// from the environment
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("Failed to read config file {:?}: {}", path, e))?;
+        let mut config = Self::parse(&content, std::env::vars())
+            .map_err(|e| anyhow::anyhow!("Invalid config file {:?}: {}", path, e))?;
+        if let Some(config_dir) = path.parent() {
+            config.genesis.file = config_dir.join(&config.genesis.file);
+        }
+        Ok(config)
+    }
+
+    /// Parse config file contents, overriding keys from `vars`
+    ///
+    /// Variables without the `YOTQUITAS_` prefix, and those of the wallet,
+    /// are ignored.
+    fn parse(content: &str, vars: impl IntoIterator<Item = (String, String)>) -> Result<Self> {
+        let mut table: toml::Table = toml::from_str(content)?;
+        for (name, value) in vars {
+            if name.starts_with(WALLET_ENV_PREFIX) {
+                continue;
+            }
+            if let Some(key) = name.strip_prefix(ENV_PREFIX) {
+                apply_override(&mut table, &name, key, &value)?;
+            }
+        }
+        Ok(Config::deserialize(toml::Value::Table(table))?)
+    }
+}
+
+/// Set the key named by the environment variable `name` to `value`
+///
+/// The value is converted to the type of the key it replaces; the type of
+/// a key absent from the file is inferred from the value.
+fn apply_override(table: &mut toml::Table, name: &str, key: &str, value: &str) -> Result<()> {
+    let key = key.to_lowercase();
+    let (section, field) = SECTIONS
+        .iter()
+        .find_map(|section| {
+            let field = key.strip_prefix(section)?.strip_prefix('_')?;
+            Some((*section, field))
+        })
+        .filter(|(_, field)| !field.is_empty())
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "Unknown config override {}: expected {}<SECTION>_<KEY> with a section of {}",
+                name,
+                ENV_PREFIX,
+                SECTIONS.join(", ")
+            )
+        })?;
+
+    let section = table
+        .entry(section)
+        .or_insert_with(|| toml::Value::Table(toml::Table::new()))
+        .as_table_mut()
+        .ok_or_else(|| anyhow::anyhow!("[{}] is not a table", section))?;
+    let converted = match section.get(field) {
+        Some(toml::Value::String(_)) => toml::Value::String(value.to_string()),
+        Some(toml::Value::Integer(_)) => toml::Value::Integer(
+            value
+                .parse()
+                .map_err(|_| anyhow::anyhow!("{} must be an integer, got {:?}", name, value))?,
+        ),
+        Some(toml::Value::Boolean(_)) => toml::Value::Boolean(
+            value
+                .parse()
+                .map_err(|_| anyhow::anyhow!("{} must be true or false, got {:?}", name, value))?,
+        ),
+        Some(toml::Value::Array(_)) => toml::Value::Array(
+            value
+                .split(',')
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .map(|item| toml::Value::String(item.to_string()))
+                .collect(),
+        ),
+        Some(_) => anyhow::bail!("{} cannot override a key of this type", name),
+        None => infer_value(value),
+    };
+    section.insert(field.to_string(), converted);
+    Ok(())
+}
+
+/// Read the value of a key absent from the file
+///
+/// Numbers only count as integers when written canonically, so that
+/// zero-padded values such as addresses stay strings.
+fn infer_value(value: &str) -> toml::Value {
+    if let Ok(number) = value.parse::<i64>() {
+        if number.to_string() == value {
+            return toml::Value::Integer(number);
+        }
+    }
+    match value.parse::<bool>() {
+        Ok(flag) => toml::Value::Boolean(flag),
+        Err(_) => toml::Value::String(value.to_string()),
+    }
+}
+
+/// Locate the config file
+///
+/// An explicit `path` is used as given. Otherwise `config_type` names a file
+/// in `config/` under the working directory, as laid out in the Docker
+/// image, or in the workspace's `levels/config/` when run from the workspace
+/// root or a crate directory.
+pub fn resolve_path(path: Option<&Path>, config_type: &str) -> Result<PathBuf> {
+    if let Some(path) = path {
+        return Ok(path.to_path_buf());
+    }
+    let file_name = format!("{}.toml", config_type);
+    let candidates =
+        ["config", "levels/config", "../config"].map(|dir| Path::new(dir).join(&file_name));
+    candidates
+        .iter()
+        .find(|candidate| candidate.is_file())
+        .cloned()
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "Config file {} not found in {:?}; pass --config <path>",
+                file_name,
+                candidates
+            )
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config_dir() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("../config")
+    }
+
+    fn vars(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_shipped_configs_parse() {
+        for config_type in ["dev", "mainnet"] {
+            let path = config_dir().join(format!("{}.toml", config_type));
+            let content = std::fs::read_to_string(&path).unwrap();
+            let config = Config::parse(&content, []).unwrap();
+            assert!(config_dir().join(&config.genesis.file).is_file());
+        }
+    }
+
+    #[test]
+    fn test_env_overrides() {
+        let content = std::fs::read_to_string(config_dir().join("dev.toml")).unwrap();
+        let config = Config::parse(
+            &content,
+            vars(&[
+                ("YOTQUITAS_NODE_DATA_DIR", "/srv/yotquitas"),
+                (
+                    "YOTQUITAS_NODE_RPC_CORS",
+                    "https://a.example, https://b.example",
+                ),
+                ("YOTQUITAS_NETWORK_NETWORK_ID", "7"),
+                ("YOTQUITAS_NODE_FEE_COLLECTOR_ADDRESS", "0000"),
+                ("YOTQUITAS_MEMPOOL_TTL_SECS", "60"),
+                ("HOME", "/root"),
+            ]),
+        )
+        .unwrap();
+        assert_eq!(config.node.data_dir, "/srv/yotquitas");
+        assert_eq!(
+            config.node.rpc_cors,
+            ["https://a.example", "https://b.example"]
+        );
+        assert_eq!(config.network.network_id, 7);
+        assert_eq!(config.node.fee_collector_address.as_deref(), Some("0000"));
+        assert_eq!(config.mempool.unwrap().ttl_secs, 60);
+
+        let err = Config::parse(&content, vars(&[("YOTQUITAS_NODE_BLOCK_TIME_MS", "fast")]));
+        assert!(err.unwrap_err().to_string().contains("must be an integer"));
+        let err = Config::parse(&content, vars(&[("YOTQUITAS_DATA_DIR", "/srv")]));
+        assert!(err
+            .unwrap_err()
+            .to_string()
+            .contains("Unknown config override"));
+    }
+
+    #[test]
+    fn test_wallet_variables_are_ignored() {
+        let content = std::fs::read_to_string(config_dir().join("dev.toml")).unwrap();
+        let config = Config::parse(
+            &content,
+            vars(&[
+                ("YOTQUITAS_WALLET_RPC_URL", "http://127.0.0.1:8545"),
+                ("YOTQUITAS_WALLET_KEYSTORE", "alice.json"),
+                ("YOTQUITAS_WALLET_PASSWORD_FILE", "alice.pass"),
+            ]),
+        )
+        .unwrap();
+        assert_eq!(
+            config.node.rpc_addr,
+            Config::parse(&content, []).unwrap().node.rpc_addr
+        );
+    }
+
+    #[test]
+    fn test_unknown_keys_are_rejected() {
+        let content = std::fs::read_to_string(config_dir().join("dev.toml")).unwrap();
+        let typo = content.replace("block_time_ms", "block_time");
+        let err = Config::parse(&typo, []).unwrap_err().to_string();
+        assert!(err.contains("unknown field `block_time`"), "{}", err);
+
+        let err = Config::parse(&content, vars(&[("YOTQUITAS_NODE_RPC_PORT", "1")]));
+        assert!(err.unwrap_err().to_string().contains("rpc_port"));
+    }
+}
@@ -0,0 +1,49 @@
+use tokio::sync::broadcast;
+use yotquitas_core::{Address, Block, Hash};
+
+/// Number of events buffered for each subscriber before it starts lagging
+pub const EVENT_BUS_CAPACITY: usize = 1024;
+
+/// Something that happened on the node that subscribers may care about
+#[derive(Debug, Clone)]
+pub enum NodeEvent {
+    /// A block was sealed and became the chain head
+    NewHead(Block),
+    /// A transaction was accepted for inclusion
+    PendingTransaction(Hash),
+    /// An account's balance changed in a new block
+    BalanceChanged {
+        address: Address,
+        balance: u64,
+        block_number: u64,
+    },
+}
+
+/// Broadcast channel fanning node events out to every subscriber
+pub struct EventBus {
+    sender: broadcast::Sender<NodeEvent>,
+}
+
+impl EventBus {
+    /// Create an event bus with the default per-subscriber capacity
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(EVENT_BUS_CAPACITY);
+        Self { sender }
+    }
+
+    /// Publish an event; it is dropped if nobody is subscribed
+    pub fn publish(&self, event: NodeEvent) {
+        let _ = self.sender.send(event);
+    }
+
+    /// Subscribe to all events published from now on
+    pub fn subscribe(&self) -> broadcast::Receiver<NodeEvent> {
+        self.sender.subscribe()
+    }
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,149 @@
+//! Base fee market and gas price oracle used for fee recommendations
+//!
+//! Every block carries a base fee per unit of gas that moves toward a target
+//! block gas usage, as in EIP-1559. The base fee part of each fee is burned
+//! and the priority tip is paid to the block proposer.
+
+use std::cmp::Ordering;
+
+/// Base fee of the genesis block
+pub const INITIAL_BASE_FEE: u64 = 1;
+
+/// Lowest base fee a block can have
+pub const MIN_BASE_FEE: u64 = 1;
+
+/// Gas all transactions of one block may use together
+pub const BLOCK_GAS_LIMIT: u64 = 30_000_000;
+
+/// Block gas usage the base fee steers toward
+pub const BLOCK_GAS_TARGET: u64 = BLOCK_GAS_LIMIT / 2;
+
+/// Bounds the base fee change between blocks to 1/8 of the parent's
+pub const BASE_FEE_CHANGE_DENOMINATOR: u64 = 8;
+
+/// Lowest priority fee ever recommended
+pub const MIN_PRIORITY_FEE: u64 = 1;
+
+/// Recent blocks sampled by the gas price oracle
+pub const GAS_PRICE_SAMPLE_BLOCKS: u64 = 20;
+
+/// Percentile of recent priority fees recommended to new transactions
+pub const GAS_PRICE_PERCENTILE: usize = 60;
+
+/// Most blocks a single `eth_feeHistory` query may cover
+pub const FEE_HISTORY_MAX_BLOCKS: u64 = 1024;
+
+/// Base fee of the block following one with the given base fee and gas usage
+///
+/// Blocks above the target raise the base fee by up to 1/8 and by at least
+/// 1; blocks below the target lower it by up to 1/8, down to the minimum.
+pub fn next_base_fee(parent_base_fee: u64, parent_gas_used: u64) -> u64 {
+    // delta = parent_base_fee * |gas_used - target| / target / 8
+    let delta = |gas_delta: u64| {
+        (parent_base_fee as u128 * gas_delta as u128
+            / BLOCK_GAS_TARGET as u128
+            / BASE_FEE_CHANGE_DENOMINATOR as u128) as u64
+    };
+    match parent_gas_used.cmp(&BLOCK_GAS_TARGET) {
+        Ordering::Equal => parent_base_fee.max(MIN_BASE_FEE),
+        Ordering::Greater => {
+            let increase = delta(parent_gas_used - BLOCK_GAS_TARGET).max(1);
+            parent_base_fee.saturating_add(increase)
+        }
+        Ordering::Less => {
+            let decrease = delta(BLOCK_GAS_TARGET - parent_gas_used);
+            parent_base_fee.saturating_sub(decrease).max(MIN_BASE_FEE)
+        }
+    }
+}
+
+/// Recommend a priority fee from the tips paid in recent blocks
+///
+/// Busy periods push recent tips up, so the percentile follows congestion;
+/// with no recent transactions the minimum tip is returned.
+pub fn suggest_priority_fee(mut recent_tips: Vec<u64>) -> u64 {
+    if recent_tips.is_empty() {
+        return MIN_PRIORITY_FEE;
+    }
+    recent_tips.sort_unstable();
+    let index = (recent_tips.len() - 1) * GAS_PRICE_PERCENTILE / 100;
+    recent_tips[index].max(MIN_PRIORITY_FEE)
+}
+
+/// Priority fees paid in one block at each of the given percentiles
+///
+/// `tips` holds the tip per unit of gas and the gas used of each transaction.
+/// Percentiles are weighted by gas used, as `eth_feeHistory` specifies; an
+/// empty block reports zero tips.
+pub fn reward_percentiles(mut tips: Vec<(u64, u64)>, percentiles: &[f64]) -> Vec<u64> {
+    if tips.is_empty() {
+        return vec![0; percentiles.len()];
+    }
+    tips.sort_unstable();
+    let total_gas: u64 = tips.iter().map(|(_, gas_used)| gas_used).sum();
+
+    percentiles
+        .iter()
+        .map(|percentile| {
+            let threshold = total_gas as f64 * percentile / 100.0;
+            let mut cumulative = 0;
+            for (tip, gas_used) in &tips {
+                cumulative += gas_used;
+                if cumulative as f64 >= threshold {
+                    return *tip;
+                }
+            }
+            tips[tips.len() - 1].0
+        })
+        .collect()
+}
+
+/// Base fee and priority fee statistics of a range of blocks
+#[derive(Debug, Clone, PartialEq)]
+pub struct FeeHistory {
+    /// First block of the range
+    pub oldest_block: u64,
+    /// Base fee of each block, plus the one of the block after the newest
+    pub base_fee_per_gas: Vec<u64>,
+    /// Gas used of each block relative to the block gas limit
+    pub gas_used_ratio: Vec<f64>,
+    /// Priority fees of each block at the requested percentiles
+    pub reward: Vec<Vec<u64>>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_next_base_fee() {
+        assert_eq!(next_base_fee(1_000, BLOCK_GAS_TARGET), 1_000);
+        // A full block raises the base fee by 1/8, an empty one lowers it by 1/8
+        assert_eq!(next_base_fee(1_000, BLOCK_GAS_LIMIT), 1_125);
+        assert_eq!(next_base_fee(1_000, 0), 875);
+        assert_eq!(next_base_fee(1_000, BLOCK_GAS_TARGET * 3 / 2), 1_062);
+        // Small base fees still rise and never fall below the minimum
+        assert_eq!(next_base_fee(1, BLOCK_GAS_TARGET + 1), 2);
+        assert_eq!(next_base_fee(MIN_BASE_FEE, 0), MIN_BASE_FEE);
+        assert_eq!(next_base_fee(u64::MAX, BLOCK_GAS_LIMIT), u64::MAX);
+    }
+
+    #[test]
+    fn test_suggest_priority_fee() {
+        assert_eq!(suggest_priority_fee(vec![]), MIN_PRIORITY_FEE);
+        assert_eq!(suggest_priority_fee(vec![0, 0]), MIN_PRIORITY_FEE);
+        assert_eq!(suggest_priority_fee(vec![5]), 5);
+        assert_eq!(suggest_priority_fee(vec![9, 1, 3, 7, 5, 2]), 5);
+    }
+
+    #[test]
+    fn test_reward_percentiles() {
+        assert_eq!(reward_percentiles(vec![], &[50.0]), vec![0]);
+        // The tip of 1 covers 3/4 of the gas used
+        let tips = vec![(5, 25_000), (1, 75_000)];
+        assert_eq!(
+            reward_percentiles(tips, &[0.0, 50.0, 75.0, 76.0, 100.0]),
+            vec![1, 1, 1, 5, 5]
+        );
+    }
+}
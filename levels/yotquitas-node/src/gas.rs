@@ -0,0 +1,151 @@
+//! Gas schedule and metering
+
+use yotquitas_core::Transaction;
+
+/// Largest gas limit a single transaction may set
+pub const MAX_GAS_LIMIT: u64 = 10_000_000;
+
+/// Gas cost of each operation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GasSchedule {
+    /// Charged once per transaction
+    pub base: u64,
+    /// Per byte of the encoded payload
+    pub payload_byte: u64,
+    /// Per signature verification
+    pub signature_check: u64,
+    /// Per state value read during execution
+    pub state_read: u64,
+    /// Per state value written during execution
+    pub state_write: u64,
+    /// Per VM instruction executed
+    pub instruction: u64,
+}
+
+impl Default for GasSchedule {
+    fn default() -> Self {
+        Self {
+            base: 5_000,
+            payload_byte: 16,
+            signature_check: 3_000,
+            state_read: 800,
+            state_write: 5_000,
+            instruction: 1,
+        }
+    }
+}
+
+impl GasSchedule {
+    /// Gas charged before execution starts: base, payload bytes and signature
+    pub fn intrinsic_gas(&self, tx: &Transaction) -> u64 {
+        let payload_len = serde_json::to_vec(&tx.payload).map_or(0, |bytes| bytes.len()) as u64;
+        self.base
+            .saturating_add(payload_len.saturating_mul(self.payload_byte))
+            .saturating_add(self.signature_check)
+    }
+}
+
+/// Execution ran out of gas
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OutOfGas;
+
+impl std::fmt::Display for OutOfGas {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Out of gas")
+    }
+}
+
+impl std::error::Error for OutOfGas {}
+
+/// Tracks gas used by one transaction against its limit
+#[derive(Debug, Clone)]
+pub struct GasMeter {
+    schedule: GasSchedule,
+    limit: u64,
+    used: u64,
+}
+
+impl GasMeter {
+    pub fn new(schedule: GasSchedule, limit: u64) -> Self {
+        Self {
+            schedule,
+            limit,
+            used: 0,
+        }
+    }
+
+    /// Charge `amount` gas; on failure the meter is left exhausted
+    pub fn charge(&mut self, amount: u64) -> Result<(), OutOfGas> {
+        match self.used.checked_add(amount) {
+            Some(used) if used <= self.limit => {
+                self.used = used;
+                Ok(())
+            }
+            _ => {
+                self.used = self.limit;
+                Err(OutOfGas)
+            }
+        }
+    }
+
+    /// Charge for `count` state reads
+    pub fn charge_reads(&mut self, count: u64) -> Result<(), OutOfGas> {
+        self.charge(self.schedule.state_read.saturating_mul(count))
+    }
+
+    /// Charge for `count` state writes
+    pub fn charge_writes(&mut self, count: u64) -> Result<(), OutOfGas> {
+        self.charge(self.schedule.state_write.saturating_mul(count))
+    }
+
+    /// Charge for `count` VM instructions
+    pub fn charge_instructions(&mut self, count: u64) -> Result<(), OutOfGas> {
+        self.charge(self.schedule.instruction.saturating_mul(count))
+    }
+
+    pub fn used(&self) -> u64 {
+        self.used
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gas_meter() {
+        let schedule = GasSchedule::default();
+        let mut meter = GasMeter::new(schedule, 10_000);
+
+        meter.charge_reads(2).unwrap();
+        meter.charge_writes(1).unwrap();
+        assert_eq!(meter.used(), 2 * schedule.state_read + schedule.state_write);
+
+        // Running out exhausts the whole limit
+        assert_eq!(meter.charge_writes(1), Err(OutOfGas));
+        assert_eq!(meter.used(), 10_000);
+        assert_eq!(meter.charge(u64::MAX), Err(OutOfGas));
+    }
+
+    #[test]
+    fn test_intrinsic_gas_grows_with_payload() {
+        let schedule = GasSchedule::default();
+        let (_, pubkey) = yotquitas_core::generate_keypair();
+        let deploy = |bytecode| {
+            Transaction::new(
+                pubkey,
+                yotquitas_core::TransactionPayload::DeployModule { bytecode },
+                MAX_GAS_LIMIT,
+                1,
+                0,
+                0,
+            )
+        };
+
+        let small = schedule.intrinsic_gas(&deploy(vec![1; 10]));
+        let large = schedule.intrinsic_gas(&deploy(vec![1; 20]));
+        assert!(small > schedule.base + schedule.signature_check);
+        // Each extra byte of bytecode encodes as two JSON bytes (",1")
+        assert_eq!(large - small, 20 * schedule.payload_byte);
+    }
+}
@@ -1,52 +1,313 @@
-use yotquitas_core::{Block, BlockHeader, Address};
-use std::collections::HashMap;
+//! Genesis specification and block
+//!
+//! The genesis is read from a standalone JSON or TOML file so every node
+//! started from the same file derives the same genesis block. Nothing in the
+//! genesis block depends on the local clock: the timestamp comes from the
+//! file, and the block's previous hash (there is no parent) is a digest of
+//! the whole specification, so two files that differ in any allocation,
+//! validator or parameter yield different genesis hashes.
 
-/// Genesis block configuration
-pub struct GenesisConfig {
-    pub treasury_address: Address,
-    pub initial_supply: u64,
+use crate::fee::INITIAL_BASE_FEE;
+use crate::vesting::VestingSchedule;
+use anyhow::Result;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::{BTreeSet, HashMap};
+use std::path::Path;
+use yotquitas_core::{sha256, Address, Block, BlockHeader, Hash};
+
+/// Contents of a genesis file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Genesis {
+    pub chain_id: u64,
+    /// Unix time in seconds stamped on the genesis block
     pub timestamp: u64,
+    #[serde(default)]
+    pub consensus: ConsensusParams,
+    /// Balances funded at genesis
+    pub alloc: Vec<Allocation>,
+    /// Validators of the initial set
+    #[serde(default)]
+    pub validators: Vec<Validator>,
+}
+
+/// Chain parameters fixed at genesis
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ConsensusParams {
+    /// Base fee per gas of the genesis block
+    pub initial_base_fee: u64,
 }
 
-impl Default for GenesisConfig {
+impl Default for ConsensusParams {
     fn default() -> Self {
         Self {
-            treasury_address: [0u8; 32], // Will be set from keypair
-            initial_supply: 1_000_000_000_000_000_000, // 1 billion AEQ (with 9 decimals)
-            timestamp: std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap()
-                .as_secs(),
+            initial_base_fee: INITIAL_BASE_FEE,
         }
     }
 }
 
-/// Create the genesis block
-pub fn create_genesis_block(config: GenesisConfig) -> Block {
-    // Genesis block has index 0 and zero previous hash
-    let header = BlockHeader::new(0, config.timestamp, [0u8; 32]);
+/// Balance credited to an account at genesis
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Allocation {
+    #[serde(with = "hex_address")]
+    pub address: Address,
+    pub balance: u64,
+    /// Part of the balance that unlocks over time
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vesting: Option<VestingSchedule>,
+}
+
+/// Member of the initial validator set
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Validator {
+    #[serde(with = "hex_address")]
+    pub address: Address,
+    /// Voting power of the validator
+    pub power: u64,
+}
+
+impl Genesis {
+    /// Read a genesis file, parsed as TOML if it has a `.toml` extension and
+    /// as JSON otherwise
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("Failed to read genesis file {:?}: {}", path, e))?;
+        let genesis: Genesis = if path.extension().is_some_and(|ext| ext == "toml") {
+            toml::from_str(&content)
+                .map_err(|e| anyhow::anyhow!("Failed to parse genesis file {:?}: {}", path, e))?
+        } else {
+            serde_json::from_str(&content)
+                .map_err(|e| anyhow::anyhow!("Failed to parse genesis file {:?}: {}", path, e))?
+        };
+        genesis.validate()?;
+        Ok(genesis)
+    }
+
+    /// Reject duplicate accounts, an overflowing supply, invalid vesting
+    /// schedules and powerless validators
+    pub fn validate(&self) -> Result<()> {
+        let mut funded = BTreeSet::new();
+        let mut supply = 0u64;
+        for allocation in &self.alloc {
+            if !funded.insert(allocation.address) {
+                anyhow::bail!(
+                    "Genesis allocates to {} more than once",
+                    hex::encode(allocation.address)
+                );
+            }
+            supply = supply
+                .checked_add(allocation.balance)
+                .ok_or_else(|| anyhow::anyhow!("Genesis supply overflows"))?;
+            if let Some(vesting) = &allocation.vesting {
+                vesting.validate()?;
+                if vesting.amount > allocation.balance {
+                    anyhow::bail!(
+                        "Genesis locks {} for {} but only allocates {}",
+                        vesting.amount,
+                        hex::encode(allocation.address),
+                        allocation.balance
+                    );
+                }
+            }
+        }
+
+        let mut validators = BTreeSet::new();
+        for validator in &self.validators {
+            if !validators.insert(validator.address) {
+                anyhow::bail!(
+                    "Genesis lists validator {} more than once",
+                    hex::encode(validator.address)
+                );
+            }
+            if validator.power == 0 {
+                anyhow::bail!(
+                    "Genesis validator {} has no voting power",
+                    hex::encode(validator.address)
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Digest of the specification, independent of the order of its lists
+    pub fn digest(&self) -> Hash {
+        let mut canonical = self.clone();
+        canonical.alloc.sort_by_key(|allocation| allocation.address);
+        canonical
+            .validators
+            .sort_by_key(|validator| validator.address);
+        sha256(&serde_json::to_vec(&canonical).expect("genesis serializes to JSON"))
+    }
+
+    /// Build the genesis block
+    pub fn block(&self) -> Block {
+        let header = BlockHeader::new(0, self.timestamp, self.digest()).with_fees(
+            self.consensus.initial_base_fee,
+            0,
+            [0u8; 32],
+        );
+
+        // Genesis block contains no transactions (allocations are funded in state)
+        Block::new(header, vec![])
+    }
+
+    /// Initial account balances
+    pub fn balances(&self) -> HashMap<Address, u64> {
+        self.alloc
+            .iter()
+            .map(|allocation| (allocation.address, allocation.balance))
+            .collect()
+    }
+
+    /// Vesting schedules of the locked allocations
+    pub fn vesting(&self) -> Vec<(Address, VestingSchedule)> {
+        self.alloc
+            .iter()
+            .filter_map(|allocation| Some((allocation.address, allocation.vesting?)))
+            .collect()
+    }
 
-    // Genesis block contains no transactions (treasury is pre-funded in state)
-    Block::new(header, vec![])
+    /// Sum of all allocations
+    pub fn total_supply(&self) -> u64 {
+        self.alloc.iter().map(|allocation| allocation.balance).sum()
+    }
 }
 
-/// Get initial account balances for genesis
-pub fn get_initial_balances(config: &GenesisConfig) -> HashMap<Address, u64> {
-    let mut balances = HashMap::new();
-    balances.insert(config.treasury_address, config.initial_supply);
-    balances
+/// Serde helpers for hex-encoded addresses, with or without `0x`
+mod hex_address {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(address: &Address, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&hex::encode(address))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Address, D::Error> {
+        let address_hex = String::deserialize(deserializer)?;
+        yotquitas_core::parse_hash(&address_hex).map_err(serde::de::Error::custom)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn genesis() -> Genesis {
+        Genesis {
+            chain_id: 1337,
+            timestamp: 1_700_000_000,
+            consensus: ConsensusParams::default(),
+            alloc: vec![
+                Allocation {
+                    address: [1u8; 32],
+                    balance: 1_000,
+                    vesting: None,
+                },
+                Allocation {
+                    address: [2u8; 32],
+                    balance: 500,
+                    vesting: Some(VestingSchedule {
+                        amount: 400,
+                        start: 1_700_000_000,
+                        cliff_secs: 0,
+                        duration_secs: 1_000,
+                    }),
+                },
+            ],
+            validators: vec![Validator {
+                address: [1u8; 32],
+                power: 10,
+            }],
+        }
+    }
+
     #[test]
     fn test_genesis_block_creation() {
-        let config = GenesisConfig::default();
-        let genesis = create_genesis_block(config);
+        let genesis = genesis();
+        let block = genesis.block();
+
+        assert_eq!(block.index(), 0);
+        assert_eq!(block.header.timestamp, 1_700_000_000);
+        assert_eq!(block.previous_hash(), genesis.digest());
+        assert_eq!(block.header.base_fee_per_gas, INITIAL_BASE_FEE);
+        assert_eq!(genesis.total_supply(), 1_500);
+        assert_eq!(genesis.balances()[&[2u8; 32]], 500);
+        assert_eq!(genesis.vesting().len(), 1);
+        assert_eq!(genesis.vesting()[0].0, [2u8; 32]);
+    }
+
+    #[test]
+    fn test_genesis_hash_is_deterministic() {
+        let genesis = genesis();
+        assert_eq!(genesis.block().hash(), genesis.clone().block().hash());
+
+        // Listing order does not matter, contents do
+        let mut reordered = genesis.clone();
+        reordered.alloc.reverse();
+        assert_eq!(reordered.block().hash(), genesis.block().hash());
+        let mut changed = genesis.clone();
+        changed.alloc[1].balance += 1;
+        assert_ne!(changed.block().hash(), genesis.block().hash());
+    }
+
+    #[test]
+    fn test_load_genesis_file() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let json_path = temp_dir.path().join("genesis.json");
+        std::fs::write(&json_path, serde_json::to_string(&genesis()).unwrap()).unwrap();
+        assert_eq!(Genesis::load(&json_path).unwrap(), genesis());
+
+        let toml_path = temp_dir.path().join("genesis.toml");
+        std::fs::write(
+            &toml_path,
+            format!(
+                r#"
+chain_id = 1337
+timestamp = 1700000000
+
+[[alloc]]
+address = "0x{}"
+balance = 1000
+"#,
+                hex::encode([1u8; 32])
+            ),
+        )
+        .unwrap();
+        let loaded = Genesis::load(&toml_path).unwrap();
+        assert_eq!(loaded.alloc[0].address, [1u8; 32]);
+        assert_eq!(loaded.consensus, ConsensusParams::default());
+        assert!(loaded.validators.is_empty());
+
+        // A misspelled key is an error rather than silently dropped
+        std::fs::write(
+            &toml_path,
+            format!(
+                r#"
+chain_id = 1337
+timestamp = 1700000000
+
+[[alloc]]
+address = "0x{}"
+balance = 1000
+vestng = {{ amount = 500, start = 0, cliff_secs = 0, duration_secs = 10 }}
+"#,
+                hex::encode([1u8; 32])
+            ),
+        )
+        .unwrap();
+        let err = Genesis::load(&toml_path).unwrap_err();
+        assert!(format!("{:#}", err).contains("vestng"), "{:#}", err);
+
+        let mut duplicate = genesis();
+        duplicate.alloc[1].address = [1u8; 32];
+        std::fs::write(&json_path, serde_json::to_string(&duplicate).unwrap()).unwrap();
+        assert!(Genesis::load(&json_path).is_err());
 
-        assert_eq!(genesis.index(), 0);
-        assert_eq!(genesis.previous_hash(), [0u8; 32]);
+        let mut overlocked = genesis();
+        overlocked.alloc[1].vesting.as_mut().unwrap().amount = 501;
+        std::fs::write(&json_path, serde_json::to_string(&overlocked).unwrap()).unwrap();
+        assert!(Genesis::load(&json_path).is_err());
     }
 }
@@ -0,0 +1,192 @@
+//! Overflow-safe balance updates used by state transitions
+
+use crate::state::StateOverlay;
+use yotquitas_core::Address;
+
+/// Why a balance update was refused
+#[derive(Debug, thiserror::Error)]
+pub enum LedgerError {
+    /// The account holds less than the amount to take from it
+    #[error("Insufficient balance")]
+    InsufficientBalance {
+        address: Address,
+        balance: u64,
+        required: u64,
+    },
+    /// Crediting the account would exceed the largest representable balance
+    #[error("Balance overflow")]
+    BalanceOverflow {
+        address: Address,
+        balance: u64,
+        amount: u64,
+    },
+    /// The underlying state could not be read
+    #[error(transparent)]
+    State(#[from] anyhow::Error),
+}
+
+/// Checked balance arithmetic over a state overlay
+///
+/// Every update either applies in full or leaves the overlay untouched, and
+/// no update creates or destroys funds except [`Ledger::debit`] and
+/// [`Ledger::credit`], which callers pair up, and [`Ledger::burn`], which
+/// records what it destroys in the tracked supply.
+pub struct Ledger<'s, 'a> {
+    state: &'s mut StateOverlay<'a>,
+}
+
+impl<'s, 'a> Ledger<'s, 'a> {
+    pub fn new(state: &'s mut StateOverlay<'a>) -> Self {
+        Self { state }
+    }
+
+    /// Get account balance
+    pub fn balance(&self, address: &Address) -> Result<u64, LedgerError> {
+        Ok(self.state.get_balance(address)?)
+    }
+
+    /// Take `amount` from an account
+    pub fn debit(&mut self, address: &Address, amount: u64) -> Result<(), LedgerError> {
+        let balance = self.balance(address)?;
+        let remaining = balance
+            .checked_sub(amount)
+            .ok_or(LedgerError::InsufficientBalance {
+                address: *address,
+                balance,
+                required: amount,
+            })?;
+        self.state.set_balance(address, remaining);
+        Ok(())
+    }
+
+    /// Add `amount` to an account
+    pub fn credit(&mut self, address: &Address, amount: u64) -> Result<(), LedgerError> {
+        let balance = self.balance(address)?;
+        let total = balance
+            .checked_add(amount)
+            .ok_or(LedgerError::BalanceOverflow {
+                address: *address,
+                balance,
+                amount,
+            })?;
+        self.state.set_balance(address, total);
+        Ok(())
+    }
+
+    /// Take `amount` from an account and record it as burned
+    pub fn burn(&mut self, address: &Address, amount: u64) -> Result<(), LedgerError> {
+        self.debit(address, amount)?;
+        self.state.burn(amount)?;
+        Ok(())
+    }
+
+    /// Move `amount` between two accounts
+    ///
+    /// Both balances are checked before either is written. A transfer to the
+    /// sender itself only checks that the sender holds `amount`.
+    pub fn transfer(
+        &mut self,
+        from: &Address,
+        to: &Address,
+        amount: u64,
+    ) -> Result<(), LedgerError> {
+        let from_balance = self.balance(from)?;
+        let from_remaining =
+            from_balance
+                .checked_sub(amount)
+                .ok_or(LedgerError::InsufficientBalance {
+                    address: *from,
+                    balance: from_balance,
+                    required: amount,
+                })?;
+        if from == to {
+            return Ok(());
+        }
+
+        let to_balance = self.balance(to)?;
+        let to_total = to_balance
+            .checked_add(amount)
+            .ok_or(LedgerError::BalanceOverflow {
+                address: *to,
+                balance: to_balance,
+                amount,
+            })?;
+        self.state.set_balance(from, from_remaining);
+        self.state.set_balance(to, to_total);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::StateDB;
+    use proptest::prelude::*;
+    use tempfile::TempDir;
+
+    const ACCOUNTS: [Address; 4] = [[1u8; 32], [2u8; 32], [3u8; 32], [4u8; 32]];
+
+    #[test]
+    fn test_ledger_rejects_overflow_and_overdraft() -> anyhow::Result<()> {
+        let temp_dir = TempDir::new()?;
+        let db = StateDB::open(temp_dir.path())?;
+        db.set_balance(&ACCOUNTS[0], 100)?;
+        db.set_balance(&ACCOUNTS[1], u64::MAX - 10)?;
+        let mut state = db.overlay();
+        let mut ledger = Ledger::new(&mut state);
+
+        assert!(matches!(
+            ledger.transfer(&ACCOUNTS[0], &ACCOUNTS[1], 50),
+            Err(LedgerError::BalanceOverflow { amount: 50, .. })
+        ));
+        assert!(matches!(
+            ledger.debit(&ACCOUNTS[0], 101),
+            Err(LedgerError::InsufficientBalance { balance: 100, .. })
+        ));
+        // Failed updates leave both accounts untouched
+        assert_eq!(ledger.balance(&ACCOUNTS[0])?, 100);
+        assert_eq!(ledger.balance(&ACCOUNTS[1])?, u64::MAX - 10);
+
+        // A self-transfer neither creates nor destroys funds
+        ledger.transfer(&ACCOUNTS[0], &ACCOUNTS[0], 100)?;
+        assert_eq!(ledger.balance(&ACCOUNTS[0])?, 100);
+        assert!(ledger.transfer(&ACCOUNTS[0], &ACCOUNTS[0], 101).is_err());
+
+        ledger.transfer(&ACCOUNTS[0], &ACCOUNTS[1], 10)?;
+        assert_eq!(ledger.balance(&ACCOUNTS[0])?, 90);
+        assert_eq!(ledger.balance(&ACCOUNTS[1])?, u64::MAX);
+        Ok(())
+    }
+
+    proptest! {
+        #[test]
+        fn prop_transfers_conserve_supply(
+            shares in prop::collection::vec(0..=u64::MAX / 4, ACCOUNTS.len()),
+            transfers in prop::collection::vec(
+                (0..ACCOUNTS.len(), 0..ACCOUNTS.len(), prop_oneof![0..1_000u64, any::<u64>()]),
+                0..32,
+            ),
+        ) {
+            let temp_dir = TempDir::new().unwrap();
+            let db = StateDB::open(temp_dir.path()).unwrap();
+            for (address, balance) in ACCOUNTS.iter().zip(&shares) {
+                db.set_balance(address, *balance).unwrap();
+            }
+            let supply: u128 = shares.iter().map(|share| *share as u128).sum();
+
+            let mut state = db.overlay();
+            let mut ledger = Ledger::new(&mut state);
+            for (from, to, amount) in transfers {
+                let before = ledger.balance(&ACCOUNTS[from]).unwrap();
+                let result = ledger.transfer(&ACCOUNTS[from], &ACCOUNTS[to], amount);
+                prop_assert_eq!(result.is_ok(), amount <= before);
+
+                let total: u128 = ACCOUNTS
+                    .iter()
+                    .map(|address| ledger.balance(address).unwrap() as u128)
+                    .sum();
+                prop_assert_eq!(total, supply);
+            }
+        }
+    }
+}
@@ -0,0 +1,19 @@
+//! Yotquitas execution node
+//!
+//! The node binary is a thin command line around this library, which can
+//! also be embedded, for example to run a node in-process in tests.
+
+pub mod api;
+pub mod config;
+pub mod events;
+pub mod fee;
+pub mod gas;
+pub mod genesis;
+pub mod ledger;
+pub mod mempool;
+pub mod metrics;
+pub mod node;
+pub mod receipt;
+pub mod state;
+pub mod vesting;
+pub mod vm;
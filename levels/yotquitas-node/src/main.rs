@@ -1,131 +1,208 @@
 use anyhow::Result;
-use clap::Parser;
-use serde::Deserialize;
-use std::env;
-use std::{path::Path, sync::Arc};
-
-mod api;
-mod genesis;
-mod node;
-mod state;
-mod vm;
-
-use node::Node;
-use state::StateDB;
-
-/// Configuration structure matching TOML files
-#[derive(Debug, Deserialize, Clone)]
-struct Config {
-    network: NetworkConfig,
-    node: NodeConfig,
-    genesis: GenesisConfigToml,
-    logging: LoggingConfig,
-}
+use clap::{Args, Parser, Subcommand};
+use std::{
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+
+mod commands;
+
+use yotquitas_node::api;
+use yotquitas_node::genesis::Genesis;
+use yotquitas_core::parse_hash;
+use yotquitas_node::node::Node;
+use yotquitas_node::state::StateDB;
 
-#[derive(Debug, Deserialize, Clone)]
-struct NetworkConfig {
-    chain_id: u64,
-    network_id: u64,
-}
+/// Yotquitas Node - Execution Node for Yotquitas Blockchain
+///
+/// Settings come from the config file, then `YOTQUITAS_<SECTION>_<KEY>`
+/// environment variables, then the flags below, each overriding the last.
+/// Without a subcommand the node runs.
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+#[command(args_conflicts_with_subcommands = true)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
 
-#[derive(Debug, Deserialize, Clone)]
-struct NodeConfig {
-    data_dir: String,
-    rpc_addr: String,
-    rpc_cors: Vec<String>,
+    #[command(flatten)]
+    run: RunArgs,
 }
 
-#[derive(Debug, Deserialize, Clone)]
-pub struct GenesisConfigToml {
-    treasury_address: String,
-    initial_supply: u64,
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Write a default config and genesis file
+    Init(commands::InitArgs),
+    /// Run the node
+    Run(RunArgs),
+    /// Generate a keypair and print its address
+    Keygen(commands::KeygenArgs),
+    /// Print a block, account or transaction from the data directory
+    Inspect(commands::InspectArgs),
+    /// Print the genesis block hash of a genesis file
+    GenesisHash {
+        /// Genesis file, TOML or JSON
+        file: PathBuf,
+    },
 }
 
-#[derive(Debug, Deserialize, Clone)]
-struct LoggingConfig {
-    level: String,
-}
+#[derive(Args, Debug)]
+struct RunArgs {
+    #[command(flatten)]
+    config: commands::ConfigArgs,
 
-/// Yotquitas Node - Execution Node for Yotquitas Blockchain
-#[derive(Parser, Debug)]
-#[command(author, version, about, long_about = None)]
-struct Args {
-    /// Configuration type: 'dev' or 'mainnet'
-    #[arg(long, default_value = "dev")]
-    config_type: String,
+    /// Override `node.rpc_addr`
+    #[arg(long)]
+    rpc_addr: Option<String>,
+
+    /// Check that all balances add up to minted minus burned, then exit
+    #[arg(long)]
+    verify_supply: bool,
 }
 
-/// Load configuration from TOML file
-fn load_config(config_type: &str) -> Result<Config> {
-    // Convert the string to a Path object
-    let parent = if let Some(parent) = Path::new(file!()).parent() {
-        let ancestor = if let Some(parent) = parent.parent() {
-            parent
-        } else {
-            anyhow::bail!("Config directory not found. Please run from workspace root or yotquitas-node directory")
-        };
-        ancestor
-    } else {
-        anyhow::bail!("Config directory not found. Please run from workspace root or yotquitas-node directory")
-    };
-
-    // Try relative to current directory first, then workspace-relative
-    let config_path = if let Some(parent) = parent.parent() {
-        parent.join("config").join(format!("{}.toml", config_type))
-    } else {
-        anyhow::bail!("Config directory not found. Please run from workspace root or yotquitas-node directory")
-    };
-
-    let config_content = std::fs::read_to_string(&config_path)
-        .map_err(|e| anyhow::anyhow!("Failed to read config file {:?}: {}", config_path, e))?;
-
-    let config: Config = toml::from_str(&config_content)
-        .map_err(|e| anyhow::anyhow!("Failed to parse config file: {}", e))?;
-
-    Ok(config)
+/// Scan all balances and fail unless they add up to minted minus burned
+fn verify_supply(state: &StateDB) -> Result<()> {
+    let audit = state.audit_supply()?;
+    tracing::info!("Accounts: {}", audit.accounts);
+    tracing::info!("Minted: {}", audit.supply.minted);
+    tracing::info!("Burned: {}", audit.supply.burned);
+    tracing::info!("Sum of balances: {}", audit.balances);
+    if !audit.is_consistent() {
+        anyhow::bail!(
+            "Supply mismatch: balances sum to {}, expected {}",
+            audit.balances,
+            audit.supply.total()
+        );
+    }
+    tracing::info!("Supply verified: {}", audit.supply.total());
+    Ok(())
 }
 
-#[tokio::main]
-async fn main() -> Result<()> {
+fn main() -> Result<()> {
     // Parse command line arguments
-    let args = Args::parse();
+    let cli = Cli::parse();
+    match cli.command {
+        Some(Command::Init(args)) => commands::init(args),
+        Some(Command::Run(args)) => run(args),
+        Some(Command::Keygen(args)) => commands::keygen(args),
+        Some(Command::Inspect(args)) => commands::inspect(args),
+        Some(Command::GenesisHash { file }) => commands::genesis_hash(&file),
+        None => run(cli.run),
+    }
+}
 
+#[tokio::main]
+async fn run(args: RunArgs) -> Result<()> {
     // Load configuration
-    let config = load_config(&args.config_type)?;
+    let (config_path, mut config) = args.config.load()?;
+    if let Some(rpc_addr) = args.rpc_addr {
+        config.node.rpc_addr = rpc_addr;
+    }
 
     // Initialize tracing with config level
     let filter = tracing_subscriber::EnvFilter::try_from_default_env()
         .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new(&config.logging.level));
-    tracing_subscriber::fmt().with_env_filter(filter).init();
+    let (filter, filter_handle) = tracing_subscriber::reload::Layer::new(filter);
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(tracing_subscriber::fmt::layer())
+        .init();
+    let set_log_level: api::LogLevelHandle = Arc::new(move |directive: &str| {
+        let filter = tracing_subscriber::EnvFilter::try_new(directive)?;
+        filter_handle.reload(filter)?;
+        Ok(())
+    });
 
     tracing::info!("Starting Yotquitas Node...");
-    tracing::info!("Configuration type: {}", args.config_type);
-    tracing::info!("Chain ID: {}", config.network.chain_id);
+    tracing::info!("Configuration file: {:?}", config_path);
     tracing::info!("Network ID: {}", config.network.network_id);
     tracing::info!("Data directory: {}", config.node.data_dir);
     tracing::info!("RPC address: {}", config.node.rpc_addr);
 
+    let genesis = Genesis::load(&config.genesis.file)?;
+    tracing::info!("Genesis file: {:?}", config.genesis.file);
+    tracing::info!("Genesis hash: {}", hex::encode(genesis.block().hash()));
+    tracing::info!("Chain ID: {}", genesis.chain_id);
+
     // Initialize state database
     let state = Arc::new(StateDB::open(&config.node.data_dir)?);
     tracing::info!("State database initialized");
 
+    if args.verify_supply {
+        return verify_supply(&state);
+    }
+
     // Create node with config
-    let node = Arc::new(Node::new(
-        state,
-        &config.genesis,
-        config.network.chain_id,
-        config.network.network_id,
-    )?);
+    let mut node = Node::new(state, &genesis, config.network.network_id)?;
+    match &config.node.proposer_address {
+        Some(proposer) => {
+            let proposer = parse_hash(proposer)
+                .map_err(|e| anyhow::anyhow!("Invalid proposer address: {}", e))?;
+            tracing::info!("Block proposer: {}", hex::encode(proposer));
+            node = node.with_proposer(proposer);
+        }
+        None => {
+            tracing::warn!("No proposer_address configured; priority fees go to the zero address")
+        }
+    }
+    if let Some(collector) = &config.node.fee_collector_address {
+        let collector = parse_hash(collector)
+            .map_err(|e| anyhow::anyhow!("Invalid fee collector address: {}", e))?;
+        tracing::info!("Fee collector: {}", hex::encode(collector));
+        node = node.with_fee_collector(collector);
+    }
+    if let Some(mempool) = &config.mempool {
+        node = node.with_mempool_config(mempool.into());
+    }
+    let node = Arc::new(node);
+    node.restore_mempool().await?;
     tracing::info!("Node initialized");
 
+    // Seal pooled transactions into blocks
+    let producer = node.clone();
+    let block_time = std::time::Duration::from_millis(config.node.block_time_ms.max(1));
+    tracing::info!("Block time: {:?}", block_time);
+    tokio::spawn(async move { producer.run_block_producer(block_time).await });
+
+    // Start the admin server on its own listener
+    if let Some(admin) = &config.admin {
+        let admin_app = api::create_admin_router(
+            node.clone(),
+            api::AdminConfig {
+                token: api::load_or_create_token(Path::new(&admin.token_file))?,
+                snapshot_dir: admin.snapshot_dir.clone().into(),
+                log_level: Some(set_log_level),
+            },
+        );
+        let admin_listener = tokio::net::TcpListener::bind(&admin.addr).await?;
+        if !admin_listener.local_addr()?.ip().is_loopback() {
+            tracing::warn!(
+                "Admin server is reachable beyond localhost on {}",
+                admin.addr
+            );
+        }
+        tracing::info!("Admin server listening on {}", admin.addr);
+        tokio::spawn(async move {
+            if let Err(e) = axum::serve(admin_listener, admin_app).await {
+                tracing::error!("Admin server failed: {}", e);
+            }
+        });
+    }
+
     // Create API router
-    let app = api::create_router(node);
+    let app = api::create_router(node, config.node.rpc_config())?;
 
     // Start server
     let listener = tokio::net::TcpListener::bind(&config.node.rpc_addr).await?;
     tracing::info!("JSON-RPC server listening on {}", config.node.rpc_addr);
 
-    axum::serve(listener, app).await?;
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await?;
 
     Ok(())
 }
@@ -0,0 +1,714 @@
+//! Pool of transactions waiting for inclusion
+//!
+//! Transactions are queued per sender by nonce. A transaction is pending
+//! once every nonce between the sender's account nonce and its own is in
+//! the pool, so it can be included in the next block; the others are
+//! future transactions waiting for the gap to be filled.
+//!
+//! A transaction can be replaced by another one from the same sender with
+//! the same nonce that raises both fee caps by the configured percentage.
+//! Wallets cancel a transaction by replacing it with a zero-value transfer
+//! to the sender.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BinaryHeap, HashMap};
+use std::time::{Duration, Instant};
+use yotquitas_core::{Address, Hash, Transaction};
+
+/// Limits of the pool
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MempoolConfig {
+    /// Most transactions held at once
+    pub max_transactions: usize,
+    /// Most encoded transaction bytes held at once
+    pub max_bytes: usize,
+    /// How long a transaction may wait before it is dropped
+    pub ttl: Duration,
+    /// Percentage by which a replacement must raise both the max fee and the tip
+    pub price_bump_percent: u64,
+}
+
+impl Default for MempoolConfig {
+    fn default() -> Self {
+        Self {
+            max_transactions: 4096,
+            max_bytes: 16 * 1024 * 1024,
+            ttl: Duration::from_secs(3 * 60 * 60),
+            price_bump_percent: 10,
+        }
+    }
+}
+
+/// Why a transaction was not added to the pool
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum MempoolError {
+    #[error("Transaction already known")]
+    AlreadyKnown,
+    #[error("Nonce too low: expected at least {expected}, got {got}")]
+    NonceTooLow { expected: u64, got: u64 },
+    #[error(
+        "Replacement transaction underpriced: needs a max fee per gas of at least \
+         {max_fee_per_gas} and a priority fee per gas of at least {max_priority_fee_per_gas}"
+    )]
+    ReplacementUnderpriced {
+        max_fee_per_gas: u64,
+        max_priority_fee_per_gas: u64,
+    },
+    #[error("Transaction of {0} bytes exceeds the mempool size")]
+    TooLarge(usize),
+    #[error("Mempool is full")]
+    Full,
+}
+
+/// A pooled transaction with its bookkeeping
+#[derive(Debug, Clone)]
+struct Entry {
+    tx: Transaction,
+    sender: Address,
+    /// Encoded size in bytes
+    size: usize,
+    added_at: Instant,
+    /// Arrival order, used to break fee ties
+    sequence: u64,
+}
+
+impl Entry {
+    /// Fee priority when the base fee is not known: tip, then max fee
+    fn priority(&self) -> (u64, u64) {
+        (
+            self.tx
+                .max_priority_fee_per_gas
+                .min(self.tx.max_fee_per_gas),
+            self.tx.max_fee_per_gas,
+        )
+    }
+}
+
+/// Record of a pooled transaction in the on-disk journal
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub tx: Transaction,
+    /// Unix time in seconds at which the transaction entered the pool
+    pub added_at: u64,
+}
+
+/// Transactions removed from the pool to make room for an inserted one
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Inserted {
+    /// Transaction with the same sender and nonce that was replaced
+    pub replaced: Option<Hash>,
+    /// Lower priority transactions evicted because the pool was full
+    pub evicted: Vec<Hash>,
+}
+
+/// Counts of pending and future transactions
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MempoolStatus {
+    pub pending: usize,
+    pub queued: usize,
+}
+
+/// Pooled transactions grouped by sender, each group in nonce order
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MempoolContent {
+    /// Transactions that can be included once their predecessors are
+    pub pending: BTreeMap<Address, Vec<Transaction>>,
+    /// Transactions waiting behind a nonce gap
+    pub queued: BTreeMap<Address, Vec<Transaction>>,
+}
+
+/// A pooled transaction and whether it can go into the next block
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PooledTransaction {
+    pub tx: Transaction,
+    pub pending: bool,
+}
+
+/// In-memory transaction pool with per-sender nonce queues
+#[derive(Debug)]
+pub struct Mempool {
+    config: MempoolConfig,
+    entries: HashMap<Hash, Entry>,
+    by_sender: HashMap<Address, BTreeMap<u64, Hash>>,
+    bytes: usize,
+    next_sequence: u64,
+}
+
+impl Mempool {
+    pub fn new(config: MempoolConfig) -> Self {
+        Self {
+            config,
+            entries: HashMap::new(),
+            by_sender: HashMap::new(),
+            bytes: 0,
+            next_sequence: 0,
+        }
+    }
+
+    /// Number of transactions in the pool
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether the pool holds no transactions
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Encoded size of all transactions in the pool
+    pub fn bytes(&self) -> usize {
+        self.bytes
+    }
+
+    /// Add a transaction whose sender's account nonce is `account_nonce`
+    ///
+    /// A transaction with the sender and nonce of a pooled one replaces it if
+    /// it pays enough more. When the pool is full the transaction with the
+    /// lowest fee priority among each sender's last queued nonce is evicted
+    /// to make room, so evictions never leave a gap behind a sender's
+    /// remaining transactions or the inserted one.
+    pub fn insert(
+        &mut self,
+        tx: Transaction,
+        account_nonce: u64,
+        now: Instant,
+    ) -> Result<Inserted, MempoolError> {
+        let hash = tx.hash();
+        if self.entries.contains_key(&hash) {
+            return Err(MempoolError::AlreadyKnown);
+        }
+        if tx.nonce < account_nonce {
+            return Err(MempoolError::NonceTooLow {
+                expected: account_nonce,
+                got: tx.nonce,
+            });
+        }
+        let sender = tx.sender_address();
+        let replaced = self
+            .by_sender
+            .get(&sender)
+            .and_then(|queue| queue.get(&tx.nonce))
+            .copied();
+        if let Some(replaced) = &replaced {
+            let pooled = &self.entries[replaced].tx;
+            let bump = self.config.price_bump_percent;
+            let max_fee_per_gas = bumped(pooled.max_fee_per_gas, bump);
+            let max_priority_fee_per_gas = bumped(pooled.max_priority_fee_per_gas, bump);
+            if tx.max_fee_per_gas < max_fee_per_gas
+                || tx.max_priority_fee_per_gas < max_priority_fee_per_gas
+            {
+                return Err(MempoolError::ReplacementUnderpriced {
+                    max_fee_per_gas,
+                    max_priority_fee_per_gas,
+                });
+            }
+        }
+        let size = serde_json::to_vec(&tx).map_or(0, |bytes| bytes.len());
+        if size > self.config.max_bytes {
+            return Err(MempoolError::TooLarge(size));
+        }
+
+        let entry = Entry {
+            tx,
+            sender,
+            size,
+            added_at: now,
+            sequence: self.next_sequence,
+        };
+        let evicted = self.make_room(&entry, replaced.as_ref())?;
+        if let Some(replaced) = &replaced {
+            self.remove(replaced);
+        }
+        self.next_sequence += 1;
+        self.bytes += size;
+        self.by_sender
+            .entry(sender)
+            .or_default()
+            .insert(entry.tx.nonce, hash);
+        self.entries.insert(hash, entry);
+        Ok(Inserted { replaced, evicted })
+    }
+
+    /// Evict lower priority transactions until `incoming` fits in place of `replaced`
+    fn make_room(
+        &mut self,
+        incoming: &Entry,
+        replaced: Option<&Hash>,
+    ) -> Result<Vec<Hash>, MempoolError> {
+        let mut count = self.entries.len();
+        let mut bytes = self.bytes;
+        if let Some(replaced) = replaced {
+            count -= 1;
+            bytes -= self.entries[replaced].size;
+        }
+        let mut victims = Vec::new();
+        // Each sender's last nonce can go without leaving a gap behind it,
+        // unless the incoming transaction would queue behind it
+        let strands_incoming =
+            |entry: &Entry| entry.sender == incoming.sender && entry.tx.nonce < incoming.tx.nonce;
+        let mut candidates: Vec<&Entry> = self
+            .by_sender
+            .values()
+            .filter_map(|queue| queue.values().next_back())
+            .filter(|hash| Some(*hash) != replaced)
+            .map(|hash| &self.entries[hash])
+            .filter(|entry| !strands_incoming(entry))
+            .collect();
+
+        // Pick all victims first so a rejected insert leaves the pool untouched
+        while count >= self.config.max_transactions || bytes + incoming.size > self.config.max_bytes
+        {
+            // Lowest priority first; among equals the newest goes first
+            let index = candidates
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, entry)| (entry.priority(), Reverse(entry.sequence)))
+                .map(|(index, _)| index)
+                .ok_or(MempoolError::Full)?;
+            let victim = candidates.swap_remove(index);
+            if victim.priority() >= incoming.priority() {
+                return Err(MempoolError::Full);
+            }
+            count -= 1;
+            bytes -= victim.size;
+            victims.push(victim.tx.hash());
+
+            let queue = &self.by_sender[&victim.sender];
+            if let Some((_, previous)) = queue.range(..victim.tx.nonce).next_back() {
+                let previous = &self.entries[previous];
+                if !strands_incoming(previous) {
+                    candidates.push(previous);
+                }
+            }
+        }
+
+        for hash in &victims {
+            self.remove(hash);
+        }
+        Ok(victims)
+    }
+
+    /// Remove a transaction from the pool
+    pub fn remove(&mut self, hash: &Hash) -> Option<Transaction> {
+        let entry = self.entries.remove(hash)?;
+        self.bytes -= entry.size;
+        if let Some(queue) = self.by_sender.get_mut(&entry.sender) {
+            queue.remove(&entry.tx.nonce);
+            if queue.is_empty() {
+                self.by_sender.remove(&entry.sender);
+            }
+        }
+        Some(entry.tx)
+    }
+
+    /// Drop transactions that waited longer than the configured TTL
+    ///
+    /// A sender's later nonces go along with an expired one, as they could
+    /// never be included without it.
+    pub fn expire(&mut self, now: Instant) -> Vec<Hash> {
+        let mut first_expired: HashMap<Address, u64> = HashMap::new();
+        for entry in self.entries.values() {
+            if now.saturating_duration_since(entry.added_at) > self.config.ttl {
+                let nonce = first_expired.entry(entry.sender).or_insert(entry.tx.nonce);
+                *nonce = (*nonce).min(entry.tx.nonce);
+            }
+        }
+        let expired: Vec<Hash> = first_expired
+            .iter()
+            .flat_map(|(sender, nonce)| self.by_sender[sender].range(nonce..))
+            .map(|(_, hash)| *hash)
+            .collect();
+        for hash in &expired {
+            self.remove(hash);
+        }
+        expired
+    }
+
+    /// Drop transactions whose nonce was already used on chain
+    pub fn prune_stale(
+        &mut self,
+        mut nonce_of: impl FnMut(&Address) -> Result<u64>,
+    ) -> Result<Vec<Hash>> {
+        let mut stale = Vec::new();
+        for (sender, queue) in &self.by_sender {
+            let account_nonce = nonce_of(sender)?;
+            stale.extend(queue.range(..account_nonce).map(|(_, hash)| *hash));
+        }
+        for hash in &stale {
+            self.remove(hash);
+        }
+        Ok(stale)
+    }
+
+    /// Transactions of one sender in nonce order
+    pub fn sender_transactions(&self, sender: &Address) -> Vec<Transaction> {
+        self.by_sender.get(sender).map_or_else(Vec::new, |queue| {
+            queue
+                .values()
+                .map(|hash| self.entries[hash].tx.clone())
+                .collect()
+        })
+    }
+
+    /// Remove every transaction, returning how many were dropped
+    pub fn clear(&mut self) -> usize {
+        let count = self.len();
+        self.entries.clear();
+        self.by_sender.clear();
+        self.bytes = 0;
+        count
+    }
+
+    /// Count pending and future transactions given each sender's account nonce
+    pub fn status(
+        &self,
+        mut nonce_of: impl FnMut(&Address) -> Result<u64>,
+    ) -> Result<MempoolStatus> {
+        let mut status = MempoolStatus::default();
+        for (sender, queue) in &self.by_sender {
+            let pending = pending_run(queue, nonce_of(sender)?);
+            status.pending += pending;
+            status.queued += queue.len() - pending;
+        }
+        Ok(status)
+    }
+
+    /// Every pooled transaction, split into pending and queued per sender
+    pub fn content(
+        &self,
+        mut nonce_of: impl FnMut(&Address) -> Result<u64>,
+    ) -> Result<MempoolContent> {
+        let mut content = MempoolContent::default();
+        for (sender, queue) in &self.by_sender {
+            let account_nonce = nonce_of(sender)?;
+            let pending_end = account_nonce + pending_run(queue, account_nonce) as u64;
+            for (nonce, hash) in queue {
+                let group = if (account_nonce..pending_end).contains(nonce) {
+                    &mut content.pending
+                } else {
+                    &mut content.queued
+                };
+                group
+                    .entry(*sender)
+                    .or_default()
+                    .push(self.entries[hash].tx.clone());
+            }
+        }
+        Ok(content)
+    }
+
+    /// Look up a pooled transaction by hash
+    pub fn get(
+        &self,
+        hash: &Hash,
+        mut nonce_of: impl FnMut(&Address) -> Result<u64>,
+    ) -> Result<Option<PooledTransaction>> {
+        let Some(entry) = self.entries.get(hash) else {
+            return Ok(None);
+        };
+        let account_nonce = nonce_of(&entry.sender)?;
+        let pending_end =
+            account_nonce + pending_run(&self.by_sender[&entry.sender], account_nonce) as u64;
+        Ok(Some(PooledTransaction {
+            tx: entry.tx.clone(),
+            pending: (account_nonce..pending_end).contains(&entry.tx.nonce),
+        }))
+    }
+
+    /// Pending transactions in the order a block should include them
+    ///
+    /// Senders' transactions stay in nonce order; across senders the highest
+    /// tip at `base_fee` goes first, then the earliest arrival. A sender whose
+    /// next transaction cannot pay `base_fee` contributes nothing further.
+    pub fn executable(
+        &self,
+        mut nonce_of: impl FnMut(&Address) -> Result<u64>,
+        base_fee: u64,
+    ) -> Result<Vec<Transaction>> {
+        let mut heads = BinaryHeap::new();
+        let push = |heads: &mut BinaryHeap<_>, hash: &Hash| {
+            let entry = &self.entries[hash];
+            let tip = entry
+                .tx
+                .effective_gas_price(base_fee)
+                .saturating_sub(base_fee);
+            heads.push((tip, Reverse(entry.sequence), *hash));
+        };
+        for (sender, queue) in &self.by_sender {
+            if let Some(hash) = queue.get(&nonce_of(sender)?) {
+                push(&mut heads, hash);
+            }
+        }
+
+        let mut ordered = Vec::new();
+        while let Some((_, _, hash)) = heads.pop() {
+            let entry = &self.entries[&hash];
+            if entry.tx.max_fee_per_gas < base_fee {
+                continue;
+            }
+            ordered.push(entry.tx.clone());
+            if let Some(next) = self.by_sender[&entry.sender].get(&(entry.tx.nonce + 1)) {
+                push(&mut heads, next);
+            }
+        }
+        Ok(ordered)
+    }
+}
+
+/// `fee` raised by `percent`, rounded up
+fn bumped(fee: u64, percent: u64) -> u64 {
+    let bumped = (fee as u128 * (100 + percent as u128)).div_ceil(100);
+    bumped.min(u64::MAX as u128) as u64
+}
+
+/// Number of consecutive nonces in `queue` starting at `account_nonce`
+fn pending_run(queue: &BTreeMap<u64, Hash>, account_nonce: u64) -> usize {
+    queue
+        .range(account_nonce..)
+        .zip(account_nonce..)
+        .take_while(|((nonce, _), expected)| *nonce == expected)
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use yotquitas_core::{generate_keypair, SigningKey, TransactionPayload};
+
+    fn transfer(key: &SigningKey, nonce: u64, tip: u64) -> Transaction {
+        Transaction::new(
+            key.verifying_key(),
+            TransactionPayload::Transfer {
+                to: [9u8; 32],
+                amount: 1,
+            },
+            25_000,
+            10 + tip,
+            tip,
+            nonce,
+        )
+        .sign(key)
+    }
+
+    fn nonces(txs: &[Transaction]) -> Vec<u64> {
+        txs.iter().map(|tx| tx.nonce).collect()
+    }
+
+    #[test]
+    fn test_nonce_queues() {
+        let (key, _) = generate_keypair();
+        let sender = transfer(&key, 0, 0).sender_address();
+        let mut pool = Mempool::new(MempoolConfig::default());
+        let now = Instant::now();
+
+        pool.insert(transfer(&key, 3, 1), 1, now).unwrap();
+        pool.insert(transfer(&key, 1, 1), 1, now).unwrap();
+        assert_eq!(
+            pool.insert(transfer(&key, 1, 1), 1, now),
+            Err(MempoolError::AlreadyKnown)
+        );
+        assert_eq!(
+            pool.insert(transfer(&key, 1, 2), 1, now),
+            Err(MempoolError::ReplacementUnderpriced {
+                max_fee_per_gas: 13,
+                max_priority_fee_per_gas: 2
+            })
+        );
+        assert_eq!(
+            pool.insert(transfer(&key, 0, 1), 1, now),
+            Err(MempoolError::NonceTooLow {
+                expected: 1,
+                got: 0
+            })
+        );
+
+        // Nonce 3 waits for nonce 2
+        let nonce_of = |_: &Address| Ok(1);
+        assert_eq!(
+            pool.status(nonce_of).unwrap(),
+            MempoolStatus {
+                pending: 1,
+                queued: 1
+            }
+        );
+        assert_eq!(nonces(&pool.executable(nonce_of, 10).unwrap()), vec![1]);
+        let content = pool.content(nonce_of).unwrap();
+        assert_eq!(nonces(&content.pending[&sender]), vec![1]);
+        assert_eq!(nonces(&content.queued[&sender]), vec![3]);
+        let queued = pool.get(&transfer(&key, 3, 1).hash(), nonce_of).unwrap();
+        assert!(!queued.unwrap().pending);
+        assert!(pool.get(&[0; 32], nonce_of).unwrap().is_none());
+
+        pool.insert(transfer(&key, 2, 1), 1, now).unwrap();
+        assert_eq!(
+            nonces(&pool.executable(nonce_of, 10).unwrap()),
+            vec![1, 2, 3]
+        );
+        let queued = pool.get(&transfer(&key, 3, 1).hash(), nonce_of).unwrap();
+        assert!(queued.unwrap().pending);
+
+        // Once nonce 1 is on chain it is pruned
+        let pruned = pool.prune_stale(|address| {
+            assert_eq!(*address, sender);
+            Ok(2)
+        });
+        assert_eq!(pruned.unwrap().len(), 1);
+        assert_eq!(pool.len(), 2);
+    }
+
+    #[test]
+    fn test_executable_orders_senders_by_tip() {
+        let (alice, _) = generate_keypair();
+        let (bob, _) = generate_keypair();
+        let mut pool = Mempool::new(MempoolConfig::default());
+        let now = Instant::now();
+
+        pool.insert(transfer(&alice, 0, 1), 0, now).unwrap();
+        pool.insert(transfer(&alice, 1, 5), 0, now).unwrap();
+        pool.insert(transfer(&bob, 0, 3), 0, now).unwrap();
+
+        let ordered = pool.executable(|_| Ok(0), 10).unwrap();
+        let tips: Vec<u64> = ordered
+            .iter()
+            .map(|tx| tx.max_priority_fee_per_gas)
+            .collect();
+        // Alice's tip of 5 waits behind her own nonce 0
+        assert_eq!(tips, vec![3, 1, 5]);
+
+        // Alice's nonce 0 cannot pay the base fee, which holds back her nonce 1
+        assert_eq!(pool.executable(|_| Ok(0), 12).unwrap().len(), 1);
+        assert!(pool.executable(|_| Ok(0), 100).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_eviction_by_fee_priority() {
+        let (alice, _) = generate_keypair();
+        let (bob, _) = generate_keypair();
+        let mut pool = Mempool::new(MempoolConfig {
+            max_transactions: 2,
+            ..MempoolConfig::default()
+        });
+        let now = Instant::now();
+
+        let cheap = transfer(&alice, 0, 1);
+        pool.insert(cheap.clone(), 0, now).unwrap();
+        pool.insert(transfer(&alice, 1, 4), 0, now).unwrap();
+
+        // Alice's last nonce is the only candidate, and it outbids bob
+        assert_eq!(
+            pool.insert(transfer(&bob, 0, 3), 0, now),
+            Err(MempoolError::Full)
+        );
+        let inserted = pool.insert(transfer(&bob, 0, 5), 0, now).unwrap();
+        assert_eq!(inserted.evicted.len(), 1);
+        assert_eq!(nonces(&pool.executable(|_| Ok(0), 10).unwrap()), vec![0, 0]);
+        assert_eq!(pool.len(), 2);
+
+        // A sender's next nonce never evicts the one it waits for
+        let mut pool = Mempool::new(MempoolConfig {
+            max_transactions: 2,
+            ..MempoolConfig::default()
+        });
+        pool.insert(transfer(&alice, 0, 1), 0, now).unwrap();
+        pool.insert(transfer(&bob, 0, 2), 0, now).unwrap();
+        let inserted = pool.insert(transfer(&alice, 1, 5), 0, now).unwrap();
+        assert_eq!(inserted.evicted, vec![transfer(&bob, 0, 2).hash()]);
+        assert_eq!(nonces(&pool.executable(|_| Ok(0), 10).unwrap()), vec![0, 1]);
+
+        let mut pool = Mempool::new(MempoolConfig {
+            max_bytes: 10,
+            ..MempoolConfig::default()
+        });
+        assert!(matches!(
+            pool.insert(cheap, 0, now),
+            Err(MempoolError::TooLarge(_))
+        ));
+    }
+
+    #[test]
+    fn test_replace_by_fee() {
+        let (key, _) = generate_keypair();
+        let sender = transfer(&key, 0, 0).sender_address();
+        let with_fees = |to, max_fee_per_gas, max_priority_fee_per_gas| {
+            Transaction::new(
+                key.verifying_key(),
+                TransactionPayload::Transfer { to, amount: 0 },
+                25_000,
+                max_fee_per_gas,
+                max_priority_fee_per_gas,
+                0,
+            )
+            .sign(&key)
+        };
+        // Replacing never needs room, even in a full pool
+        let mut pool = Mempool::new(MempoolConfig {
+            max_transactions: 1,
+            ..MempoolConfig::default()
+        });
+        let now = Instant::now();
+
+        let original = with_fees([9u8; 32], 20, 10);
+        pool.insert(original.clone(), 0, now).unwrap();
+        // Both fee caps must rise by 10%
+        assert_eq!(
+            pool.insert(with_fees([9u8; 32], 30, 10), 0, now),
+            Err(MempoolError::ReplacementUnderpriced {
+                max_fee_per_gas: 22,
+                max_priority_fee_per_gas: 11
+            })
+        );
+
+        // A zero-value transfer to the sender cancels the original
+        let cancel = with_fees(sender, 22, 11);
+        let inserted = pool.insert(cancel.clone(), 0, now).unwrap();
+        assert_eq!(inserted.replaced, Some(original.hash()));
+        assert!(inserted.evicted.is_empty());
+        assert_eq!(pool.len(), 1);
+        assert_eq!(pool.sender_transactions(&sender), vec![cancel]);
+        assert_eq!(
+            pool.bytes(),
+            serde_json::to_vec(&pool.sender_transactions(&sender)[0])
+                .unwrap()
+                .len()
+        );
+    }
+
+    #[test]
+    fn test_ttl_expiry_and_clear() {
+        let (key, _) = generate_keypair();
+        let mut pool = Mempool::new(MempoolConfig {
+            ttl: Duration::from_secs(60),
+            ..MempoolConfig::default()
+        });
+        let start = Instant::now();
+
+        pool.insert(transfer(&key, 0, 1), 0, start).unwrap();
+        pool.insert(transfer(&key, 1, 1), 0, start + Duration::from_secs(30))
+            .unwrap();
+        pool.insert(transfer(&key, 3, 1), 0, start + Duration::from_secs(30))
+            .unwrap();
+        let (other, _) = generate_keypair();
+        pool.insert(transfer(&other, 0, 1), 0, start + Duration::from_secs(30))
+            .unwrap();
+        assert!(pool.bytes() > 0);
+
+        // Nonces 1 and 3 have not expired but could never follow nonce 0
+        let expired = pool.expire(start + Duration::from_secs(61));
+        assert_eq!(
+            expired,
+            vec![
+                transfer(&key, 0, 1).hash(),
+                transfer(&key, 1, 1).hash(),
+                transfer(&key, 3, 1).hash()
+            ]
+        );
+        assert_eq!(pool.len(), 1);
+        assert!(pool.expire(start + Duration::from_secs(61)).is_empty());
+
+        assert_eq!(pool.clear(), 1);
+        assert_eq!(pool.len(), 0);
+        assert_eq!(pool.bytes(), 0);
+    }
+}
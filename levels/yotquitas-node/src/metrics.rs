@@ -0,0 +1,248 @@
+//! Node metrics rendered in the Prometheus text exposition format
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Upper bounds, in seconds, of the RPC latency histogram buckets
+pub const LATENCY_BUCKETS: [f64; 9] = [0.0005, 0.001, 0.005, 0.01, 0.05, 0.1, 0.5, 1.0, 5.0];
+
+/// Latency histogram of one RPC method
+#[derive(Debug, Default, Clone)]
+struct Histogram {
+    /// Observations per bucket, not cumulative
+    buckets: [u64; LATENCY_BUCKETS.len()],
+    count: u64,
+    sum: f64,
+}
+
+impl Histogram {
+    fn observe(&mut self, seconds: f64) {
+        if let Some(bucket) = LATENCY_BUCKETS.iter().position(|bound| seconds <= *bound) {
+            self.buckets[bucket] += 1;
+        }
+        self.count += 1;
+        self.sum += seconds;
+    }
+}
+
+/// Counters updated by the node and the RPC server
+#[derive(Debug, Default)]
+pub struct Metrics {
+    blocks_sealed: AtomicU64,
+    transactions_included: AtomicU64,
+    transactions_failed: AtomicU64,
+    transactions_rejected: AtomicU64,
+    gas_used: AtomicU64,
+    rpc_latency: Mutex<BTreeMap<String, Histogram>>,
+}
+
+/// Point-in-time values sampled when metrics are scraped
+#[derive(Debug, Default, Clone)]
+pub struct Gauges {
+    pub block_height: u64,
+    pub block_production_paused: bool,
+    /// Mempool transactions executable in the next block
+    pub mempool_pending: u64,
+    /// Mempool transactions waiting for an earlier nonce
+    pub mempool_queued: u64,
+    /// Encoded size of the transactions in the mempool
+    pub mempool_bytes: u64,
+    /// RocksDB properties by name, e.g. `rocksdb.estimate-num-keys`
+    pub db_stats: Vec<(String, u64)>,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a sealed block and the execution outcome of its transactions
+    pub fn record_block(&self, transactions: u64, failed: u64, gas_used: u64) {
+        self.blocks_sealed.fetch_add(1, Ordering::Relaxed);
+        self.transactions_included
+            .fetch_add(transactions, Ordering::Relaxed);
+        self.transactions_failed
+            .fetch_add(failed, Ordering::Relaxed);
+        self.gas_used.fetch_add(gas_used, Ordering::Relaxed);
+    }
+
+    /// Record a transaction refused before inclusion
+    pub fn record_rejected_transaction(&self) {
+        self.transactions_rejected.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Record how long an RPC method took to serve
+    pub fn record_rpc(&self, method: &str, elapsed: Duration) {
+        self.rpc_latency
+            .lock()
+            .unwrap()
+            .entry(method.to_string())
+            .or_default()
+            .observe(elapsed.as_secs_f64());
+    }
+
+    /// Render all metrics in the Prometheus text format
+    pub fn render(&self, gauges: &Gauges) -> String {
+        let mut out = String::new();
+
+        gauge(
+            &mut out,
+            "yotquitas_block_height",
+            "Height of the chain head",
+            gauges.block_height,
+        );
+        gauge(
+            &mut out,
+            "yotquitas_block_production_paused",
+            "Whether block production is paused by an operator",
+            gauges.block_production_paused as u64,
+        );
+        gauge(
+            &mut out,
+            "yotquitas_mempool_pending",
+            "Mempool transactions executable in the next block",
+            gauges.mempool_pending,
+        );
+        gauge(
+            &mut out,
+            "yotquitas_mempool_queued",
+            "Mempool transactions waiting for an earlier nonce",
+            gauges.mempool_queued,
+        );
+        gauge(
+            &mut out,
+            "yotquitas_mempool_bytes",
+            "Encoded size of the transactions in the mempool",
+            gauges.mempool_bytes,
+        );
+        counter(
+            &mut out,
+            "yotquitas_blocks_sealed_total",
+            "Blocks sealed since start",
+            self.blocks_sealed.load(Ordering::Relaxed),
+        );
+        counter(
+            &mut out,
+            "yotquitas_transactions_included_total",
+            "Transactions included in sealed blocks since start",
+            self.transactions_included.load(Ordering::Relaxed),
+        );
+        counter(
+            &mut out,
+            "yotquitas_transactions_failed_total",
+            "Included transactions whose execution failed",
+            self.transactions_failed.load(Ordering::Relaxed),
+        );
+        counter(
+            &mut out,
+            "yotquitas_transactions_rejected_total",
+            "Transactions refused before inclusion",
+            self.transactions_rejected.load(Ordering::Relaxed),
+        );
+        counter(
+            &mut out,
+            "yotquitas_execution_gas_used_total",
+            "Gas used by included transactions since start",
+            self.gas_used.load(Ordering::Relaxed),
+        );
+
+        let _ = writeln!(out, "# HELP yotquitas_db_property RocksDB property value");
+        let _ = writeln!(out, "# TYPE yotquitas_db_property gauge");
+        for (name, value) in &gauges.db_stats {
+            let _ = writeln!(out, "yotquitas_db_property{{name=\"{}\"}} {}", name, value);
+        }
+
+        let name = "yotquitas_rpc_request_duration_seconds";
+        let _ = writeln!(
+            out,
+            "# HELP {} Time spent serving RPC calls by method",
+            name
+        );
+        let _ = writeln!(out, "# TYPE {} histogram", name);
+        for (method, histogram) in self.rpc_latency.lock().unwrap().iter() {
+            let mut cumulative = 0;
+            for (bound, observations) in LATENCY_BUCKETS.iter().zip(histogram.buckets) {
+                cumulative += observations;
+                let _ = writeln!(
+                    out,
+                    "{}_bucket{{method=\"{}\",le=\"{}\"}} {}",
+                    name, method, bound, cumulative
+                );
+            }
+            let _ = writeln!(
+                out,
+                "{}_bucket{{method=\"{}\",le=\"+Inf\"}} {}",
+                name, method, histogram.count
+            );
+            let _ = writeln!(
+                out,
+                "{}_sum{{method=\"{}\"}} {}",
+                name, method, histogram.sum
+            );
+            let _ = writeln!(
+                out,
+                "{}_count{{method=\"{}\"}} {}",
+                name, method, histogram.count
+            );
+        }
+
+        out
+    }
+}
+
+fn gauge(out: &mut String, name: &str, help: &str, value: u64) {
+    sample(out, name, help, "gauge", value);
+}
+
+fn counter(out: &mut String, name: &str, help: &str, value: u64) {
+    sample(out, name, help, "counter", value);
+}
+
+fn sample(out: &mut String, name: &str, help: &str, kind: &str, value: u64) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+    let _ = writeln!(out, "{} {}", name, value);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let metrics = Metrics::new();
+        metrics.record_block(3, 1, 71000);
+        metrics.record_rejected_transaction();
+        metrics.record_rpc("eth_chainId", Duration::from_micros(200));
+        metrics.record_rpc("eth_chainId", Duration::from_millis(20));
+
+        let text = metrics.render(&Gauges {
+            block_height: 7,
+            block_production_paused: false,
+            mempool_pending: 2,
+            mempool_queued: 1,
+            mempool_bytes: 640,
+            db_stats: vec![("rocksdb.estimate-num-keys".to_string(), 12)],
+        });
+
+        assert!(text.contains("yotquitas_block_height 7\n"));
+        assert!(text.contains("yotquitas_mempool_pending 2\n"));
+        assert!(text.contains("yotquitas_mempool_queued 1\n"));
+        assert!(text.contains("yotquitas_transactions_included_total 3\n"));
+        assert!(text.contains("yotquitas_transactions_failed_total 1\n"));
+        assert!(text.contains("yotquitas_transactions_rejected_total 1\n"));
+        assert!(text.contains("yotquitas_execution_gas_used_total 71000\n"));
+        assert!(text.contains("yotquitas_db_property{name=\"rocksdb.estimate-num-keys\"} 12\n"));
+        assert!(text.contains(
+            "yotquitas_rpc_request_duration_seconds_bucket{method=\"eth_chainId\",le=\"0.0005\"} 1\n"
+        ));
+        assert!(text.contains(
+            "yotquitas_rpc_request_duration_seconds_bucket{method=\"eth_chainId\",le=\"0.05\"} 2\n"
+        ));
+        assert!(text
+            .contains("yotquitas_rpc_request_duration_seconds_count{method=\"eth_chainId\"} 2\n"));
+    }
+}
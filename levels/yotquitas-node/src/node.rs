@@ -1,93 +1,631 @@
-use crate::state::StateDB;
-use crate::vm::VM;
-use crate::genesis::{create_genesis_block, get_initial_balances, GenesisConfig};
-use yotquitas_core::{Block, Transaction, Address, Hash};
+use crate::events::{EventBus, NodeEvent};
+use crate::fee;
+use crate::gas::MAX_GAS_LIMIT;
+use crate::ledger::{Ledger, LedgerError};
+use crate::mempool::{
+    JournalEntry, Mempool, MempoolConfig, MempoolContent, MempoolStatus, PooledTransaction,
+};
+use crate::metrics::{Gauges, Metrics};
+use crate::receipt::Receipt;
+use crate::state::{AccountDiff, StateDB, StateOverlay, Supply};
+use crate::vm::{ExecutionResult, VM};
+use crate::genesis::Genesis;
+use yotquitas_core::{Block, BlockHeader, Transaction, TransactionPayload, Address, Hash};
 use anyhow::Result;
-use std::sync::Arc;
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use tokio::sync::RwLock;
 
+/// Snapshot of an account's state at a given block
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Account {
+    pub address: Address,
+    pub balance: u64,
+    /// Part of the balance locked by a vesting schedule
+    pub locked_balance: u64,
+    pub nonce: u64,
+    pub block_number: u64,
+}
+
+/// Outcome of a dry run of a transaction against the current state
+#[derive(Debug, Clone)]
+pub struct Simulation {
+    /// Head block the transaction was simulated on top of
+    pub block_number: u64,
+    /// Base fee of the block the transaction would be included in
+    pub base_fee_per_gas: u64,
+    /// Execution result, or why the transaction would be rejected
+    pub outcome: std::result::Result<ExecutionResult, String>,
+    /// Accounts the transaction would change
+    pub state_diff: Vec<AccountDiff>,
+}
+
 /// Core node structure
 pub struct Node {
     state: Arc<StateDB>,
     vm: Arc<VM>,
     current_block: Arc<RwLock<Option<Block>>>,
+    events: EventBus,
+    metrics: Metrics,
+    /// While set, no new blocks are produced and transactions are refused
+    paused: AtomicBool,
+    /// Peers registered by the operator; recorded only, as there is no
+    /// peer-to-peer networking yet
+    peers: std::sync::RwLock<BTreeSet<String>>,
     chain_id: u64,
     network_id: u64,
+    /// Receives the priority tips of produced blocks
+    proposer: Address,
+    /// Receives the base fees, which are burned when unset
+    fee_collector: Option<Address>,
+    /// Transactions waiting for inclusion
+    mempool: Mutex<Mempool>,
 }
 
 impl Node {
     /// Create a new node instance
-    pub fn new(
-        state: Arc<StateDB>,
-        genesis_config: &crate::GenesisConfigToml,
-        chain_id: u64,
-        network_id: u64,
-    ) -> Result<Self> {
-        // Initialize genesis block if needed
-        if state.get_latest_block_hash()?.is_none() {
-            // Convert hex treasury address to Address
-            let treasury_address_str = genesis_config.treasury_address.trim_start_matches("0x");
-            let treasury_address_bytes = hex::decode(treasury_address_str)
-                .map_err(|e| anyhow::anyhow!("Invalid treasury address format: {}", e))?;
-
-            if treasury_address_bytes.len() != 32 {
-                anyhow::bail!(
-                    "Treasury address must be 32 bytes, got {} bytes",
-                    treasury_address_bytes.len()
+    ///
+    /// An empty database is initialized from `genesis`; an existing one must
+    /// have been initialized from the same genesis.
+    pub fn new(state: Arc<StateDB>, genesis: &Genesis, network_id: u64) -> Result<Self> {
+        let genesis_block = genesis.block();
+        let genesis_hash = genesis_block.hash();
+        match state.get_block_hash_by_height(0)? {
+            Some(stored) if stored != genesis_hash => anyhow::bail!(
+                "Data directory was initialized with genesis {} but the genesis file describes {}",
+                hex::encode(stored),
+                hex::encode(genesis_hash)
+            ),
+            Some(_) => {}
+            None => {
+                // Store genesis block and its balances
+                let block_data = serde_json::to_vec(&genesis_block)?;
+                state.initialize_genesis(
+                    &genesis_hash,
+                    &block_data,
+                    &genesis.balances(),
+                    &genesis.vesting(),
+                )?;
+
+                tracing::info!("Genesis block created: {}", hex::encode(genesis_hash));
+                tracing::info!(
+                    "Allocated {} AEQ to {} accounts",
+                    genesis.total_supply(),
+                    genesis.alloc.len()
                 );
             }
+        }
 
-            let treasury_address: Address = treasury_address_bytes
-                .try_into()
-                .map_err(|_| anyhow::anyhow!("Failed to convert treasury address to array"))?;
+        let current_block = match state.get_latest_block_hash()? {
+            Some(hash) => match state.get_block(&hash)? {
+                Some(block_data) => Some(serde_json::from_slice::<Block>(&block_data)?),
+                None => None,
+            },
+            None => None,
+        };
 
-            let timestamp = std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap()
-                .as_secs();
+        Ok(Self {
+            state,
+            vm: Arc::new(VM::new()),
+            current_block: Arc::new(RwLock::new(current_block)),
+            events: EventBus::new(),
+            metrics: Metrics::new(),
+            paused: AtomicBool::new(false),
+            peers: std::sync::RwLock::new(BTreeSet::new()),
+            chain_id: genesis.chain_id,
+            network_id,
+            proposer: [0u8; 32],
+            fee_collector: None,
+            mempool: Mutex::new(Mempool::new(MempoolConfig::default())),
+        })
+    }
 
-            let config = GenesisConfig {
-                treasury_address,
-                initial_supply: genesis_config.initial_supply,
-                timestamp,
-            };
+    /// Pay the priority tips of produced blocks to `proposer`
+    pub fn with_proposer(mut self, proposer: Address) -> Self {
+        self.proposer = proposer;
+        self
+    }
 
-            // Get balances before moving config
-            let balances = get_initial_balances(&config);
+    /// Replace the mempool with an empty one with the given limits
+    pub fn with_mempool_config(mut self, config: MempoolConfig) -> Self {
+        self.mempool = Mutex::new(Mempool::new(config));
+        self
+    }
 
-            let genesis = create_genesis_block(config);
-            let genesis_hash = genesis.hash();
+    /// Pay base fees to `collector` instead of burning them
+    pub fn with_fee_collector(mut self, collector: Address) -> Self {
+        self.fee_collector = Some(collector);
+        self
+    }
 
-            // Store genesis block
-            let block_data = serde_json::to_vec(&genesis)?;
-            state.store_block(&genesis_hash, &block_data)?;
-            state.set_latest_block_hash(&genesis_hash)?;
+    /// Add a transaction to the mempool for inclusion by the block producer
+    ///
+    /// Transactions that fail validation, reuse a nonce already on chain or
+    /// whose sender cannot pay the maximum fee are rejected outright. A nonce
+    /// ahead of the account nonce is accepted and waits for the gap to fill,
+    /// and a nonce already pooled replaces that transaction if it pays enough
+    /// more.
+    pub async fn submit_transaction(&self, tx: Transaction) -> Result<Hash> {
+        let tx_hash = tx.hash();
+        if let Err(e) = self.admit_transaction(tx, unix_time()).await {
+            self.metrics.record_rejected_transaction();
+            return Err(e);
+        }
+        self.events.publish(NodeEvent::PendingTransaction(tx_hash));
+        Ok(tx_hash)
+    }
 
-            // Initialize balances
-            state.initialize_genesis(&balances)?;
+    /// Check a transaction against the head state, then pool and journal it
+    ///
+    /// `added_at` is the Unix time at which the transaction first entered the
+    /// pool, which its time to live counts from.
+    async fn admit_transaction(&self, tx: Transaction, added_at: u64) -> Result<()> {
+        if self.is_paused() {
+            anyhow::bail!("Block production is paused");
+        }
+        let current_block = self.current_block.read().await;
+        let base_fee = pending_base_fee(&current_block)?;
+        self.vm.validate_transaction(&tx, base_fee)?;
 
-            tracing::info!("Genesis block created: {}", hex::encode(genesis_hash));
-            tracing::info!("Treasury address: {}", hex::encode(treasury_address));
-            tracing::info!("Initial supply: {} AEQ", genesis_config.initial_supply);
+        let sender_address = tx.sender_address();
+        let max_fee = tx
+            .max_fee()
+            .ok_or_else(|| anyhow::anyhow!("Gas limit times gas price overflows"))?;
+        let balance = self.state.get_balance(&sender_address)?;
+        if balance < max_fee {
+            anyhow::bail!("Insufficient balance for fee");
         }
+        self.check_vesting(&tx, balance, max_fee, next_timestamp(&current_block)?)?;
 
-        Ok(Self {
-            state,
-            vm: Arc::new(VM::new()),
-            current_block: Arc::new(RwLock::new(None)),
-            chain_id,
-            network_id,
+        let account_nonce = self.state.get_nonce(&sender_address)?;
+        let tx_hash = tx.hash();
+        let entry = serde_json::to_vec(&JournalEntry {
+            tx: tx.clone(),
+            added_at,
+        })?;
+        let age = Duration::from_secs(unix_time().saturating_sub(added_at));
+        let added_at = Instant::now().checked_sub(age).unwrap_or_else(Instant::now);
+
+        // The journal is updated under the pool lock so both stay in step
+        let mut mempool = self.mempool.lock().unwrap();
+        let inserted = mempool.insert(tx, account_nonce, added_at)?;
+        self.state.put_mempool_entry(&tx_hash, &entry)?;
+        if let Some(hash) = inserted.replaced {
+            tracing::debug!("Replaced transaction {} in mempool", hex::encode(hash));
+        }
+        for hash in &inserted.evicted {
+            tracing::debug!("Evicted transaction {} from mempool", hex::encode(hash));
+        }
+        let removed: Vec<Hash> = inserted
+            .replaced
+            .into_iter()
+            .chain(inserted.evicted)
+            .collect();
+        self.state.delete_mempool_entries(&removed)?;
+        Ok(())
+    }
+
+    /// Reload the mempool journal after a restart
+    ///
+    /// Every journaled transaction is checked again against the current
+    /// state, so those whose nonce was used or that expired while the node
+    /// was down are dropped. Returns the number of transactions restored.
+    pub async fn restore_mempool(&self) -> Result<usize> {
+        let mut entries = Vec::new();
+        let mut dropped = Vec::new();
+        for (hash, data) in self.state.get_mempool_entries()? {
+            match serde_json::from_slice::<JournalEntry>(&data) {
+                Ok(entry) if entry.tx.hash() == hash => entries.push(entry),
+                _ => dropped.push(hash),
+            }
+        }
+        // Re-admit in arrival order so replacements and evictions repeat
+        entries.sort_by_key(|entry| entry.added_at);
+
+        let mut restored = 0usize;
+        for entry in entries {
+            let tx_hash = entry.tx.hash();
+            match self.admit_transaction(entry.tx, entry.added_at).await {
+                Ok(()) => restored += 1,
+                Err(e) => {
+                    tracing::debug!(
+                        "Dropping journaled transaction {}: {}",
+                        hex::encode(tx_hash),
+                        e
+                    );
+                    dropped.push(tx_hash);
+                }
+            }
+        }
+        let expired = self.mempool.lock().unwrap().expire(Instant::now());
+        restored = restored.saturating_sub(expired.len());
+        dropped.extend(expired);
+        self.state.delete_mempool_entries(&dropped)?;
+
+        tracing::info!(
+            "Restored {} transactions to the mempool, dropped {}",
+            restored,
+            dropped.len()
+        );
+        Ok(restored)
+    }
+
+    /// Seal the executable transactions of the mempool into a new block
+    ///
+    /// Transactions are taken in fee priority order, keeping each sender's
+    /// nonce order, until the block gas limit is reached. Those that no
+    /// longer pass the inclusion checks are dropped from the pool. Once
+    /// the sender can pay the fee a transaction is included even if execution
+    /// fails: the fee is charged, the nonce advances and the receipt records
+    /// the error. No block is produced when nothing is executable or while
+    /// block production is paused.
+    pub async fn produce_block(&self) -> Result<Vec<Receipt>> {
+        // Hold the head for the whole state transition so blocks are sealed in order
+        let mut current_block = self.current_block.write().await;
+        if self.is_paused() {
+            return Ok(Vec::new());
+        }
+        let base_fee = pending_base_fee(&current_block)?;
+        let timestamp = next_timestamp(&current_block)?;
+
+        let candidates = {
+            let mut mempool = self.mempool.lock().unwrap();
+            let expired = mempool.expire(Instant::now());
+            if !expired.is_empty() {
+                tracing::debug!("Expired {} transactions from mempool", expired.len());
+            }
+            let stale = mempool.prune_stale(|address| self.state.get_nonce(address))?;
+            self.state.delete_mempool_entries(&expired)?;
+            self.state.delete_mempool_entries(&stale)?;
+            mempool.executable(|address| self.state.get_nonce(address), base_fee)?
+        };
+
+        let mut state = self.state.overlay();
+        let mut executed = Vec::new();
+        let mut dropped = Vec::new();
+        // Senders whose next transaction did not make it into this block
+        let mut held_back = BTreeSet::new();
+        let mut gas_left = fee::BLOCK_GAS_LIMIT;
+        for tx in candidates {
+            let sender_address = tx.sender_address();
+            if held_back.contains(&sender_address) {
+                continue;
+            }
+            if tx.gas_limit > gas_left {
+                held_back.insert(sender_address);
+                continue;
+            }
+            // Signatures were verified when the transaction was submitted
+            if let Err(e) = self.check_transaction(&state, &tx, base_fee, timestamp, false) {
+                tracing::debug!("Dropping transaction {}: {}", hex::encode(tx.hash()), e);
+                dropped.push(tx.hash());
+                held_back.insert(sender_address);
+                continue;
+            }
+            let result = self.apply_transaction(&mut state, &tx, base_fee)?;
+            gas_left -= tx.gas_limit;
+            executed.push((tx, result));
+        }
+
+        self.remove_from_mempool(&dropped)?;
+        for _ in &dropped {
+            self.metrics.record_rejected_transaction();
+        }
+        if executed.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut touched = Vec::new();
+        let (mut tipped, mut collected) = (false, false);
+        for (tx, result) in &executed {
+            touched.push(tx.sender_address());
+            if let TransactionPayload::Transfer { to, .. } = &tx.payload {
+                touched.push(*to);
+            }
+            let gas_price = tx.effective_gas_price(base_fee);
+            tipped |= result.fee(gas_price.saturating_sub(base_fee)) > 0;
+            collected |= result.fee(gas_price.min(base_fee)) > 0;
+        }
+        // Tips and collected base fees change balances outside the payload
+        if tipped {
+            touched.push(self.proposer);
+        }
+        if let Some(collector) = self.fee_collector.filter(|_| collected) {
+            touched.push(collector);
+        }
+        let mut seen = BTreeSet::new();
+        touched.retain(|address| seen.insert(*address));
+
+        let included: Vec<Hash> = executed.iter().map(|(tx, _)| tx.hash()).collect();
+        let receipts =
+            self.seal_block(&mut current_block, &state, base_fee, timestamp, executed)?;
+        self.remove_from_mempool(&included)?;
+        let block_number = current_block.as_ref().map_or(0, |block| block.index());
+        for address in touched {
+            self.events.publish(NodeEvent::BalanceChanged {
+                address,
+                balance: self.state.get_balance(&address)?,
+                block_number,
+            });
+        }
+        for receipt in &receipts {
+            tracing::info!(
+                "Transaction processed: {}",
+                hex::encode(receipt.transaction_hash)
+            );
+        }
+        Ok(receipts)
+    }
+
+    /// Remove transactions from the mempool and its journal
+    fn remove_from_mempool(&self, hashes: &[Hash]) -> Result<()> {
+        let mut mempool = self.mempool.lock().unwrap();
+        for hash in hashes {
+            mempool.remove(hash);
+        }
+        self.state.delete_mempool_entries(hashes)
+    }
+
+    /// Submit a transaction and immediately seal a block with it
+    ///
+    /// Returns the transaction's receipt. A transaction whose nonce is not
+    /// executable yet stays in the mempool and an error is returned.
+    #[cfg(test)]
+    pub async fn process_transaction(&self, tx: Transaction) -> Result<Receipt> {
+        let tx_hash = self.submit_transaction(tx).await?;
+        self.produce_block()
+            .await?
+            .into_iter()
+            .find(|receipt| receipt.transaction_hash == tx_hash)
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Transaction {} is queued until its nonce is executable",
+                    hex::encode(tx_hash)
+                )
+            })
+    }
+
+    /// Produce a block every `interval` from the executable mempool transactions
+    pub async fn run_block_producer(&self, interval: Duration) {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            ticker.tick().await;
+            if let Err(e) = self.produce_block().await {
+                tracing::error!("Block production failed: {}", e);
+            }
+        }
+    }
+
+    /// Transactions of `sender` waiting in the mempool, in nonce order
+    pub fn pending_transactions(&self, sender: &Address) -> Vec<Transaction> {
+        self.mempool.lock().unwrap().sender_transactions(sender)
+    }
+
+    /// Counts of pending and queued transactions in the mempool
+    pub fn mempool_status(&self) -> Result<MempoolStatus> {
+        let mempool = self.mempool.lock().unwrap();
+        mempool.status(|address| self.state.get_nonce(address))
+    }
+
+    /// Every transaction in the mempool, grouped by sender
+    pub fn mempool_content(&self) -> Result<MempoolContent> {
+        let mempool = self.mempool.lock().unwrap();
+        mempool.content(|address| self.state.get_nonce(address))
+    }
+
+    /// Look up a transaction waiting in the mempool
+    pub fn pooled_transaction(&self, hash: &Hash) -> Result<Option<PooledTransaction>> {
+        let mempool = self.mempool.lock().unwrap();
+        mempool.get(hash, |address| self.state.get_nonce(address))
+    }
+
+    /// Drop every transaction in the mempool, returning how many were dropped
+    pub fn flush_mempool(&self) -> Result<usize> {
+        let mut mempool = self.mempool.lock().unwrap();
+        let journaled: Vec<Hash> = self
+            .state
+            .get_mempool_entries()?
+            .into_iter()
+            .map(|(hash, _)| hash)
+            .collect();
+        self.state.delete_mempool_entries(&journaled)?;
+        let flushed = mempool.clear();
+        tracing::warn!("Flushed {} transactions from mempool", flushed);
+        Ok(flushed)
+    }
+
+    /// Dry-run a transaction against the current state
+    ///
+    /// Runs the checks of `check_transaction` and the state transition of
+    /// `apply_transaction` that block production runs, on a copy-on-write
+    /// overlay that is discarded afterwards. The signature is only verified
+    /// if present, so transactions can be simulated before they are signed.
+    pub async fn simulate_transaction(&self, tx: &Transaction) -> Result<Simulation> {
+        let current_block = self.current_block.read().await;
+        let block_number = current_block
+            .as_ref()
+            .map(|block| block.index())
+            .ok_or_else(|| anyhow::anyhow!("Chain has no head block"))?;
+        let base_fee = pending_base_fee(&current_block)?;
+        let timestamp = next_timestamp(&current_block)?;
+
+        let mut state = self.state.overlay();
+        let verify_signature = tx.signature.is_some();
+        if let Err(e) = self.check_transaction(&state, tx, base_fee, timestamp, verify_signature) {
+            return Ok(Simulation {
+                block_number,
+                base_fee_per_gas: base_fee,
+                outcome: Err(e.to_string()),
+                state_diff: Vec::new(),
+            });
+        }
+
+        let result = self.apply_transaction(&mut state, tx, base_fee)?;
+        Ok(Simulation {
+            block_number,
+            base_fee_per_gas: base_fee,
+            outcome: Ok(result),
+            state_diff: state.diff()?,
         })
     }
 
-    /// Process a transaction
-    pub async fn process_transaction(&self, tx: Transaction) -> Result<()> {
+    /// Estimate the gas `tx` needs by simulating it
+    ///
+    /// The signature is ignored and the gas limit is raised to the most the
+    /// sender can pay for besides the amount transferred, since the gas is
+    /// what the caller is trying to work out. A max fee below the current base
+    /// fee is simulated as the base fee.
+    pub async fn estimate_gas(&self, tx: &Transaction) -> Result<u64> {
+        let mut tx = tx.clone();
+        tx.signature = None;
+        tx.max_fee_per_gas = tx.max_fee_per_gas.max(self.base_fee_per_gas().await?);
+        tx.max_priority_fee_per_gas = tx.max_priority_fee_per_gas.min(tx.max_fee_per_gas);
+        let sender_address = tx.sender_address();
+        // Vesting is evaluated at the time of the block the simulation builds
+        let timestamp = next_timestamp(&*self.current_block.read().await)?;
+        let amount = match &tx.payload {
+            TransactionPayload::Transfer { amount, .. } => *amount,
+            _ => 0,
+        };
+        let balance = self
+            .state
+            .get_balance(&sender_address)?
+            .saturating_sub(self.locked_balance(&sender_address, timestamp)?)
+            .saturating_sub(amount);
+        tx.gas_limit = (balance / tx.max_fee_per_gas).min(MAX_GAS_LIMIT);
+        if tx.gas_limit < self.vm.schedule().intrinsic_gas(&tx) {
+            anyhow::bail!("Insufficient balance for fee");
+        }
+
+        match self.simulate_transaction(&tx).await?.outcome {
+            Ok(result) if result.success => Ok(result.gas_used),
+            Ok(result) => Err(anyhow::anyhow!(
+                "Execution would fail: {}",
+                result.error.unwrap_or_default()
+            )),
+            Err(error) => Err(anyhow::anyhow!(error)),
+        }
+    }
+
+    /// Base fee of the next block
+    pub async fn base_fee_per_gas(&self) -> Result<u64> {
+        pending_base_fee(&*self.current_block.read().await)
+    }
+
+    /// Recommend a priority fee from the tips paid in recent blocks
+    pub async fn suggest_priority_fee(&self) -> Result<u64> {
+        let head = self.block_number().await?;
+        let first = head.saturating_sub(fee::GAS_PRICE_SAMPLE_BLOCKS - 1);
+
+        let mut tips = Vec::new();
+        for number in first..=head {
+            if let Some(block) = self.get_block_by_number(number)? {
+                tips.extend(self.block_tips(&block)?.into_iter().map(|(tip, _)| tip));
+            }
+        }
+        Ok(fee::suggest_priority_fee(tips))
+    }
+
+    /// Recommend a gas price: the next base fee plus the suggested tip
+    pub async fn suggest_gas_price(&self) -> Result<u64> {
+        let base_fee = self.base_fee_per_gas().await?;
+        Ok(base_fee.saturating_add(self.suggest_priority_fee().await?))
+    }
+
+    /// Base fees, gas usage and tip percentiles of up to `block_count` blocks
+    /// ending at `newest_block`
+    pub async fn fee_history(
+        &self,
+        block_count: u64,
+        newest_block: u64,
+        reward_percentiles: &[f64],
+    ) -> Result<fee::FeeHistory> {
+        let head = self.block_number().await?;
+        let newest_block = newest_block.min(head);
+        let block_count = block_count.clamp(1, fee::FEE_HISTORY_MAX_BLOCKS);
+        let oldest_block = (newest_block + 1).saturating_sub(block_count);
+
+        let mut history = fee::FeeHistory {
+            oldest_block,
+            base_fee_per_gas: Vec::new(),
+            gas_used_ratio: Vec::new(),
+            reward: Vec::new(),
+        };
+        let mut next_base_fee = fee::INITIAL_BASE_FEE;
+        for number in oldest_block..=newest_block {
+            let block = self
+                .get_block_by_number(number)?
+                .ok_or_else(|| anyhow::anyhow!("Block {} not found", number))?;
+            let header = &block.header;
+            history.base_fee_per_gas.push(header.base_fee_per_gas);
+            history
+                .gas_used_ratio
+                .push(header.gas_used as f64 / fee::BLOCK_GAS_LIMIT as f64);
+            if !reward_percentiles.is_empty() {
+                history.reward.push(fee::reward_percentiles(
+                    self.block_tips(&block)?,
+                    reward_percentiles,
+                ));
+            }
+            next_base_fee = fee::next_base_fee(header.base_fee_per_gas, header.gas_used);
+        }
+        history.base_fee_per_gas.push(next_base_fee);
+        Ok(history)
+    }
+
+    /// Tip per unit of gas and gas used of each transaction in `block`
+    fn block_tips(&self, block: &Block) -> Result<Vec<(u64, u64)>> {
+        let mut tips = Vec::new();
+        for tx in &block.transactions {
+            if let Some(receipt) = self.get_receipt(&tx.hash())? {
+                let tip = receipt
+                    .effective_gas_price
+                    .saturating_sub(block.header.base_fee_per_gas);
+                tips.push((tip, receipt.gas_used));
+            }
+        }
+        Ok(tips)
+    }
+
+    /// Execute a read-only Move call against the current state
+    ///
+    /// No nonce, fee or signature checks apply and nothing is persisted. A
+    /// zero gas limit runs the call with the maximum gas limit.
+    pub async fn call(&self, tx: &Transaction) -> Result<ExecutionResult> {
+        let _head = self.current_block.read().await;
+        if !matches!(tx.payload, TransactionPayload::MoveCall { .. }) {
+            anyhow::bail!("Only Move calls can be executed read-only");
+        }
+        let mut tx = tx.clone();
+        if tx.gas_limit == 0 || tx.gas_limit > MAX_GAS_LIMIT {
+            tx.gas_limit = MAX_GAS_LIMIT;
+        }
+        self.vm.execute_transaction(&tx)
+    }
+
+    /// Check that `tx` can be included on top of `state` in a block stamped `timestamp`
+    fn check_transaction(
+        &self,
+        state: &StateOverlay,
+        tx: &Transaction,
+        base_fee: u64,
+        timestamp: u64,
+        verify_signature: bool,
+    ) -> Result<()> {
         // Validate transaction
-        self.vm.validate_transaction(&tx)?;
+        if verify_signature {
+            self.vm.validate_transaction(tx, base_fee)?;
+        } else {
+            self.vm.validate_unsigned(tx, base_fee)?;
+        }
 
         // Check nonce
         let sender_address = tx.sender_address();
-        let expected_nonce = self.state.get_nonce(&sender_address)?;
+        let expected_nonce = state.get_nonce(&sender_address)?;
         if tx.nonce != expected_nonce {
             anyhow::bail!(
                 "Invalid nonce: expected {}, got {}",
@@ -96,49 +634,175 @@ impl Node {
             );
         }
 
-        // Check balance for fee
-        let balance = self.state.get_balance(&sender_address)?;
-        if balance < tx.fee {
+        // Check balance for the largest fee the gas limit allows
+        let max_fee = tx
+            .max_fee()
+            .ok_or_else(|| anyhow::anyhow!("Gas limit times gas price overflows"))?;
+        let balance = state.get_balance(&sender_address)?;
+        if balance < max_fee {
             anyhow::bail!("Insufficient balance for fee");
         }
+        self.check_vesting(tx, balance, max_fee, timestamp)?;
+
+        Ok(())
+    }
+
+    /// Refuse a transaction whose maximum fee and transferred amount would
+    /// reach into the part of `balance` still locked at `timestamp`
+    fn check_vesting(
+        &self,
+        tx: &Transaction,
+        balance: u64,
+        max_fee: u64,
+        timestamp: u64,
+    ) -> Result<()> {
+        let locked = self.locked_balance(&tx.sender_address(), timestamp)?;
+        if locked == 0 {
+            return Ok(());
+        }
+        let amount = match &tx.payload {
+            TransactionPayload::Transfer { amount, .. } => *amount,
+            _ => 0,
+        };
+        let unlocked = balance.saturating_sub(locked);
+        if max_fee.saturating_add(amount) > unlocked {
+            anyhow::bail!(
+                "Transaction spends locked balance: {} of {} is locked by vesting",
+                locked,
+                balance
+            );
+        }
+        Ok(())
+    }
+
+    /// Part of the balance of `address` locked by vesting at `timestamp`
+    fn locked_balance(&self, address: &Address, timestamp: u64) -> Result<u64> {
+        Ok(self
+            .state
+            .get_vesting(address)?
+            .map_or(0, |schedule| schedule.locked_at(timestamp)))
+    }
+
+    /// Execute a checked transaction and apply its effects to `state`
+    ///
+    /// The fee for the gas used is always charged and the nonce always
+    /// advances. Only `gas_used * effective_gas_price` is deducted, which
+    /// refunds the unused part of the maximum fee checked before inclusion.
+    /// The base fee part of the fee is burned, or paid to the fee collector
+    /// if one is set, and the tip is paid to the proposer. A transfer that
+    /// runs out of gas or that the sender cannot cover is marked as failed
+    /// and moves no funds.
+    fn apply_transaction(
+        &self,
+        state: &mut StateOverlay,
+        tx: &Transaction,
+        base_fee: u64,
+    ) -> Result<ExecutionResult> {
+        let sender_address = tx.sender_address();
 
         // Execute transaction
-        let result = self.vm.execute_transaction(&tx)?;
-        if !result.success {
-            anyhow::bail!("Transaction execution failed");
+        let mut result = self.vm.execute_transaction(tx)?;
+        let gas_price = tx.effective_gas_price(base_fee);
+        let fee = result.fee(gas_price);
+        let tip = result.fee(gas_price.saturating_sub(base_fee));
+
+        // The maximum fee was checked against the balance before inclusion
+        let mut ledger = Ledger::new(state);
+        ledger.debit(&sender_address, tip)?;
+        match &self.fee_collector {
+            Some(collector) => ledger.transfer(&sender_address, collector, fee - tip)?,
+            None => ledger.burn(&sender_address, fee - tip)?,
         }
 
         // Apply state changes
-        match &tx.payload {
-            yotquitas_core::TransactionPayload::Transfer { to, amount } => {
-                // Deduct from sender
-                let sender_balance = self.state.get_balance(&sender_address)?;
-                if sender_balance < *amount + tx.fee {
-                    anyhow::bail!("Insufficient balance");
+        if let TransactionPayload::Transfer { to, amount } = &tx.payload {
+            if result.success {
+                match ledger.transfer(&sender_address, to, *amount) {
+                    Ok(()) => {}
+                    Err(LedgerError::State(e)) => return Err(e),
+                    Err(e) => {
+                        result.success = false;
+                        result.error = Some(e.to_string());
+                    }
                 }
-                self.state
-                    .set_balance(&sender_address, sender_balance - amount - tx.fee)?;
-
-                // Add to recipient
-                let recipient_balance = self.state.get_balance(to)?;
-                self.state.set_balance(to, recipient_balance + amount)?;
-            }
-            _ => {
-                // For other transaction types, just deduct fee
-                let sender_balance = self.state.get_balance(&sender_address)?;
-                if sender_balance < tx.fee {
-                    anyhow::bail!("Insufficient balance for fee");
-                }
-                self.state
-                    .set_balance(&sender_address, sender_balance - tx.fee)?;
             }
         }
 
+        // Pay the tip to the proposer
+        if tip > 0 {
+            ledger.credit(&self.proposer, tip)?;
+        }
+
         // Increment nonce
-        self.state.increment_nonce(&sender_address)?;
+        state.increment_nonce(&sender_address)?;
 
-        tracing::info!("Transaction processed: {}", hex::encode(tx.hash()));
-        Ok(())
+        Ok(result)
+    }
+
+    /// Seal executed transactions into a new block on top of `head` stamped
+    /// `timestamp`, committing their effects in `state` along with it
+    fn seal_block(
+        &self,
+        head: &mut Option<Block>,
+        state: &StateOverlay,
+        base_fee: u64,
+        timestamp: u64,
+        executed: Vec<(Transaction, ExecutionResult)>,
+    ) -> Result<Vec<Receipt>> {
+        let parent = head
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Chain has no head block"))?;
+
+        let (transactions, results): (Vec<_>, Vec<_>) = executed.into_iter().unzip();
+        let gas_used = results.iter().map(|result| result.gas_used).sum();
+        let header = BlockHeader::new(parent.index() + 1, timestamp, parent.hash()).with_fees(
+            base_fee,
+            gas_used,
+            self.proposer,
+        );
+        let block = Block::new(header, transactions);
+        let block_hash = block.hash();
+
+        let receipts: Vec<Receipt> = block
+            .transactions
+            .iter()
+            .zip(&results)
+            .enumerate()
+            .map(|(index, (tx, result))| {
+                let gas_price = tx.effective_gas_price(base_fee);
+                Receipt::new(tx, result, gas_price, &block, index as u64)
+            })
+            .collect();
+
+        let mut encoded_receipts = Vec::with_capacity(receipts.len());
+        for receipt in &receipts {
+            encoded_receipts.push((receipt.transaction_hash, serde_json::to_vec(receipt)?));
+        }
+
+        let block_data = serde_json::to_vec(&block)?;
+        self.state.commit_block_with_overlay(
+            state,
+            &block_hash,
+            block.index(),
+            &block_data,
+            &encoded_receipts,
+        )?;
+
+        self.metrics.record_block(
+            receipts.len() as u64,
+            receipts.iter().filter(|receipt| !receipt.success).count() as u64,
+            gas_used,
+        );
+
+        tracing::info!(
+            "Block {} sealed: {} ({} transactions)",
+            block.index(),
+            hex::encode(block_hash),
+            block.transactions.len()
+        );
+        self.events.publish(NodeEvent::NewHead(block.clone()));
+        *head = Some(block);
+        Ok(receipts)
     }
 
     /// Get account balance
@@ -146,6 +810,50 @@ impl Node {
         self.state.get_balance(address)
     }
 
+    /// Get account nonce
+    pub fn get_nonce(&self, address: &Address) -> Result<u64> {
+        self.state.get_nonce(address)
+    }
+
+    /// Get balance, locked balance and nonce of an account, read consistently
+    /// with the chain head
+    pub async fn get_account(&self, address: &Address) -> Result<Account> {
+        // Readers are excluded while a transaction is applied and sealed
+        let current_block = self.current_block.read().await;
+        let block_number = current_block
+            .as_ref()
+            .map(|block| block.index())
+            .ok_or_else(|| anyhow::anyhow!("Chain has no head block"))?;
+
+        Ok(Account {
+            address: *address,
+            balance: self.state.get_balance(address)?,
+            locked_balance: self.locked_balance(address, next_timestamp(&current_block)?)?,
+            nonce: self.state.get_nonce(address)?,
+            block_number,
+        })
+    }
+
+    /// Get the tracked supply and the height of the block it was read at
+    pub async fn get_supply(&self) -> Result<(Supply, u64)> {
+        let current_block = self.current_block.read().await;
+        let block_number = current_block
+            .as_ref()
+            .map(|block| block.index())
+            .ok_or_else(|| anyhow::anyhow!("Chain has no head block"))?;
+        Ok((self.state.get_supply()?, block_number))
+    }
+
+    /// Get the height of the latest block
+    pub async fn block_number(&self) -> Result<u64> {
+        self.current_block
+            .read()
+            .await
+            .as_ref()
+            .map(|block| block.index())
+            .ok_or_else(|| anyhow::anyhow!("Chain has no head block"))
+    }
+
     /// Get the latest block
     pub async fn get_latest_block(&self) -> Result<Option<Block>> {
         match self.state.get_latest_block_hash()? {
@@ -160,6 +868,14 @@ impl Node {
         }
     }
 
+    /// Get block by height
+    pub fn get_block_by_number(&self, number: u64) -> Result<Option<Block>> {
+        match self.state.get_block_hash_by_height(number)? {
+            Some(hash) => self.get_block_by_hash(&hash),
+            None => Ok(None),
+        }
+    }
+
     /// Get block by hash
     pub fn get_block_by_hash(&self, hash: &Hash) -> Result<Option<Block>> {
         match self.state.get_block(hash)? {
@@ -171,6 +887,123 @@ impl Node {
         }
     }
 
+    /// Get a committed transaction with the block that includes it and its index
+    pub fn get_transaction(&self, hash: &Hash) -> Result<Option<(Transaction, Block, u64)>> {
+        let Some((height, index)) = self.state.get_transaction_location(hash)? else {
+            return Ok(None);
+        };
+        let block = self
+            .get_block_by_number(height)?
+            .ok_or_else(|| anyhow::anyhow!("Indexed block {} not found", height))?;
+        let tx = block
+            .transactions
+            .get(index as usize)
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("Indexed transaction missing from block {}", height))?;
+        Ok(Some((tx, block, index)))
+    }
+
+    /// Get the receipt of a committed transaction
+    pub fn get_receipt(&self, hash: &Hash) -> Result<Option<Receipt>> {
+        match self.state.get_receipt(hash)? {
+            Some(receipt_data) => Ok(Some(serde_json::from_slice(&receipt_data)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Stop producing blocks; returns false if already paused
+    pub fn pause_block_production(&self) -> bool {
+        let changed = !self.paused.swap(true, Ordering::SeqCst);
+        if changed {
+            tracing::warn!("Block production paused");
+        }
+        changed
+    }
+
+    /// Resume producing blocks; returns false if not paused
+    pub fn resume_block_production(&self) -> bool {
+        let changed = self.paused.swap(false, Ordering::SeqCst);
+        if changed {
+            tracing::info!("Block production resumed");
+        }
+        changed
+    }
+
+    /// Whether block production is paused
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::SeqCst)
+    }
+
+    /// Write a consistent copy of the database under `dir`
+    ///
+    /// Returns the snapshot directory and the height of its head block.
+    pub async fn snapshot(&self, dir: &Path) -> Result<(PathBuf, u64)> {
+        // No block can be committed while the head is read-locked
+        let current_block = self.current_block.read().await;
+        let height = current_block
+            .as_ref()
+            .map(|block| block.index())
+            .ok_or_else(|| anyhow::anyhow!("Chain has no head block"))?;
+
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        std::fs::create_dir_all(dir)?;
+        let path = dir.join(format!("block-{}-{}", height, timestamp));
+        self.state.create_checkpoint(&path)?;
+
+        tracing::info!("Snapshot at block {} written to {:?}", height, path);
+        Ok((path, height))
+    }
+
+    /// Register a static peer; returns false if it was already known
+    pub fn add_peer(&self, peer: &str) -> bool {
+        self.peers.write().unwrap().insert(peer.to_string())
+    }
+
+    /// Forget a static peer; returns false if it was not known
+    pub fn remove_peer(&self, peer: &str) -> bool {
+        self.peers.write().unwrap().remove(peer)
+    }
+
+    /// Registered static peers
+    pub fn peers(&self) -> Vec<String> {
+        self.peers.read().unwrap().iter().cloned().collect()
+    }
+
+    /// Check that the database answers reads
+    pub fn check_database(&self) -> Result<()> {
+        self.state.get_latest_block_hash().map(|_| ())
+    }
+
+    /// Metrics collected by the node
+    pub fn metrics(&self) -> &Metrics {
+        &self.metrics
+    }
+
+    /// Sample the current gauge values for a metrics scrape
+    pub async fn gauges(&self) -> Result<Gauges> {
+        let (mempool, mempool_bytes) = {
+            let mempool = self.mempool.lock().unwrap();
+            let status = mempool.status(|address| self.state.get_nonce(address))?;
+            (status, mempool.bytes() as u64)
+        };
+        Ok(Gauges {
+            block_height: self.block_number().await?,
+            block_production_paused: self.is_paused(),
+            mempool_pending: mempool.pending as u64,
+            mempool_queued: mempool.queued as u64,
+            mempool_bytes,
+            db_stats: self.state.stats()?,
+        })
+    }
+
+    /// Subscribe to events published by the node
+    pub fn subscribe(&self) -> tokio::sync::broadcast::Receiver<NodeEvent> {
+        self.events.subscribe()
+    }
+
     /// Get chain ID
     pub fn chain_id(&self) -> u64 {
         self.chain_id
@@ -181,3 +1014,637 @@ impl Node {
         self.network_id
     }
 }
+
+/// Current Unix time in seconds
+fn unix_time() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+/// Base fee of the block that would follow `head`
+fn pending_base_fee(head: &Option<Block>) -> Result<u64> {
+    let head = head
+        .as_ref()
+        .ok_or_else(|| anyhow::anyhow!("Chain has no head block"))?;
+    Ok(fee::next_base_fee(
+        head.header.base_fee_per_gas,
+        head.header.gas_used,
+    ))
+}
+
+/// Timestamp of the block that would be built on top of `head`
+///
+/// Block timestamps never go backwards, even if the clock does.
+fn next_timestamp(head: &Option<Block>) -> Result<u64> {
+    let head = head
+        .as_ref()
+        .ok_or_else(|| anyhow::anyhow!("Chain has no head block"))?;
+    Ok(unix_time().max(head.header.timestamp))
+}
+
+#[cfg(test)]
+pub(crate) mod test_utils {
+    use super::*;
+    use tempfile::TempDir;
+    use yotquitas_core::{generate_keypair, SigningKey};
+
+    /// Start a node in `temp_dir` whose treasury is controlled by the returned key
+    pub fn test_node(temp_dir: &TempDir) -> Result<(Node, SigningKey)> {
+        let (signing_key, pubkey) = generate_keypair();
+        let treasury = yotquitas_core::sha256(&pubkey.to_bytes());
+        let state = Arc::new(StateDB::open(temp_dir.path())?);
+        Ok((
+            Node::new(state, &test_genesis(treasury), 1337)?,
+            signing_key,
+        ))
+    }
+
+    /// Genesis of chain 1337 funding `treasury` with 1,000,000
+    pub fn test_genesis(treasury: Address) -> Genesis {
+        Genesis {
+            chain_id: 1337,
+            timestamp: 0,
+            consensus: Default::default(),
+            alloc: vec![crate::genesis::Allocation {
+                address: treasury,
+                balance: 1_000_000,
+                vesting: None,
+            }],
+            validators: vec![],
+        }
+    }
+
+    /// Transfer of `amount` to `to` with a 25,000 gas limit, a max fee of
+    /// the initial base fee and no tip
+    pub fn unsigned_transfer(
+        signing_key: &SigningKey,
+        to: Address,
+        amount: u64,
+        nonce: u64,
+    ) -> Transaction {
+        Transaction::new(
+            signing_key.verifying_key(),
+            TransactionPayload::Transfer { to, amount },
+            25_000,
+            fee::INITIAL_BASE_FEE,
+            0,
+            nonce,
+        )
+    }
+
+    /// [`unsigned_transfer`] signed by `signing_key`
+    pub fn signed_transfer(
+        signing_key: &SigningKey,
+        to: Address,
+        amount: u64,
+        nonce: u64,
+    ) -> Transaction {
+        unsigned_transfer(signing_key, to, amount, nonce).sign(signing_key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::test_utils::{signed_transfer, test_genesis, test_node, unsigned_transfer};
+    use super::*;
+    use tempfile::TempDir;
+    use yotquitas_core::TransactionPayload;
+
+    #[tokio::test]
+    async fn test_transaction_receipt_and_index() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let (node, signing_key) = test_node(&temp_dir)?;
+
+        let tx = signed_transfer(&signing_key, [9u8; 32], 100, 0);
+        let tx_hash = tx.hash();
+
+        let receipt = node.process_transaction(tx.clone()).await?;
+        assert!(receipt.success);
+        assert_eq!(receipt.block_number, 1);
+        assert_eq!(receipt.transaction_index, 0);
+        assert_eq!(receipt.fee_paid, receipt.gas_used);
+        assert!(receipt.gas_used < 25_000);
+
+        let (stored_tx, block, index) = node.get_transaction(&tx_hash)?.unwrap();
+        assert_eq!(stored_tx, tx);
+        assert_eq!(block.index(), 1);
+        assert_eq!(index, 0);
+        assert_eq!(node.get_receipt(&tx_hash)?, Some(receipt));
+        assert_eq!(
+            node.get_block_by_number(1)?.map(|b| b.hash()),
+            Some(block.hash())
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_failed_transfer_is_included() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let (node, signing_key) = test_node(&temp_dir)?;
+
+        let tx = signed_transfer(&signing_key, [9u8; 32], 10_000_000, 0);
+
+        let receipt = node.process_transaction(tx).await?;
+        assert!(!receipt.success);
+        assert_eq!(receipt.error.as_deref(), Some("Insufficient balance"));
+        assert_eq!(node.get_balance(&[9u8; 32])?, 0);
+        assert_eq!(
+            node.get_balance(&receipt.from)?,
+            1_000_000 - receipt.fee_paid
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_base_fee_is_burned_and_tip_paid_to_proposer() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let (node, signing_key) = test_node(&temp_dir)?;
+        let node = node.with_proposer([7u8; 32]);
+        let base_fee = node.base_fee_per_gas().await?;
+        assert_eq!(base_fee, fee::INITIAL_BASE_FEE);
+
+        // A max fee below the base fee is rejected
+        let transfer = |max_fee_per_gas, max_priority_fee_per_gas| {
+            Transaction {
+                max_fee_per_gas,
+                max_priority_fee_per_gas,
+                ..unsigned_transfer(&signing_key, [9u8; 32], 100, 0)
+            }
+            .sign(&signing_key)
+        };
+        assert!(node
+            .process_transaction(transfer(base_fee - 1, 0))
+            .await
+            .is_err());
+
+        // The tip is capped so base fee plus tip stays within the max fee
+        let receipt = node.process_transaction(transfer(base_fee + 2, 3)).await?;
+        assert_eq!(receipt.effective_gas_price, base_fee + 2);
+        assert_eq!(receipt.fee_paid, receipt.gas_used * (base_fee + 2));
+        assert_eq!(node.get_balance(&[7u8; 32])?, receipt.gas_used * 2);
+        assert_eq!(
+            node.get_balance(&receipt.from)?,
+            1_000_000 - 100 - receipt.fee_paid
+        );
+
+        let block = node.get_block_by_number(1)?.unwrap();
+        assert_eq!(block.header.base_fee_per_gas, base_fee);
+        assert_eq!(block.header.gas_used, receipt.gas_used);
+        assert_eq!(block.header.proposer, [7u8; 32]);
+        let (supply, _) = node.get_supply().await?;
+        assert_eq!(supply.burned, receipt.gas_used * base_fee);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_fee_collector_receives_base_fee() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let (node, signing_key) = test_node(&temp_dir)?;
+        let node = node.with_proposer([7u8; 32]).with_fee_collector([8u8; 32]);
+        let mut events = node.subscribe();
+
+        let tx = Transaction {
+            max_fee_per_gas: fee::INITIAL_BASE_FEE + 1,
+            max_priority_fee_per_gas: 1,
+            ..unsigned_transfer(&signing_key, [9u8; 32], 100, 0)
+        }
+        .sign(&signing_key);
+        let receipt = node.process_transaction(tx).await?;
+
+        assert_eq!(node.get_balance(&[8u8; 32])?, receipt.gas_used);
+        assert_eq!(node.get_balance(&[7u8; 32])?, receipt.gas_used);
+        // Both credits are announced after the sender and the recipient
+        let mut changed = Vec::new();
+        while let Ok(event) = events.try_recv() {
+            if let NodeEvent::BalanceChanged {
+                address, balance, ..
+            } = event
+            {
+                changed.push((address, balance));
+            }
+        }
+        assert_eq!(
+            changed[2..],
+            [([7u8; 32], receipt.gas_used), ([8u8; 32], receipt.gas_used)]
+        );
+        // Nothing is burned, so the supply is unchanged
+        let (supply, block_number) = node.get_supply().await?;
+        assert_eq!(
+            supply,
+            Supply {
+                minted: 1_000_000,
+                burned: 0
+            }
+        );
+        assert_eq!(block_number, 1);
+        assert!(node.state.audit_supply()?.is_consistent());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_mempool_queues_future_nonces() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let (node, signing_key) = test_node(&temp_dir)?;
+        let transfer = |nonce| signed_transfer(&signing_key, [9u8; 32], 100, nonce);
+
+        // Nonce 1 waits for nonce 0, so no block is produced
+        node.submit_transaction(transfer(1)).await?;
+        assert!(node.produce_block().await?.is_empty());
+        assert_eq!(node.block_number().await?, 0);
+
+        node.submit_transaction(transfer(0)).await?;
+        let receipts = node.produce_block().await?;
+        assert_eq!(receipts.len(), 2);
+        assert_eq!(receipts[0].transaction_hash, transfer(0).hash());
+        assert_eq!(receipts[1].transaction_index, 1);
+        assert_eq!(node.get_block_by_number(1)?.unwrap().transactions.len(), 2);
+        assert_eq!(node.get_balance(&[9u8; 32])?, 200);
+
+        // Used nonces are rejected, and a flush drops what is still queued
+        assert!(node.submit_transaction(transfer(1)).await.is_err());
+        node.submit_transaction(transfer(5)).await?;
+        assert_eq!(node.gauges().await?.mempool_queued, 1);
+        assert_eq!(node.flush_mempool()?, 1);
+        assert!(node.produce_block().await?.is_empty());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_mempool_survives_restart() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let (node, signing_key) = test_node(&temp_dir)?;
+        let transfer = |nonce| signed_transfer(&signing_key, [9u8; 32], 100, nonce);
+
+        node.process_transaction(transfer(0)).await?;
+        node.submit_transaction(transfer(1)).await?;
+        node.submit_transaction(transfer(3)).await?;
+        // One entry left behind as if the node stopped between sealing a
+        // block and updating the journal, and one older than the time to live
+        for (tx, added_at) in [(transfer(0), unix_time()), (transfer(4), 0)] {
+            let entry = serde_json::to_vec(&JournalEntry {
+                tx: tx.clone(),
+                added_at,
+            })?;
+            node.state.put_mempool_entry(&tx.hash(), &entry)?;
+        }
+        drop(node);
+
+        let sender = transfer(0).sender_address();
+        let state = Arc::new(StateDB::open(temp_dir.path())?);
+        let node = Node::new(state, &test_genesis(sender), 1337)?;
+        assert_eq!(node.restore_mempool().await?, 2);
+        assert_eq!(node.state.get_mempool_entries()?.len(), 2);
+
+        assert_eq!(
+            node.pending_transactions(&sender),
+            vec![transfer(1), transfer(3)]
+        );
+        let receipts = node.produce_block().await?;
+        assert_eq!(receipts.len(), 1);
+        assert_eq!(receipts[0].transaction_hash, transfer(1).hash());
+        assert_eq!(node.state.get_mempool_entries()?.len(), 1);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_vesting_locks_balance() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let (signing_key, pubkey) = yotquitas_core::generate_keypair();
+        let treasury = yotquitas_core::sha256(&pubkey.to_bytes());
+        let mut genesis = test_genesis(treasury);
+        // Nothing unlocks for the lifetime of the test
+        genesis.alloc[0].vesting = Some(crate::vesting::VestingSchedule {
+            amount: 900_000,
+            start: unix_time(),
+            cliff_secs: 1_000_000,
+            duration_secs: 2_000_000,
+        });
+        let state = Arc::new(StateDB::open(temp_dir.path())?);
+        let node = Node::new(state, &genesis, 1337)?;
+        let transfer = |amount, nonce| signed_transfer(&signing_key, [9u8; 32], amount, nonce);
+
+        let account = node.get_account(&treasury).await?;
+        assert_eq!(
+            (account.balance, account.locked_balance),
+            (1_000_000, 900_000)
+        );
+        // Gas is estimated from what is unlocked besides the amount
+        let estimate = node
+            .estimate_gas(&unsigned_transfer(&signing_key, [9u8; 32], 1, 0))
+            .await?;
+        assert!(estimate > 0);
+
+        // 100,000 is unlocked, less the maximum fee of 25,000
+        let err = node
+            .submit_transaction(transfer(75_001, 0))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("locked"));
+        node.process_transaction(transfer(75_000, 0)).await?;
+        assert_eq!(node.get_balance(&[9u8; 32])?, 75_000);
+
+        // What is left unlocked no longer covers the maximum fee
+        let account = node.get_account(&treasury).await?;
+        assert_eq!(account.locked_balance, 900_000);
+        assert!(account.balance - account.locked_balance < 25_000);
+        assert!(node.submit_transaction(transfer(0, 1)).await.is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_genesis_mismatch_is_refused() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let genesis = test_genesis([1u8; 32]);
+        let state = Arc::new(StateDB::open(temp_dir.path())?);
+        let node = Node::new(state, &genesis, 1337)?;
+        assert_eq!(node.get_balance(&[1u8; 32])?, 1_000_000);
+        drop(node);
+
+        // Restarting from the same genesis is fine, a different one is refused
+        let state = Arc::new(StateDB::open(temp_dir.path())?);
+        drop(Node::new(state, &genesis, 1337)?);
+        let mut other = genesis.clone();
+        other.alloc[0].balance += 1;
+        let state = Arc::new(StateDB::open(temp_dir.path())?);
+        let err = Node::new(state, &other, 1337).err().unwrap();
+        assert!(err.to_string().contains("genesis"));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_self_transfer_only_pays_fee() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let (node, signing_key) = test_node(&temp_dir)?;
+        let sender = yotquitas_core::sha256(&signing_key.verifying_key().to_bytes());
+
+        let tx = signed_transfer(&signing_key, sender, 500, 0);
+        let receipt = node.process_transaction(tx).await?;
+        assert!(receipt.success);
+        assert_eq!(node.get_balance(&sender)?, 1_000_000 - receipt.fee_paid);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_out_of_gas_charges_whole_limit() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let (node, signing_key) = test_node(&temp_dir)?;
+        let transfer = |gas_limit| Transaction {
+            gas_limit,
+            max_fee_per_gas: 2,
+            max_priority_fee_per_gas: 1,
+            ..unsigned_transfer(&signing_key, [9u8; 32], 100, 0)
+        };
+        let intrinsic_gas = node.vm.schedule().intrinsic_gas(&transfer(0));
+
+        // Below the intrinsic gas the transaction is rejected outright
+        let rejected = transfer(intrinsic_gas - 1).sign(&signing_key);
+        assert!(node.process_transaction(rejected).await.is_err());
+
+        let tx = transfer(intrinsic_gas + 1).sign(&signing_key);
+        let receipt = node.process_transaction(tx).await?;
+        assert!(!receipt.success);
+        assert_eq!(receipt.error.as_deref(), Some("Out of gas"));
+        assert_eq!(receipt.gas_used, intrinsic_gas + 1);
+        assert_eq!(receipt.fee_paid, 2 * (intrinsic_gas + 1));
+        assert_eq!(node.get_balance(&[9u8; 32])?, 0);
+        assert_eq!(
+            node.get_balance(&receipt.from)?,
+            1_000_000 - receipt.fee_paid
+        );
+        assert_eq!(node.get_nonce(&receipt.from)?, 1);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_events_published() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let (node, signing_key) = test_node(&temp_dir)?;
+        let mut events = node.subscribe();
+
+        let tx = signed_transfer(&signing_key, [9u8; 32], 100, 0);
+        let receipt = node.process_transaction(tx.clone()).await?;
+
+        assert!(
+            matches!(events.recv().await?, NodeEvent::PendingTransaction(hash) if hash == tx.hash())
+        );
+        assert!(matches!(events.recv().await?, NodeEvent::NewHead(block) if block.index() == 1));
+        assert!(matches!(
+            events.recv().await?,
+            NodeEvent::BalanceChanged { address, balance, block_number: 1 }
+                if address == receipt.from && balance == 1_000_000 - 100 - receipt.fee_paid
+        ));
+        assert!(matches!(
+            events.recv().await?,
+            NodeEvent::BalanceChanged { address, balance: 100, block_number: 1 }
+                if address == [9u8; 32]
+        ));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_pause_and_snapshot() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let (node, signing_key) = test_node(&temp_dir)?;
+        let transfer = |nonce| signed_transfer(&signing_key, [9u8; 32], 100, nonce);
+
+        assert!(node.pause_block_production());
+        assert!(!node.pause_block_production());
+        assert!(node.process_transaction(transfer(0)).await.is_err());
+        assert_eq!(node.block_number().await?, 0);
+
+        assert!(node.resume_block_production());
+        node.process_transaction(transfer(0)).await?;
+
+        let snapshots = TempDir::new()?;
+        let (path, height) = node.snapshot(snapshots.path()).await?;
+        assert_eq!(height, 1);
+        let copy = StateDB::open(&path)?;
+        assert_eq!(copy.get_balance(&[9u8; 32])?, 100);
+        assert_eq!(
+            copy.get_block_hash_by_height(1)?,
+            node.get_block_by_number(1)?.map(|b| b.hash())
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_simulation_is_not_persisted() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let (node, signing_key) = test_node(&temp_dir)?;
+        let sender = yotquitas_core::sha256(&signing_key.verifying_key().to_bytes());
+        let tx = unsigned_transfer(&signing_key, [9u8; 32], 2_000_000, 0);
+
+        // The transfer exceeds the balance, so it would fail but still pay the fee
+        let simulation = node.simulate_transaction(&tx).await?;
+        let result = simulation.outcome.unwrap();
+        assert!(!result.success);
+        assert_eq!(simulation.state_diff.len(), 1);
+        assert_eq!(
+            simulation.state_diff[0].balance_after,
+            1_000_000 - result.gas_used
+        );
+
+        // A bad signature is still caught when one is given
+        let mut forged = tx.sign(&signing_key);
+        forged.max_fee_per_gas = 2;
+        assert!(node.simulate_transaction(&forged).await?.outcome.is_err());
+
+        assert_eq!(node.get_balance(&sender)?, 1_000_000);
+        assert_eq!(node.get_nonce(&sender)?, 0);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_gas_estimation() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let (node, signing_key) = test_node(&temp_dir)?;
+        // The base fee stays at its initial value, so the max fee is base plus tip
+        let transfer = |gas_limit, tip, nonce| Transaction {
+            gas_limit,
+            max_fee_per_gas: fee::INITIAL_BASE_FEE + tip,
+            max_priority_fee_per_gas: tip,
+            ..unsigned_transfer(&signing_key, [9u8; 32], 100, nonce)
+        };
+
+        let estimate = node.estimate_gas(&transfer(0, 0, 0)).await?;
+        assert!(node.estimate_gas(&transfer(0, 0, 7)).await.is_err());
+        let mut too_large = transfer(0, 0, 0);
+        too_large.payload = TransactionPayload::Transfer {
+            to: [9u8; 32],
+            amount: 2_000_000,
+        };
+        assert!(node.estimate_gas(&too_large).await.is_err());
+
+        // The estimate is exactly enough and the unused limit is refunded
+        assert_eq!(node.suggest_priority_fee().await?, fee::MIN_PRIORITY_FEE);
+        let receipt = node
+            .process_transaction(transfer(estimate, 2, 0).sign(&signing_key))
+            .await?;
+        assert!(receipt.success);
+        assert_eq!(receipt.gas_used, estimate);
+        let receipt = node
+            .process_transaction(transfer(estimate * 2, 1, 1).sign(&signing_key))
+            .await?;
+        assert_eq!(receipt.fee_paid, estimate * 2);
+        assert_eq!(node.suggest_priority_fee().await?, 1);
+        assert_eq!(node.suggest_gas_price().await?, 2);
+
+        let history = node.fee_history(10, u64::MAX, &[50.0]).await?;
+        assert_eq!(history.oldest_block, 0);
+        assert_eq!(history.base_fee_per_gas, vec![fee::INITIAL_BASE_FEE; 4]);
+        assert_eq!(history.reward, vec![vec![0], vec![2], vec![1]]);
+
+        Ok(())
+    }
+
+    /// A transfer between a random sender and recipient
+    #[derive(Debug, Clone)]
+    struct RandomTransfer {
+        sender: usize,
+        recipient: usize,
+        amount: u64,
+        gas_limit: u64,
+        tip: u64,
+        stale_nonce: bool,
+    }
+
+    fn random_transfer() -> impl proptest::strategy::Strategy<Value = RandomTransfer> {
+        use proptest::prelude::*;
+        (
+            0..3usize,
+            0..5usize,
+            prop_oneof![0..400_000u64, any::<u64>()],
+            prop_oneof![0..30_000u64, Just(MAX_GAS_LIMIT)],
+            0..4u64,
+            proptest::bool::weighted(0.1),
+        )
+            .prop_map(|(sender, recipient, amount, gas_limit, tip, stale_nonce)| {
+                RandomTransfer {
+                    sender,
+                    recipient,
+                    amount,
+                    gas_limit,
+                    tip,
+                    stale_nonce,
+                }
+            })
+    }
+
+    proptest::proptest! {
+        #![proptest_config(proptest::prelude::ProptestConfig::with_cases(32))]
+
+        /// Balances plus burned base fees always add up to the initial supply,
+        /// and the tracked supply agrees
+        #[test]
+        fn prop_transactions_conserve_supply(
+            transfers in proptest::collection::vec(random_transfer(), 1..16),
+        ) {
+            let proposer = [7u8; 32];
+            let temp_dir = TempDir::new().unwrap();
+            let (node, treasury_key) = test_node(&temp_dir).unwrap();
+            let node = node.with_proposer(proposer);
+            let keys = [
+                treasury_key,
+                yotquitas_core::generate_keypair().0,
+                yotquitas_core::generate_keypair().0,
+            ];
+            let mut addresses: Vec<Address> = keys
+                .iter()
+                .map(|key| yotquitas_core::sha256(&key.verifying_key().to_bytes()))
+                .collect();
+            addresses.extend([[9u8; 32], proposer]);
+
+            let runtime = tokio::runtime::Runtime::new().unwrap();
+            let mut burned = 0u128;
+            let mut counted = 0;
+            for transfer in transfers {
+                let sender = &keys[transfer.sender];
+                let nonce = node.get_nonce(&addresses[transfer.sender]).unwrap();
+                let tx = Transaction {
+                    gas_limit: transfer.gas_limit,
+                    max_fee_per_gas: fee::INITIAL_BASE_FEE + transfer.tip,
+                    max_priority_fee_per_gas: transfer.tip,
+                    ..unsigned_transfer(
+                        sender,
+                        addresses[transfer.recipient],
+                        transfer.amount,
+                        nonce + transfer.stale_nonce as u64,
+                    )
+                }
+                .sign(sender);
+
+                // Transactions queued behind a nonce gap may land in later blocks
+                let _ = runtime.block_on(node.process_transaction(tx));
+                let head = runtime.block_on(node.block_number()).unwrap();
+                for number in counted + 1..=head {
+                    let block = node.get_block_by_number(number).unwrap().unwrap();
+                    burned += block.header.gas_used as u128 * block.header.base_fee_per_gas as u128;
+                }
+                counted = head;
+
+                let balances: u128 = addresses
+                    .iter()
+                    .map(|address| node.get_balance(address).unwrap() as u128)
+                    .sum();
+                proptest::prop_assert_eq!(balances + burned, 1_000_000);
+                let supply = node.state.get_supply().unwrap();
+                proptest::prop_assert_eq!(supply.burned as u128, burned);
+                proptest::prop_assert!(node.state.audit_supply().unwrap().is_consistent());
+            }
+        }
+    }
+}
@@ -0,0 +1,45 @@
+use crate::vm::ExecutionResult;
+use serde::{Deserialize, Serialize};
+use yotquitas_core::{Address, Block, Hash, Transaction};
+
+/// Outcome of a transaction that has been included in a block
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Receipt {
+    pub transaction_hash: Hash,
+    pub block_hash: Hash,
+    pub block_number: u64,
+    pub transaction_index: u64,
+    pub from: Address,
+    pub success: bool,
+    pub gas_used: u64,
+    /// Price paid per unit of gas, base fee plus priority tip
+    pub effective_gas_price: u64,
+    pub fee_paid: u64,
+    pub return_data: Vec<u8>,
+    pub error: Option<String>,
+}
+
+impl Receipt {
+    /// Build the receipt for the transaction at `index` in `block`
+    pub fn new(
+        tx: &Transaction,
+        result: &ExecutionResult,
+        effective_gas_price: u64,
+        block: &Block,
+        index: u64,
+    ) -> Self {
+        Self {
+            transaction_hash: tx.hash(),
+            block_hash: block.hash(),
+            block_number: block.index(),
+            transaction_index: index,
+            from: tx.sender_address(),
+            success: result.success,
+            gas_used: result.gas_used,
+            effective_gas_price,
+            fee_paid: result.fee(effective_gas_price),
+            return_data: result.return_data.clone(),
+            error: result.error.clone(),
+        }
+    }
+}
@@ -1,9 +1,65 @@
-use rocksdb::{DB, Options};
+use crate::vesting::VestingSchedule;
+use rocksdb::{checkpoint::Checkpoint, WriteBatch, DB, Options};
 use yotquitas_core::Address;
 use std::path::Path;
 use anyhow::Result;
+use std::collections::BTreeMap;
 use std::sync::Arc;
 
+/// RocksDB properties reported by [`StateDB::stats`]
+const DB_STAT_PROPERTIES: [&str; 4] = [
+    "rocksdb.estimate-num-keys",
+    "rocksdb.total-sst-files-size",
+    "rocksdb.cur-size-all-mem-tables",
+    "rocksdb.estimate-pending-compaction-bytes",
+];
+
+/// Key prefix of account balances
+const BALANCE_PREFIX: &str = "balance:";
+
+/// Key prefix of the mempool journal
+const MEMPOOL_PREFIX: &str = "mempool:";
+
+/// Key prefix of vesting schedules of locked genesis allocations
+const VESTING_PREFIX: &str = "vesting:";
+
+/// Total amount minted since genesis
+const SUPPLY_MINTED_KEY: &[u8] = b"supply:minted";
+
+/// Total amount of fees burned since genesis
+const SUPPLY_BURNED_KEY: &[u8] = b"supply:burned";
+
+/// Amounts minted and burned over the life of the chain
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Supply {
+    pub minted: u64,
+    pub burned: u64,
+}
+
+impl Supply {
+    /// Amount held by all accounts together, `minted - burned`
+    pub fn total(&self) -> u64 {
+        self.minted.saturating_sub(self.burned)
+    }
+}
+
+/// Result of comparing the tracked supply against the sum of all balances
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SupplyAudit {
+    pub supply: Supply,
+    /// Sum of all account balances
+    pub balances: u128,
+    /// Number of accounts with a stored balance
+    pub accounts: u64,
+}
+
+impl SupplyAudit {
+    /// Whether the balances add up to minted minus burned
+    pub fn is_consistent(&self) -> bool {
+        self.supply.burned <= self.supply.minted && self.balances == self.supply.total() as u128
+    }
+}
+
 /// State database wrapper around RocksDB
 pub struct StateDB {
     db: Arc<DB>,
@@ -45,6 +101,92 @@ impl StateDB {
         Ok(())
     }
 
+    /// Read a little-endian counter, zero if unset
+    fn get_counter(&self, key: &[u8]) -> Result<u64> {
+        match self.db.get(key)? {
+            Some(bytes) => Ok(u64::from_le_bytes(
+                bytes
+                    .try_into()
+                    .map_err(|_| anyhow::anyhow!("Invalid counter bytes"))?,
+            )),
+            None => Ok(0),
+        }
+    }
+
+    /// Get the amounts minted and burned so far
+    pub fn get_supply(&self) -> Result<Supply> {
+        Ok(Supply {
+            minted: self.get_counter(SUPPLY_MINTED_KEY)?,
+            burned: self.get_counter(SUPPLY_BURNED_KEY)?,
+        })
+    }
+
+    /// Sum every stored balance and compare it against the tracked supply
+    pub fn audit_supply(&self) -> Result<SupplyAudit> {
+        let mut balances = 0u128;
+        let mut accounts = 0u64;
+        for (_, value) in self.scan_prefix(BALANCE_PREFIX)? {
+            let balance = u64::from_le_bytes(
+                value
+                    .try_into()
+                    .map_err(|_| anyhow::anyhow!("Invalid balance bytes"))?,
+            );
+            balances += balance as u128;
+            accounts += 1;
+        }
+        Ok(SupplyAudit {
+            supply: self.get_supply()?,
+            balances,
+            accounts,
+        })
+    }
+
+    /// Read every key-value pair whose key starts with `prefix`
+    fn scan_prefix(&self, prefix: &str) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        let mut pairs = Vec::new();
+        // Keys are sorted, so the prefix ends at the first key past it
+        for entry in self.db.prefix_iterator(prefix.as_bytes()) {
+            let (key, value) = entry?;
+            if !key.starts_with(prefix.as_bytes()) {
+                break;
+            }
+            pairs.push((key.into_vec(), value.into_vec()));
+        }
+        Ok(pairs)
+    }
+
+    /// Journal a pooled transaction so it survives a restart
+    pub fn put_mempool_entry(&self, tx_hash: &[u8; 32], entry: &[u8]) -> Result<()> {
+        let key = format!("{}{}", MEMPOOL_PREFIX, hex::encode(tx_hash));
+        self.db.put(key.as_bytes(), entry)?;
+        Ok(())
+    }
+
+    /// Remove transactions from the mempool journal
+    pub fn delete_mempool_entries(&self, tx_hashes: &[[u8; 32]]) -> Result<()> {
+        if tx_hashes.is_empty() {
+            return Ok(());
+        }
+        let mut batch = WriteBatch::default();
+        for tx_hash in tx_hashes {
+            batch.delete(format!("{}{}", MEMPOOL_PREFIX, hex::encode(tx_hash)).as_bytes());
+        }
+        self.db.write(batch)?;
+        Ok(())
+    }
+
+    /// Get every journaled mempool transaction by hash
+    pub fn get_mempool_entries(&self) -> Result<Vec<([u8; 32], Vec<u8>)>> {
+        let mut entries = Vec::new();
+        for (key, value) in self.scan_prefix(MEMPOOL_PREFIX)? {
+            let hash: [u8; 32] = hex::decode(&key[MEMPOOL_PREFIX.len()..])?
+                .try_into()
+                .map_err(|_| anyhow::anyhow!("Invalid mempool journal key"))?;
+            entries.push((hash, value));
+        }
+        Ok(entries)
+    }
+
     /// Get account nonce
     pub fn get_nonce(&self, address: &Address) -> Result<u64> {
         let key = format!("nonce:{}", hex::encode(address));
@@ -62,24 +204,94 @@ impl StateDB {
     }
 
     /// Increment account nonce
+    ///
+    /// Transactions advance nonces through a [`StateOverlay`] instead.
     pub fn increment_nonce(&self, address: &Address) -> Result<u64> {
         let current_nonce = self.get_nonce(address)?;
-        let new_nonce = current_nonce + 1;
+        let new_nonce = current_nonce
+            .checked_add(1)
+            .ok_or_else(|| anyhow::anyhow!("Nonce overflow"))?;
         let key = format!("nonce:{}", hex::encode(address));
         self.db.put(key.as_bytes(), new_nonce.to_le_bytes())?;
         Ok(new_nonce)
     }
 
-    /// Store a block
-    pub fn store_block(&self, block_hash: &[u8; 32], block_data: &[u8]) -> Result<()> {
+    /// Get a block
+    pub fn get_block(&self, block_hash: &[u8; 32]) -> Result<Option<Vec<u8>>> {
         let key = format!("block:{}", hex::encode(block_hash));
-        self.db.put(key.as_bytes(), block_data)?;
+        Ok(self.db.get(key.as_bytes())?)
+    }
+
+    /// Get the hash of the block at the given height
+    pub fn get_block_hash_by_height(&self, height: u64) -> Result<Option<[u8; 32]>> {
+        let key = format!("block_height:{}", height);
+        match self.db.get(key.as_bytes())? {
+            Some(bytes) => {
+                let hash: [u8; 32] = bytes
+                    .try_into()
+                    .map_err(|_| anyhow::anyhow!("Invalid block hash"))?;
+                Ok(Some(hash))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Commit a block: store it, index it by height and make it the latest block
+    ///
+    /// `receipts` holds the hash and encoded receipt of each transaction in
+    /// block order; every transaction is indexed by its position in the block.
+    pub fn commit_block(
+        &self,
+        block_hash: &[u8; 32],
+        height: u64,
+        block_data: &[u8],
+        receipts: &[([u8; 32], Vec<u8>)],
+    ) -> Result<()> {
+        let mut batch = WriteBatch::default();
+        write_block(&mut batch, block_hash, height, block_data, receipts);
+        self.db.write(batch)?;
         Ok(())
     }
 
-    /// Get a block
-    pub fn get_block(&self, block_hash: &[u8; 32]) -> Result<Option<Vec<u8>>> {
-        let key = format!("block:{}", hex::encode(block_hash));
+    /// Commit a block together with the state changes of its transactions
+    ///
+    /// Everything is written in one batch, so a crash never leaves balances
+    /// and nonces changed without the block, receipts and indexes recording
+    /// why.
+    pub fn commit_block_with_overlay(
+        &self,
+        overlay: &StateOverlay,
+        block_hash: &[u8; 32],
+        height: u64,
+        block_data: &[u8],
+        receipts: &[([u8; 32], Vec<u8>)],
+    ) -> Result<()> {
+        let mut batch = WriteBatch::default();
+        self.write_overlay(&mut batch, overlay)?;
+        write_block(&mut batch, block_hash, height, block_data, receipts);
+        self.db.write(batch)?;
+        Ok(())
+    }
+
+    /// Get the block height and position of a committed transaction
+    pub fn get_transaction_location(&self, tx_hash: &[u8; 32]) -> Result<Option<(u64, u64)>> {
+        let key = format!("tx:{}", hex::encode(tx_hash));
+        match self.db.get(key.as_bytes())? {
+            Some(bytes) => {
+                if bytes.len() != 16 {
+                    anyhow::bail!("Invalid transaction location bytes");
+                }
+                let height = u64::from_le_bytes(bytes[..8].try_into()?);
+                let index = u64::from_le_bytes(bytes[8..].try_into()?);
+                Ok(Some((height, index)))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Get the receipt of a committed transaction
+    pub fn get_receipt(&self, tx_hash: &[u8; 32]) -> Result<Option<Vec<u8>>> {
+        let key = format!("receipt:{}", hex::encode(tx_hash));
         Ok(self.db.get(key.as_bytes())?)
     }
 
@@ -96,22 +308,222 @@ impl StateDB {
         }
     }
 
-    /// Set the latest block hash
-    pub fn set_latest_block_hash(&self, block_hash: &[u8; 32]) -> Result<()> {
-        self.db.put(b"latest_block", block_hash)?;
+    /// Start a copy-on-write view of the current state
+    pub fn overlay(&self) -> StateOverlay<'_> {
+        StateOverlay {
+            base: self,
+            balances: BTreeMap::new(),
+            nonces: BTreeMap::new(),
+            burned: 0,
+        }
+    }
+
+    /// Persist the writes of an overlay in one atomic batch
+    pub fn apply_overlay(&self, overlay: &StateOverlay) -> Result<()> {
+        let mut batch = WriteBatch::default();
+        self.write_overlay(&mut batch, overlay)?;
+        self.db.write(batch)?;
+        Ok(())
+    }
+
+    /// Add the writes of an overlay to `batch`
+    fn write_overlay(&self, batch: &mut WriteBatch, overlay: &StateOverlay) -> Result<()> {
+        for (address, balance) in &overlay.balances {
+            batch.put(
+                format!("balance:{}", hex::encode(address)).as_bytes(),
+                balance.to_le_bytes(),
+            );
+        }
+        for (address, nonce) in &overlay.nonces {
+            batch.put(
+                format!("nonce:{}", hex::encode(address)).as_bytes(),
+                nonce.to_le_bytes(),
+            );
+        }
+        if overlay.burned > 0 {
+            let burned = self
+                .get_counter(SUPPLY_BURNED_KEY)?
+                .checked_add(overlay.burned)
+                .ok_or_else(|| anyhow::anyhow!("Burned supply overflow"))?;
+            batch.put(SUPPLY_BURNED_KEY, burned.to_le_bytes());
+        }
+        Ok(())
+    }
+
+    /// Read the RocksDB properties exported as metrics
+    pub fn stats(&self) -> Result<Vec<(String, u64)>> {
+        let mut stats = Vec::with_capacity(DB_STAT_PROPERTIES.len());
+        for name in DB_STAT_PROPERTIES {
+            if let Some(value) = self.db.property_int_value(name)? {
+                stats.push((name.to_string(), value));
+            }
+        }
+        Ok(stats)
+    }
+
+    /// Write a consistent copy of the database to `path`, which must not exist yet
+    pub fn create_checkpoint<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        Checkpoint::new(&self.db)?.create_checkpoint(path)?;
         Ok(())
     }
 
-    /// Initialize genesis state
+    /// Commit the genesis block together with its allocations
+    ///
+    /// The initial balances are recorded as minted and the schedules of
+    /// locked allocations are stored. Everything is written in one batch, so
+    /// a crash never leaves the genesis block indexed without its state.
     pub fn initialize_genesis(
         &self,
+        block_hash: &[u8; 32],
+        block_data: &[u8],
         initial_balances: &std::collections::HashMap<Address, u64>,
+        schedules: &[(Address, VestingSchedule)],
     ) -> Result<()> {
+        let mut batch = WriteBatch::default();
+        let mut minted = 0u64;
         for (address, balance) in initial_balances {
-            self.set_balance(address, *balance)?;
+            minted = minted
+                .checked_add(*balance)
+                .ok_or_else(|| anyhow::anyhow!("Genesis supply overflow"))?;
+            batch.put(
+                format!("balance:{}", hex::encode(address)).as_bytes(),
+                balance.to_le_bytes(),
+            );
+        }
+        batch.put(SUPPLY_MINTED_KEY, minted.to_le_bytes());
+        for (address, schedule) in schedules {
+            let key = format!("{}{}", VESTING_PREFIX, hex::encode(address));
+            batch.put(key.as_bytes(), serde_json::to_vec(schedule)?);
         }
+        write_block(&mut batch, block_hash, 0, block_data, &[]);
+        self.db.write(batch)?;
         Ok(())
     }
+
+    /// Get the vesting schedule of an account, if part of its balance is locked
+    pub fn get_vesting(&self, address: &Address) -> Result<Option<VestingSchedule>> {
+        let key = format!("{}{}", VESTING_PREFIX, hex::encode(address));
+        match self.db.get(key.as_bytes())? {
+            Some(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Add a block, its height and transaction indexes and its receipts to
+/// `batch`, making it the latest block
+fn write_block(
+    batch: &mut WriteBatch,
+    block_hash: &[u8; 32],
+    height: u64,
+    block_data: &[u8],
+    receipts: &[([u8; 32], Vec<u8>)],
+) {
+    batch.put(
+        format!("block:{}", hex::encode(block_hash)).as_bytes(),
+        block_data,
+    );
+    batch.put(format!("block_height:{}", height).as_bytes(), block_hash);
+    for (index, (tx_hash, receipt_data)) in receipts.iter().enumerate() {
+        let mut location = Vec::with_capacity(16);
+        location.extend_from_slice(&height.to_le_bytes());
+        location.extend_from_slice(&(index as u64).to_le_bytes());
+        batch.put(format!("tx:{}", hex::encode(tx_hash)).as_bytes(), location);
+        batch.put(
+            format!("receipt:{}", hex::encode(tx_hash)).as_bytes(),
+            receipt_data,
+        );
+    }
+    batch.put(b"latest_block", block_hash);
+}
+
+/// Change of one account between the database and an overlay
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccountDiff {
+    pub address: Address,
+    pub balance_before: u64,
+    pub balance_after: u64,
+    pub nonce_before: u64,
+    pub nonce_after: u64,
+}
+
+/// Copy-on-write view of the state
+///
+/// Reads fall through to the database until a value is written; writes stay
+/// in memory until the overlay is passed to [`StateDB::apply_overlay`] or
+/// [`StateDB::commit_block_with_overlay`].
+pub struct StateOverlay<'a> {
+    base: &'a StateDB,
+    balances: BTreeMap<Address, u64>,
+    nonces: BTreeMap<Address, u64>,
+    /// Fees burned by the overlay's writes
+    burned: u64,
+}
+
+impl StateOverlay<'_> {
+    /// Get account balance
+    pub fn get_balance(&self, address: &Address) -> Result<u64> {
+        match self.balances.get(address) {
+            Some(balance) => Ok(*balance),
+            None => self.base.get_balance(address),
+        }
+    }
+
+    /// Set account balance
+    pub fn set_balance(&mut self, address: &Address, balance: u64) {
+        self.balances.insert(*address, balance);
+    }
+
+    /// Get account nonce
+    pub fn get_nonce(&self, address: &Address) -> Result<u64> {
+        match self.nonces.get(address) {
+            Some(nonce) => Ok(*nonce),
+            None => self.base.get_nonce(address),
+        }
+    }
+
+    /// Increment account nonce
+    pub fn increment_nonce(&mut self, address: &Address) -> Result<u64> {
+        let new_nonce = self
+            .get_nonce(address)?
+            .checked_add(1)
+            .ok_or_else(|| anyhow::anyhow!("Nonce overflow"))?;
+        self.nonces.insert(*address, new_nonce);
+        Ok(new_nonce)
+    }
+
+    /// Record `amount` as burned; the caller removes it from a balance
+    pub fn burn(&mut self, amount: u64) -> Result<()> {
+        self.burned = self
+            .burned
+            .checked_add(amount)
+            .ok_or_else(|| anyhow::anyhow!("Burned supply overflow"))?;
+        Ok(())
+    }
+
+    /// Accounts whose balance or nonce differ from the database
+    pub fn diff(&self) -> Result<Vec<AccountDiff>> {
+        let mut addresses: Vec<&Address> = self.balances.keys().chain(self.nonces.keys()).collect();
+        addresses.sort();
+        addresses.dedup();
+
+        let mut diff = Vec::new();
+        for address in addresses {
+            let account = AccountDiff {
+                address: *address,
+                balance_before: self.base.get_balance(address)?,
+                balance_after: self.get_balance(address)?,
+                nonce_before: self.base.get_nonce(address)?,
+                nonce_after: self.get_nonce(address)?,
+            };
+            if account.balance_before != account.balance_after
+                || account.nonce_before != account.nonce_after
+            {
+                diff.push(account);
+            }
+        }
+        Ok(diff)
+    }
 }
 
 #[cfg(test)]
@@ -138,4 +550,182 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_block_height_index() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let db = StateDB::open(temp_dir.path())?;
+
+        assert_eq!(db.get_block_hash_by_height(0)?, None);
+
+        db.commit_block(&[1u8; 32], 0, b"genesis", &[])?;
+        db.commit_block(&[2u8; 32], 1, b"block one", &[])?;
+
+        assert_eq!(db.get_block_hash_by_height(0)?, Some([1u8; 32]));
+        assert_eq!(db.get_block_hash_by_height(1)?, Some([2u8; 32]));
+        assert_eq!(db.get_latest_block_hash()?, Some([2u8; 32]));
+        assert_eq!(db.get_block(&[2u8; 32])?, Some(b"block one".to_vec()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_transaction_index() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let db = StateDB::open(temp_dir.path())?;
+
+        let receipts = vec![
+            ([7u8; 32], b"first".to_vec()),
+            ([8u8; 32], b"second".to_vec()),
+        ];
+        db.commit_block(&[3u8; 32], 4, b"block four", &receipts)?;
+
+        assert_eq!(db.get_transaction_location(&[7u8; 32])?, Some((4, 0)));
+        assert_eq!(db.get_transaction_location(&[8u8; 32])?, Some((4, 1)));
+        assert_eq!(db.get_receipt(&[8u8; 32])?, Some(b"second".to_vec()));
+        assert_eq!(db.get_transaction_location(&[9u8; 32])?, None);
+        assert_eq!(db.get_receipt(&[9u8; 32])?, None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_commit_block_with_overlay() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let db = StateDB::open(temp_dir.path())?;
+        let alice = [1u8; 32];
+        db.set_balance(&alice, 100)?;
+
+        let mut overlay = db.overlay();
+        overlay.set_balance(&alice, 90);
+        overlay.increment_nonce(&alice)?;
+        overlay.burn(10)?;
+        let receipts = vec![([7u8; 32], b"receipt".to_vec())];
+        db.commit_block_with_overlay(&overlay, &[2u8; 32], 1, b"block one", &receipts)?;
+
+        assert_eq!(db.get_balance(&alice)?, 90);
+        assert_eq!(db.get_nonce(&alice)?, 1);
+        assert_eq!(db.get_supply()?.burned, 10);
+        assert_eq!(db.get_block_hash_by_height(1)?, Some([2u8; 32]));
+        assert_eq!(db.get_latest_block_hash()?, Some([2u8; 32]));
+        assert_eq!(db.get_transaction_location(&[7u8; 32])?, Some((1, 0)));
+        assert_eq!(db.get_receipt(&[7u8; 32])?, Some(b"receipt".to_vec()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_state_overlay() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let db = StateDB::open(temp_dir.path())?;
+        let (alice, bob) = ([1u8; 32], [2u8; 32]);
+        db.set_balance(&alice, 100)?;
+
+        let mut overlay = db.overlay();
+        overlay.set_balance(&alice, 60);
+        overlay.set_balance(&bob, 40);
+        overlay.increment_nonce(&alice)?;
+        overlay.set_balance(&[3u8; 32], 0);
+
+        // Writes are visible through the overlay only
+        assert_eq!(overlay.get_balance(&alice)?, 60);
+        assert_eq!(overlay.get_nonce(&alice)?, 1);
+        assert_eq!(db.get_balance(&alice)?, 100);
+        assert_eq!(db.get_balance(&bob)?, 0);
+
+        // Unchanged accounts are left out of the diff
+        let diff = overlay.diff()?;
+        assert_eq!(diff.len(), 2);
+        assert_eq!(
+            diff[0],
+            AccountDiff {
+                address: alice,
+                balance_before: 100,
+                balance_after: 60,
+                nonce_before: 0,
+                nonce_after: 1,
+            }
+        );
+        assert_eq!(diff[1].balance_after, 40);
+
+        db.apply_overlay(&overlay)?;
+        assert_eq!(db.get_balance(&alice)?, 60);
+        assert_eq!(db.get_balance(&bob)?, 40);
+        assert_eq!(db.get_nonce(&alice)?, 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_supply_tracking() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let db = StateDB::open(temp_dir.path())?;
+        let (alice, bob) = ([1u8; 32], [2u8; 32]);
+        let balances = std::collections::HashMap::from([(alice, 700), (bob, 300)]);
+        db.initialize_genesis(&[0u8; 32], b"{}", &balances, &[])?;
+        assert_eq!(db.get_block_hash_by_height(0)?, Some([0u8; 32]));
+        // Unrelated keys sorted after the balances are not counted
+        db.increment_nonce(&alice)?;
+
+        assert_eq!(
+            db.get_supply()?,
+            Supply {
+                minted: 1_000,
+                burned: 0
+            }
+        );
+        let audit = db.audit_supply()?;
+        assert_eq!((audit.balances, audit.accounts), (1_000, 2));
+        assert!(audit.is_consistent());
+
+        // Burned fees are only recorded once the overlay is applied
+        let mut overlay = db.overlay();
+        overlay.set_balance(&alice, 690);
+        overlay.burn(10)?;
+        assert_eq!(db.get_supply()?.burned, 0);
+        db.apply_overlay(&overlay)?;
+        assert_eq!(
+            db.get_supply()?,
+            Supply {
+                minted: 1_000,
+                burned: 10
+            }
+        );
+        assert!(db.audit_supply()?.is_consistent());
+
+        // Funds appearing outside a state transition are detected
+        db.set_balance(&[3u8; 32], 5)?;
+        let audit = db.audit_supply()?;
+        assert_eq!(audit.balances, 995);
+        assert!(!audit.is_consistent());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_mempool_journal() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let db = StateDB::open(temp_dir.path())?;
+        db.set_balance(&[1u8; 32], 100)?;
+
+        db.put_mempool_entry(&[7u8; 32], b"first")?;
+        db.put_mempool_entry(&[8u8; 32], b"second")?;
+        assert_eq!(
+            db.get_mempool_entries()?,
+            vec![
+                ([7u8; 32], b"first".to_vec()),
+                ([8u8; 32], b"second".to_vec())
+            ]
+        );
+
+        db.delete_mempool_entries(&[[7u8; 32]])?;
+        assert_eq!(
+            db.get_mempool_entries()?,
+            vec![([8u8; 32], b"second".to_vec())]
+        );
+        // Other keys are untouched
+        assert_eq!(db.get_balance(&[1u8; 32])?, 100);
+
+        Ok(())
+    }
 }
@@ -0,0 +1,92 @@
+//! Vesting schedules of time-locked genesis allocations
+//!
+//! Part of an account's genesis allocation can be locked and released over
+//! time: nothing unlocks before the cliff, then the locked amount unlocks
+//! linearly until the end of the schedule. Funds the account receives later
+//! are never locked. Unlocking is measured against block timestamps, so
+//! every node agrees on what an account may spend in a given block.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+/// Unlock schedule of a locked allocation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct VestingSchedule {
+    /// Amount locked when vesting starts
+    pub amount: u64,
+    /// Unix time in seconds at which vesting starts
+    pub start: u64,
+    /// Seconds after `start` before anything unlocks
+    pub cliff_secs: u64,
+    /// Seconds after `start` after which everything is unlocked
+    pub duration_secs: u64,
+}
+
+impl VestingSchedule {
+    /// Reject schedules whose cliff falls after their end
+    pub fn validate(&self) -> Result<()> {
+        if self.cliff_secs > self.duration_secs {
+            anyhow::bail!(
+                "Vesting cliff of {}s is longer than its duration of {}s",
+                self.cliff_secs,
+                self.duration_secs
+            );
+        }
+        Ok(())
+    }
+
+    /// Amount unlocked at Unix time `timestamp`
+    pub fn unlocked_at(&self, timestamp: u64) -> u64 {
+        let elapsed = timestamp.saturating_sub(self.start);
+        if timestamp < self.start || elapsed < self.cliff_secs {
+            0
+        } else if elapsed >= self.duration_secs {
+            self.amount
+        } else {
+            (self.amount as u128 * elapsed as u128 / self.duration_secs as u128) as u64
+        }
+    }
+
+    /// Amount still locked at Unix time `timestamp`
+    pub fn locked_at(&self, timestamp: u64) -> u64 {
+        self.amount - self.unlocked_at(timestamp)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cliff_and_linear_unlock() {
+        let schedule = VestingSchedule {
+            amount: 1_000,
+            start: 100,
+            cliff_secs: 25,
+            duration_secs: 100,
+        };
+        assert!(schedule.validate().is_ok());
+
+        assert_eq!(schedule.locked_at(0), 1_000);
+        assert_eq!(schedule.locked_at(124), 1_000);
+        // The cliff releases everything vested so far at once
+        assert_eq!(schedule.unlocked_at(125), 250);
+        assert_eq!(schedule.unlocked_at(150), 500);
+        assert_eq!(schedule.locked_at(199), 10);
+        assert_eq!(schedule.locked_at(200), 0);
+        assert_eq!(schedule.locked_at(u64::MAX), 0);
+
+        let immediate = VestingSchedule {
+            duration_secs: 0,
+            cliff_secs: 0,
+            ..schedule
+        };
+        assert_eq!(immediate.locked_at(100), 0);
+        let invalid = VestingSchedule {
+            cliff_secs: 101,
+            ..schedule
+        };
+        assert!(invalid.validate().is_err());
+    }
+}
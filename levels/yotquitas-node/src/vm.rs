@@ -1,64 +1,132 @@
+use crate::gas::{GasMeter, GasSchedule, OutOfGas, MAX_GAS_LIMIT};
 use yotquitas_core::{Transaction, TransactionPayload};
 use anyhow::Result;
 
+/// Stand-in instruction count of a Move call until real bytecode is interpreted
+const MOVE_CALL_INSTRUCTIONS: u64 = 1_000;
+
+/// Bytes of module bytecode stored per state write
+const BYTECODE_WORD: u64 = 32;
+
 /// Virtual Machine for executing transactions
 ///
 /// Note: For MVP, this is a simplified VM. Full MoveVM integration
 /// would require integrating with Aptos or Sui Move VM libraries.
-pub struct VM;
+pub struct VM {
+    schedule: GasSchedule,
+}
 
 impl VM {
     /// Create a new VM instance
     pub fn new() -> Self {
-        Self
+        Self {
+            schedule: GasSchedule::default(),
+        }
+    }
+
+    /// Gas schedule execution is metered with
+    pub fn schedule(&self) -> &GasSchedule {
+        &self.schedule
     }
 
     /// Execute a transaction and return the execution result
+    ///
+    /// Execution is metered against `tx.gas_limit`, starting with the
+    /// intrinsic gas. Running out of gas aborts execution with a failed
+    /// result that used the whole limit.
     pub fn execute_transaction(&self, tx: &Transaction) -> Result<ExecutionResult> {
+        let mut meter = GasMeter::new(self.schedule, tx.gas_limit);
+        let outcome = meter
+            .charge(self.schedule.intrinsic_gas(tx))
+            .and_then(|()| self.run(tx, &mut meter));
+
+        Ok(match outcome {
+            Ok(return_data) => ExecutionResult {
+                success: true,
+                gas_used: meter.used(),
+                return_data,
+                error: None,
+            },
+            Err(OutOfGas) => ExecutionResult {
+                success: false,
+                gas_used: meter.used(),
+                return_data: vec![],
+                error: Some(OutOfGas.to_string()),
+            },
+        })
+    }
+
+    /// Charge the execution of `tx` to `meter`
+    fn run(&self, tx: &Transaction, meter: &mut GasMeter) -> Result<Vec<u8>, OutOfGas> {
         match &tx.payload {
             TransactionPayload::Transfer { to: _, amount: _ } => {
                 // Simple transfer logic - in production, this would use MoveVM
-                Ok(ExecutionResult {
-                    success: true,
-                    gas_used: 21000, // Standard transfer gas
-                    return_data: vec![],
-                })
+                // Reads and writes the sender and recipient balances
+                meter.charge_reads(2)?;
+                meter.charge_writes(2)?;
+                Ok(vec![])
             }
             TransactionPayload::MoveCall {
                 module,
                 function,
-                args: _,
+                args,
             } => {
                 // Move call execution - simplified for MVP
                 tracing::info!("Executing Move call: {}::{}", module, function);
-                Ok(ExecutionResult {
-                    success: true,
-                    gas_used: 50000, // Estimated gas for Move call
-                    return_data: vec![],
-                })
+                meter.charge_reads(1)?;
+                meter.charge_instructions(
+                    MOVE_CALL_INSTRUCTIONS.saturating_add(args.len() as u64),
+                )?;
+                Ok(vec![])
             }
-            TransactionPayload::DeployModule { bytecode: _ } => {
+            TransactionPayload::DeployModule { bytecode } => {
                 // Module deployment - simplified for MVP
                 tracing::info!("Deploying Move module");
-                Ok(ExecutionResult {
-                    success: true,
-                    gas_used: 100000, // Estimated gas for deployment
-                    return_data: vec![],
-                })
+                let len = bytecode.len() as u64;
+                meter.charge_instructions(len)?;
+                meter.charge_writes(len.div_ceil(BYTECODE_WORD))?;
+                Ok(vec![])
             }
         }
     }
 
-    /// Validate a transaction without executing it
-    pub fn validate_transaction(&self, tx: &Transaction) -> Result<()> {
+    /// Validate a transaction for a block with the given base fee, without executing it
+    pub fn validate_transaction(&self, tx: &Transaction, base_fee_per_gas: u64) -> Result<()> {
         // Verify signature
         if !tx.verify() {
             anyhow::bail!("Invalid transaction signature");
         }
 
+        self.validate_unsigned(tx, base_fee_per_gas)
+    }
+
+    /// Validate everything but the signature, e.g. to simulate before signing
+    pub fn validate_unsigned(&self, tx: &Transaction, base_fee_per_gas: u64) -> Result<()> {
         // Basic validation
-        if tx.fee == 0 {
-            anyhow::bail!("Transaction fee cannot be zero");
+        if tx.max_fee_per_gas < base_fee_per_gas {
+            anyhow::bail!(
+                "Max fee per gas {} is below the base fee of {}",
+                tx.max_fee_per_gas,
+                base_fee_per_gas
+            );
+        }
+        if tx.max_priority_fee_per_gas > tx.max_fee_per_gas {
+            anyhow::bail!("Max priority fee per gas exceeds the max fee per gas");
+        }
+        if tx.gas_limit > MAX_GAS_LIMIT {
+            anyhow::bail!(
+                "Gas limit {} exceeds the maximum of {}",
+                tx.gas_limit,
+                MAX_GAS_LIMIT
+            );
+        }
+        let intrinsic_gas = self.schedule.intrinsic_gas(tx);
+        if tx.gas_limit < intrinsic_gas {
+            anyhow::bail!(
+                "Gas limit {} is below the intrinsic gas of {}",
+                tx.gas_limit,
+                intrinsic_gas
+            );
         }
 
         Ok(())
@@ -66,10 +134,19 @@ impl VM {
 }
 
 /// Result of transaction execution
+#[derive(Debug, Clone)]
 pub struct ExecutionResult {
     pub success: bool,
     pub gas_used: u64,
     pub return_data: Vec<u8>,
+    pub error: Option<String>,
+}
+
+impl ExecutionResult {
+    /// Fee charged for the gas used, `gas_used * gas_price`
+    pub fn fee(&self, gas_price: u64) -> u64 {
+        self.gas_used.saturating_mul(gas_price)
+    }
 }
 
 impl Default for VM {
@@ -81,27 +158,57 @@ impl Default for VM {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use yotquitas_core::{generate_keypair, PublicKey};
+    use yotquitas_core::generate_keypair;
 
-    #[test]
-    fn test_vm_execute_transfer() -> Result<()> {
-        let vm = VM::new();
-        let (signing_key, pubkey) = generate_keypair();
+    fn transfer(gas_limit: u64) -> Transaction {
+        transfer_with_fees(gas_limit, 2, 1)
+    }
 
-        let tx = yotquitas_core::Transaction::new(
+    fn transfer_with_fees(
+        gas_limit: u64,
+        max_fee_per_gas: u64,
+        max_priority_fee_per_gas: u64,
+    ) -> Transaction {
+        let (signing_key, pubkey) = generate_keypair();
+        yotquitas_core::Transaction::new(
             pubkey,
             TransactionPayload::Transfer {
                 to: [0u8; 32],
                 amount: 100,
             },
-            1,
+            gas_limit,
+            max_fee_per_gas,
+            max_priority_fee_per_gas,
             0,
         )
-        .sign(&signing_key);
+        .sign(&signing_key)
+    }
+
+    #[test]
+    fn test_vm_execute_transfer() -> Result<()> {
+        let vm = VM::new();
+        let tx = transfer(MAX_GAS_LIMIT);
 
         let result = vm.execute_transaction(&tx)?;
+        let schedule = vm.schedule();
         assert!(result.success);
-        assert_eq!(result.gas_used, 21000);
+        assert_eq!(
+            result.gas_used,
+            schedule.intrinsic_gas(&tx) + 2 * schedule.state_read + 2 * schedule.state_write
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_vm_out_of_gas() -> Result<()> {
+        let vm = VM::new();
+        let tx = transfer(vm.schedule().intrinsic_gas(&transfer(0)) + 1);
+
+        let result = vm.execute_transaction(&tx)?;
+        assert!(!result.success);
+        assert_eq!(result.gas_used, tx.gas_limit);
+        assert_eq!(result.error.as_deref(), Some("Out of gas"));
 
         Ok(())
     }
@@ -109,20 +216,17 @@ mod tests {
     #[test]
     fn test_vm_validate_transaction() -> Result<()> {
         let vm = VM::new();
-        let (signing_key, pubkey) = generate_keypair();
 
-        let tx = yotquitas_core::Transaction::new(
-            pubkey,
-            TransactionPayload::Transfer {
-                to: [0u8; 32],
-                amount: 100,
-            },
-            1,
-            0,
-        )
-        .sign(&signing_key);
+        assert!(vm.validate_transaction(&transfer(25_000), 2).is_ok());
+        assert!(vm.validate_transaction(&transfer(1_000), 2).is_err());
+        assert!(vm
+            .validate_transaction(&transfer(MAX_GAS_LIMIT + 1), 2)
+            .is_err());
 
-        assert!(vm.validate_transaction(&tx).is_ok());
+        // The max fee must cover the base fee and the tip must not exceed it
+        assert!(vm.validate_transaction(&transfer(25_000), 3).is_err());
+        let tx = transfer_with_fees(25_000, 2, 3);
+        assert!(vm.validate_transaction(&tx, 1).is_err());
 
         Ok(())
     }
@@ -0,0 +1,205 @@
+use anyhow::Result;
+use clap::{Args, Parser, Subcommand};
+use std::path::PathBuf;
+use yotquitas_core::{generate_keypair, parse_hash, Address, TransactionPayload};
+
+mod send;
+mod signer;
+
+use send::SendArgs;
+use signer::KeystoreArgs;
+use yotquitas_client::Client;
+
+/// Yotquitas Wallet - manage keys and send transactions to a Yotquitas node
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Cli {
+    /// JSON-RPC endpoint of the node
+    #[arg(
+        long,
+        global = true,
+        env = "YOTQUITAS_WALLET_RPC_URL",
+        default_value = "http://127.0.0.1:8545"
+    )]
+    rpc_url: String,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Create a new account in a keystore file
+    New(KeystoreArgs),
+    /// Import a hex-encoded secret key into a keystore file
+    Import {
+        #[command(flatten)]
+        keystore: KeystoreArgs,
+
+        /// Read the secret key from this file instead of prompting
+        #[arg(long)]
+        secret_key_file: Option<PathBuf>,
+    },
+    /// Print the address of a keystore
+    Address(KeystoreArgs),
+    /// Print the balance of an account
+    Balance(AccountArgs),
+    /// Print the next nonce of an account, counting pooled transactions
+    Nonce(AccountArgs),
+    /// Transfer AEQ to an address
+    Transfer {
+        /// Recipient address
+        #[arg(long)]
+        to: String,
+
+        /// Amount in the smallest unit
+        #[arg(long)]
+        amount: u64,
+
+        #[command(flatten)]
+        keystore: KeystoreArgs,
+
+        #[command(flatten)]
+        send: SendArgs,
+    },
+    /// Call a function of a deployed Move module
+    Call {
+        #[arg(long)]
+        module: String,
+
+        #[arg(long)]
+        function: String,
+
+        /// Hex-encoded call arguments
+        #[arg(long, default_value = "")]
+        args: String,
+
+        #[command(flatten)]
+        keystore: KeystoreArgs,
+
+        #[command(flatten)]
+        send: SendArgs,
+    },
+    /// Deploy a Move module
+    Deploy {
+        /// File holding the compiled module
+        #[arg(long)]
+        bytecode: PathBuf,
+
+        #[command(flatten)]
+        keystore: KeystoreArgs,
+
+        #[command(flatten)]
+        send: SendArgs,
+    },
+}
+
+/// Account to query, given by address or by keystore
+#[derive(Args, Debug)]
+struct AccountArgs {
+    /// Address of the account; takes precedence over `--keystore`
+    #[arg(required_unless_present = "keystore")]
+    address: Option<String>,
+
+    /// Keystore file of the account
+    #[arg(long, env = "YOTQUITAS_WALLET_KEYSTORE")]
+    keystore: Option<PathBuf>,
+}
+
+impl AccountArgs {
+    fn address(&self) -> Result<Address> {
+        match (&self.address, &self.keystore) {
+            (Some(address), _) => {
+                parse_hash(address).map_err(|e| anyhow::anyhow!("Invalid address: {}", e))
+            }
+            (None, Some(keystore)) => KeystoreArgs {
+                keystore: keystore.clone(),
+                password_file: None,
+            }
+            .address(),
+            (None, None) => anyhow::bail!("Pass an address or --keystore"),
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let client = Client::new(&cli.rpc_url);
+
+    match cli.command {
+        Command::New(keystore) => {
+            let (signing_key, _) = generate_keypair();
+            let address = keystore.create(&signing_key)?;
+            println!("Address: 0x{}", hex::encode(address));
+            println!("Keystore: {}", keystore.keystore.display());
+        }
+        Command::Import {
+            keystore,
+            secret_key_file,
+        } => {
+            let secret_hex = match secret_key_file {
+                Some(path) => std::fs::read_to_string(&path).map_err(|e| {
+                    anyhow::anyhow!("Failed to read secret key file {:?}: {}", path, e)
+                })?,
+                None => rpassword::prompt_password("Secret key (hex): ")?,
+            };
+            let address = keystore.create(&signer::parse_secret_key(&secret_hex)?)?;
+            println!("Address: 0x{}", hex::encode(address));
+            println!("Keystore: {}", keystore.keystore.display());
+        }
+        Command::Address(keystore) => {
+            println!("0x{}", hex::encode(keystore.address()?));
+        }
+        Command::Balance(account) => {
+            let account = client.account(&account.address()?).await?;
+            println!("Balance: {}", account.balance);
+            println!("Locked: {}", account.locked_balance);
+            println!("Spendable: {}", account.unlocked_balance);
+            println!("Nonce: {}", account.nonce);
+            println!("Block: {}", account.block_number);
+        }
+        Command::Nonce(account) => {
+            println!("{}", client.next_nonce(&account.address()?).await?);
+        }
+        Command::Transfer {
+            to,
+            amount,
+            keystore,
+            send,
+        } => {
+            let payload = TransactionPayload::Transfer {
+                to: parse_hash(&to).map_err(|e| anyhow::anyhow!("Invalid address: {}", e))?,
+                amount,
+            };
+            send::send(&client, &keystore.unlock()?, payload, &send).await?;
+        }
+        Command::Call {
+            module,
+            function,
+            args,
+            keystore,
+            send,
+        } => {
+            let args = hex::decode(args.trim_start_matches("0x"))
+                .map_err(|_| anyhow::anyhow!("Call arguments are not valid hex"))?;
+            let payload = TransactionPayload::MoveCall {
+                module,
+                function,
+                args,
+            };
+            send::send(&client, &keystore.unlock()?, payload, &send).await?;
+        }
+        Command::Deploy {
+            bytecode,
+            keystore,
+            send,
+        } => {
+            let bytecode = std::fs::read(&bytecode)
+                .map_err(|e| anyhow::anyhow!("Failed to read module {:?}: {}", bytecode, e))?;
+            let payload = TransactionPayload::DeployModule { bytecode };
+            send::send(&client, &keystore.unlock()?, payload, &send).await?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,91 @@
+//! Building, signing and submitting transactions
+
+use anyhow::Result;
+use clap::Args;
+use std::time::Duration;
+use yotquitas_client::types::to_hex;
+use yotquitas_client::{Client, ClientError, Signer, TxOptions};
+use yotquitas_core::{SigningKey, TransactionPayload};
+
+/// Options controlling nonce, fees and waiting; estimated by the node when
+/// left out
+#[derive(Args, Debug, Clone)]
+pub struct SendArgs {
+    /// Gas limit of the transaction
+    #[arg(long)]
+    pub gas_limit: Option<u64>,
+
+    /// Most paid per unit of gas, base fee and tip included
+    #[arg(long)]
+    pub max_fee_per_gas: Option<u64>,
+
+    /// Most tipped to the block proposer per unit of gas
+    #[arg(long)]
+    pub max_priority_fee_per_gas: Option<u64>,
+
+    /// Nonce of the transaction
+    #[arg(long)]
+    pub nonce: Option<u64>,
+
+    /// Print the transaction hash without waiting for the receipt
+    #[arg(long)]
+    pub no_wait: bool,
+
+    /// Seconds to wait for the receipt
+    #[arg(long, default_value_t = 60)]
+    pub timeout: u64,
+}
+
+impl SendArgs {
+    fn options(&self) -> TxOptions {
+        TxOptions {
+            nonce: self.nonce,
+            gas_limit: self.gas_limit,
+            max_fee_per_gas: self.max_fee_per_gas,
+            max_priority_fee_per_gas: self.max_priority_fee_per_gas,
+        }
+    }
+}
+
+/// Build a transaction carrying `payload`, sign it, submit it and, unless
+/// told not to, wait for its receipt
+pub async fn send(
+    client: &Client,
+    signing_key: &SigningKey,
+    payload: TransactionPayload,
+    args: &SendArgs,
+) -> Result<()> {
+    let signer = Signer::new(client.clone(), signing_key.clone());
+    let hash = signer.send_with(payload, args.options()).await?;
+    println!("Transaction hash: {}", to_hex(&hash));
+    if args.no_wait {
+        return Ok(());
+    }
+
+    let receipt = match client
+        .wait_for_receipt(&hash, Duration::from_secs(args.timeout))
+        .await
+    {
+        Err(ClientError::ReceiptTimeout { .. }) => anyhow::bail!(
+            "No receipt for {} after {}s; it may still be included later",
+            to_hex(&hash),
+            args.timeout
+        ),
+        receipt => receipt?,
+    };
+    // Printed as the node returns it
+    let raw: serde_json::Value = client
+        .request(
+            "eth_getTransactionReceipt",
+            serde_json::json!([to_hex(&hash)]),
+        )
+        .await?;
+    println!("{}", serde_json::to_string_pretty(&raw)?);
+    if !receipt.success() {
+        anyhow::bail!(
+            "Transaction failed: {}",
+            receipt.error.as_deref().unwrap_or("execution reverted")
+        );
+    }
+    Ok(())
+}
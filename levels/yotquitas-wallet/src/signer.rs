@@ -0,0 +1,112 @@
+//! Keys held in encrypted keystore files
+
+use anyhow::Result;
+use clap::Args;
+use std::path::PathBuf;
+use yotquitas_core::{read_passphrase_file, Address, Keystore, KeystoreError, SigningKey};
+
+/// Options locating a keystore and its passphrase
+#[derive(Args, Debug, Clone)]
+pub struct KeystoreArgs {
+    /// Keystore file of the account
+    #[arg(long, env = "YOTQUITAS_WALLET_KEYSTORE")]
+    pub keystore: PathBuf,
+
+    /// Read the passphrase from this file instead of prompting
+    #[arg(long, env = "YOTQUITAS_WALLET_PASSWORD_FILE")]
+    pub password_file: Option<PathBuf>,
+}
+
+impl KeystoreArgs {
+    /// Encrypt `signing_key` into a new keystore file
+    pub fn create(&self, signing_key: &SigningKey) -> Result<Address> {
+        if self.keystore.exists() {
+            anyhow::bail!("Keystore {:?} already exists", self.keystore);
+        }
+        let passphrase = match &self.password_file {
+            Some(path) => read_passphrase_file(path)?,
+            None => {
+                let passphrase = rpassword::prompt_password("New passphrase: ")?;
+                if rpassword::prompt_password("Repeat passphrase: ")? != passphrase {
+                    anyhow::bail!("Passphrases do not match");
+                }
+                passphrase
+            }
+        };
+        let keystore = Keystore::create(&self.keystore, signing_key, &passphrase)?;
+        Ok(keystore.address()?)
+    }
+
+    /// Address of the stored key, read without the passphrase
+    pub fn address(&self) -> Result<Address> {
+        Ok(self.load()?.address()?)
+    }
+
+    /// Decrypt the stored key
+    pub fn unlock(&self) -> Result<SigningKey> {
+        let keystore = self.load()?;
+        let passphrase = match &self.password_file {
+            Some(path) => read_passphrase_file(path)?,
+            None => {
+                rpassword::prompt_password(format!("Passphrase for {}: ", self.keystore.display()))?
+            }
+        };
+        keystore.decrypt(&passphrase).map_err(|e| match e {
+            KeystoreError::WrongPassphrase => {
+                anyhow::anyhow!("Wrong passphrase for {:?}", self.keystore)
+            }
+            e => e.into(),
+        })
+    }
+
+    fn load(&self) -> Result<Keystore> {
+        Keystore::load(&self.keystore)
+            .map_err(|e| anyhow::anyhow!("Failed to read keystore {:?}: {}", self.keystore, e))
+    }
+}
+
+/// Decode a hex-encoded 32-byte secret key
+pub fn parse_secret_key(secret_hex: &str) -> Result<SigningKey> {
+    let bytes = hex::decode(secret_hex.trim().trim_start_matches("0x"))
+        .map_err(|_| anyhow::anyhow!("Secret key is not valid hex"))?;
+    let bytes: [u8; 32] = bytes
+        .try_into()
+        .map_err(|_| anyhow::anyhow!("Secret key must be 32 bytes"))?;
+    Ok(SigningKey::from_bytes(&bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use yotquitas_core::{generate_keypair, public_key_address};
+
+    #[test]
+    fn test_import_and_unlock() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let password_file = temp_dir.path().join("password");
+        std::fs::write(&password_file, "correct horse\n").unwrap();
+        let args = KeystoreArgs {
+            keystore: temp_dir.path().join("key.json"),
+            password_file: Some(password_file.clone()),
+        };
+
+        let (signing_key, verifying_key) = generate_keypair();
+        let imported = parse_secret_key(&hex::encode(signing_key.to_bytes())).unwrap();
+        let address = args.create(&imported).unwrap();
+        assert_eq!(address, public_key_address(&verifying_key));
+        assert_eq!(args.address().unwrap(), address);
+        assert_eq!(args.unlock().unwrap().to_bytes(), signing_key.to_bytes());
+        // An existing keystore is never replaced
+        assert!(args.create(&imported).is_err());
+
+        std::fs::write(&password_file, "wrong").unwrap();
+        let err = args.unlock().unwrap_err().to_string();
+        assert!(err.contains("Wrong passphrase"), "{}", err);
+    }
+
+    #[test]
+    fn test_parse_secret_key() {
+        assert!(parse_secret_key("0x1234").is_err());
+        assert!(parse_secret_key("0xzz").is_err());
+    }
+}